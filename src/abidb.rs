@@ -0,0 +1,93 @@
+use crate::symbols;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::Path;
+
+/// A database of the versioned symbols a set of libraries export, built from
+/// glibc's `abilist` files or from installed libraries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AbiDb {
+    /// library => symbol => the versions it is available at
+    pub libraries: BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+}
+
+impl AbiDb {
+    /// Reads a database written by `gen-abidb`.
+    pub fn read(path: &Path) -> Result<AbiDb, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Adds the input at `path`: a directory of `*.abilist` files, a single
+    /// `abilist` file or an installed shared library.
+    pub fn add_path(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "abilist"))
+                .collect::<Vec<_>>();
+            entries.sort();
+            for entry in entries {
+                self.add_path(&entry)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "abilist") {
+            let library = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            self.add_abilist(&library, &std::fs::read_to_string(path)?);
+        } else {
+            self.add_library(path);
+        }
+        Ok(())
+    }
+
+    /// Adds the symbols of an `abilist` file, whose lines read
+    /// `GLIBC_2.2.5 memcpy F` (or `... D 0x10` for data symbols).
+    pub fn add_abilist(&mut self, library: &str, text: &str) {
+        let symbols = self.libraries.entry(library.to_string()).or_default();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(version), Some(symbol)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            // the version itself is listed with an `A` (absolute) symbol
+            if symbol == version {
+                continue;
+            }
+            symbols
+                .entry(symbol.to_string())
+                .or_default()
+                .insert(version.to_string());
+        }
+    }
+
+    /// Adds the versioned symbols exported by the shared library at `path`,
+    /// keyed by its file name.
+    pub fn add_library(&mut self, path: &Path) {
+        let library = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.add_exports(library, symbols::read_exports(path));
+    }
+
+    /// Adds the versioned symbols of a library under the given name.
+    pub fn add_exports(&mut self, library: String, exports: symbols::Exports) {
+        let symbols = self.libraries.entry(library).or_default();
+        for (symbol, version) in exports.versioned {
+            symbols.entry(symbol).or_default().insert(version);
+        }
+    }
+
+    /// Whether any library in the database exports `symbol` at `version`.
+    pub fn provides(&self, symbol: &str, version: &str) -> bool {
+        self.libraries.values().any(|symbols| {
+            symbols
+                .get(symbol)
+                .is_some_and(|versions| versions.contains(version))
+        })
+    }
+}
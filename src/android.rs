@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+/// The target triples the NDK sysroot has library directories for.
+const NDK_TRIPLES: [&str; 4] = [
+    "aarch64-linux-android",
+    "arm-linux-androideabi",
+    "x86_64-linux-android",
+    "i686-linux-android",
+];
+
+/// The API level that introduced each Bionic `LIBC_*` symbol version.
+/// Symbols at the plain `LIBC` version predate API level 24.
+const LIBC_VERSIONS: &[(&str, u32)] = &[
+    ("N", 24),
+    ("O", 26),
+    ("OMR1", 27),
+    ("P", 28),
+    ("Q", 29),
+    ("R", 30),
+    ("S", 31),
+    ("T", 33),
+    ("U", 34),
+    ("V", 35),
+];
+
+/// Returns the API level that introduced the given `LIBC_*` version, e.g.
+/// 28 for `P`.
+pub fn libc_api_level(version: &str) -> Option<u32> {
+    LIBC_VERSIONS
+        .iter()
+        .find(|(name, _)| *name == version)
+        .map(|(_, api)| *api)
+}
+
+/// Returns the library directories of an NDK sysroot (or an Android system
+/// image) at `root`, the ones specific to `api` first.
+pub fn library_paths(root: &Path, api: u32) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for triple in NDK_TRIPLES {
+        let dir = root.join("usr/lib").join(triple);
+        if dir.is_dir() {
+            paths.push(dir.join(api.to_string()));
+            paths.push(dir);
+        }
+    }
+    for dir in ["system/lib64", "system/lib"] {
+        paths.push(root.join(dir));
+    }
+    paths
+}
@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Magic bytes at the start of a static library (`ar`) archive.
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+/// Whether the given bytes look like an `ar` archive.
+pub fn is_ar_archive(data: &[u8]) -> bool {
+    data.starts_with(AR_MAGIC)
+}
+
+/// Reads every member of an `ar` archive, returning the member names and
+/// their contents in archive order.
+///
+/// The symbol table and the GNU/BSD long-name tables are consumed by the
+/// reader and never returned as members.
+pub fn ar_members(data: &[u8]) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = ar::Archive::new(data);
+    let mut members = Vec::new();
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(entry.header().identifier()).to_string();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        members.push((name, contents));
+    }
+    Ok(members)
+}
+
+/// Whether the file at the given path starts with the `ar` archive magic.
+pub fn is_ar_archive_file(path: &Path) -> bool {
+    let mut magic = [0u8; AR_MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| is_ar_archive(&magic))
+}
+
+/// Joins an archive member name to `dir`, refusing names that would escape
+/// it.
+pub fn safe_join(dir: &Path, name: &str) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir | Component::RootDir => {}
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+    (path != dir).then_some(path)
+}
@@ -0,0 +1,45 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STDOUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Turns colors on for stdout and stderr, always, never, or when they are
+/// terminals and `NO_COLOR` is not set (to anything but the empty string).
+pub fn init(always: Option<bool>) {
+    let (stdout, stderr) = match always {
+        Some(always) => (always, always),
+        None => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            (
+                !no_color && std::io::stdout().is_terminal(),
+                !no_color && std::io::stderr().is_terminal(),
+            )
+        }
+    };
+    STDOUT.store(stdout, Ordering::Relaxed);
+    STDERR.store(stderr, Ordering::Relaxed);
+}
+
+/// A line of stdout about a problem, a gate that failed.
+pub fn red(text: &str) -> String {
+    paint(&STDOUT, "31", text)
+}
+
+/// A label of stdout about something to look into.
+pub fn yellow(text: &str) -> String {
+    paint(&STDOUT, "33", text)
+}
+
+/// A line of stderr about a file that could not be analyzed.
+pub fn warning(text: &str) -> String {
+    paint(&STDERR, "33", text)
+}
+
+fn paint(enabled: &AtomicBool, code: &str, text: &str) -> String {
+    if enabled.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,118 @@
+use elf::abi::{ELFCOMPRESS_ZLIB, ELFCOMPRESS_ZSTD, SHT_SYMTAB};
+use elf::endian::AnyEndian;
+use elf::section::SectionHeader;
+use elf::ElfBytes;
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::Read;
+
+/// Returns the contents of a section, transparently decompressing
+/// `SHF_COMPRESSED` sections (zlib or zstd).
+pub fn section_data<'data>(
+    file: &ElfBytes<'data, AnyEndian>,
+    shdr: &SectionHeader,
+) -> Result<Cow<'data, [u8]>, Box<dyn Error>> {
+    let (data, chdr) = file.section_data(shdr)?;
+    let Some(chdr) = chdr else {
+        return Ok(Cow::Borrowed(data));
+    };
+
+    Ok(Cow::Owned(decompress_section(
+        chdr.ch_type,
+        chdr.ch_size,
+        data,
+    )?))
+}
+
+/// Decompresses the data of an `SHF_COMPRESSED` section, which has to hold
+/// exactly `size` bytes. `size` comes from the file, so it only bounds what
+/// is read, and the buffer grows with what is actually decompressed.
+fn decompress_section(ch_type: u32, size: u64, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    // zlib does not compress better than about 1:1000
+    let capacity = size.min(data.len() as u64 * 1024);
+    let mut decompressed = Vec::with_capacity(usize::try_from(capacity)?);
+    // one byte more than announced tells a longer stream apart
+    let limit = size.saturating_add(1);
+    match ch_type {
+        ELFCOMPRESS_ZLIB => {
+            flate2::read::ZlibDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut decompressed)?;
+        }
+        ELFCOMPRESS_ZSTD => {
+            ruzstd::decoding::StreamingDecoder::new(data)?
+                .take(limit)
+                .read_to_end(&mut decompressed)?;
+        }
+        other => return Err(format!("unsupported section compression type {}", other).into()),
+    }
+    if decompressed.len() as u64 != size {
+        return Err("decompressed section size does not match its header".into());
+    }
+    Ok(decompressed)
+}
+
+/// Returns the raw bytes of `.symtab` and its string table, decompressing
+/// either of them if needed.
+#[allow(clippy::type_complexity)]
+pub fn symbol_table_data<'data>(
+    file: &ElfBytes<'data, AnyEndian>,
+) -> Result<Option<(Cow<'data, [u8]>, Cow<'data, [u8]>)>, Box<dyn Error>> {
+    let Some(shdrs) = file.section_headers() else {
+        return Ok(None);
+    };
+    let Some(symtab) = shdrs.iter().find(|shdr| shdr.sh_type == SHT_SYMTAB) else {
+        return Ok(None);
+    };
+    let strtab = shdrs.get(symtab.sh_link as usize)?;
+    Ok(Some((
+        section_data(file, &symtab)?,
+        section_data(file, &strtab)?,
+    )))
+}
+
+/// Decompresses a gzip, xz or zstd stream, recognized by its magic bytes.
+/// Anything else is returned as is.
+pub fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, Box<dyn Error>> {
+    let mut decompressed = Vec::new();
+    if data.starts_with(b"\x1f\x8b") {
+        flate2::read::MultiGzDecoder::new(data).read_to_end(&mut decompressed)?;
+    } else if data.starts_with(b"\xfd7zXZ\x00") {
+        lzma_rs::xz_decompress(&mut &data[..], &mut decompressed)?;
+    } else if data.starts_with(b"\x28\xb5\x2f\xfd") {
+        ruzstd::decoding::StreamingDecoder::new(data)?.read_to_end(&mut decompressed)?;
+    } else {
+        return Ok(Cow::Borrowed(data));
+    }
+    Ok(Cow::Owned(decompressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decompresses_sections_of_the_announced_size() {
+        let data = zlib(b"symbols");
+        assert_eq!(
+            decompress_section(ELFCOMPRESS_ZLIB, 7, &data).unwrap(),
+            b"symbols"
+        );
+    }
+
+    #[test]
+    fn rejects_sections_of_another_size() {
+        let data = zlib(b"symbols");
+        // a crafted size must not be allocated up front
+        assert!(decompress_section(ELFCOMPRESS_ZLIB, u64::MAX, &data).is_err());
+        assert!(decompress_section(ELFCOMPRESS_ZLIB, 3, &data).is_err());
+    }
+}
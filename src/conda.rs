@@ -0,0 +1,44 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// The glibc version of conda-forge's default sysroot (`sysroot_linux-*`),
+/// what its packages are built against.
+pub const CONDA_FORGE_GLIBC: &str = "2.17";
+
+/// The record of an installed package in `conda-meta/`.
+#[derive(Debug, Deserialize)]
+struct PackageRecord {
+    name: String,
+    version: String,
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+/// Returns the package each file of the conda environment at `prefix` was
+/// installed by, as `<name>-<version>`, from the records in `conda-meta/`.
+pub fn file_owners(prefix: &Path) -> Result<HashMap<PathBuf, String>, Box<dyn Error>> {
+    let meta = prefix.join("conda-meta");
+    if !meta.is_dir() {
+        return Err(format!(
+            "{} is not a conda environment, it has no conda-meta directory",
+            prefix.display()
+        )
+        .into());
+    }
+    let mut owners = HashMap::new();
+    for entry in std::fs::read_dir(&meta)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let record: PackageRecord = serde_json::from_slice(&std::fs::read(&path)?)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let package = format!("{}-{}", record.name, record.version);
+        for file in record.files {
+            owners.insert(prefix.join(file), package.clone());
+        }
+    }
+    Ok(owners)
+}
@@ -0,0 +1,22 @@
+/// The header of the csv and tsv formats.
+pub const HEADER: [&str; 4] = ["version", "function", "file", "referenced_by"];
+
+/// Formats a row of comma (or tab) separated values. For csv, fields with a
+/// comma, a quote or a line break are quoted as RFC 4180 has it, tsv has no
+/// quoting, so tabs and line breaks are replaced with spaces.
+pub fn format_row<S: AsRef<str>>(fields: &[S], separator: char) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if separator == '\t' {
+                field.replace(['\t', '\r', '\n'], " ")
+            } else if field.contains([separator, '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(&separator.to_string())
+}
@@ -0,0 +1,81 @@
+use elf::abi::{PT_NOTE, SHT_NOTE};
+use elf::endian::AnyEndian;
+use elf::note::Note;
+use elf::ElfBytes;
+use std::path::{Path, PathBuf};
+
+/// Returns the GNU build-id of the given ELF file as a lowercase hex string.
+pub fn build_id(file: &ElfBytes<AnyEndian>) -> Option<String> {
+    let from_sections = file.section_headers().and_then(|shdrs| {
+        shdrs
+            .iter()
+            .filter(|shdr| shdr.sh_type == SHT_NOTE)
+            .find_map(|shdr| find_build_id(file.section_data_as_notes(&shdr).ok()?))
+    });
+    let id = from_sections.or_else(|| {
+        file.segments()?
+            .iter()
+            .filter(|phdr| phdr.p_type == PT_NOTE)
+            .find_map(|phdr| find_build_id(file.segment_data_as_notes(&phdr).ok()?))
+    })?;
+    Some(id.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn find_build_id<'data>(mut notes: impl Iterator<Item = Note<'data>>) -> Option<&'data [u8]> {
+    notes.find_map(|note| match note {
+        Note::GnuBuildId(id) => Some(id.0),
+        _ => None,
+    })
+}
+
+/// Returns the file name and CRC32 stored in `.gnu_debuglink`.
+pub fn debuglink(file: &ElfBytes<AnyEndian>) -> Option<(String, u32)> {
+    let shdr = file.section_header_by_name(".gnu_debuglink").ok()??;
+    let (data, _) = file.section_data(&shdr).ok()?;
+    let name_len = data.iter().position(|byte| *byte == 0)?;
+    let name = std::str::from_utf8(&data[..name_len]).ok()?.to_string();
+    // the CRC follows the name, padded to a 4 byte boundary
+    let crc_offset = (name_len + 4) & !3;
+    let crc: [u8; 4] = data.get(crc_offset..crc_offset + 4)?.try_into().ok()?;
+    let crc = match file.ehdr.endianness {
+        AnyEndian::Little => u32::from_le_bytes(crc),
+        AnyEndian::Big => u32::from_be_bytes(crc),
+    };
+    Some((name, crc))
+}
+
+/// Locates the separate debug file of the ELF file at `path`, looking in the
+/// same places as gdb does, relative to `root`:
+///
+/// - `/usr/lib/debug/.build-id/xx/yyyy.debug`, by build-id
+/// - `<dir>/<debuglink>`, `<dir>/.debug/<debuglink>` and
+///   `/usr/lib/debug/<dir>/<debuglink>`, verified against the debuglink CRC
+pub fn find_debug_file(file: &ElfBytes<AnyEndian>, path: &Path, root: &Path) -> Option<PathBuf> {
+    let debug_dir = root.join("usr/lib/debug");
+    if let Some(id) = build_id(file) {
+        if id.len() > 2 {
+            let candidate = debug_dir
+                .join(".build-id")
+                .join(&id[..2])
+                .join(format!("{}.debug", &id[2..]));
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    let (name, crc) = debuglink(file)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    let relative = relative.strip_prefix("/").unwrap_or(relative);
+    [
+        dir.join(&name),
+        dir.join(".debug").join(&name),
+        debug_dir.join(relative).join(&name),
+    ]
+    .into_iter()
+    .find(|candidate| {
+        candidate.as_path() != path
+            && std::fs::read(candidate).is_ok_and(|data| crc32fast::hash(&data) == crc)
+    })
+}
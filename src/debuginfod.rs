@@ -0,0 +1,57 @@
+use std::error::Error;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Returns the debuginfod servers listed in `DEBUGINFOD_URLS`.
+pub fn server_urls() -> Vec<String> {
+    std::env::var("DEBUGINFOD_URLS")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|url| url.trim_end_matches('/').to_string())
+        .collect()
+}
+
+/// Returns the directory downloaded debug files are cached in, following
+/// the same lookup order as elfutils' debuginfod client.
+fn cache_dir() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("DEBUGINFOD_CACHE_PATH") {
+        return Some(PathBuf::from(path));
+    }
+    if let Ok(path) = std::env::var("XDG_CACHE_HOME") {
+        return Some(PathBuf::from(path).join("debuginfod_client"));
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".cache/debuginfod_client"))
+}
+
+/// Fetches the debug file for the given build-id from the first server that
+/// has it, reusing a previously downloaded copy if there is one.
+pub fn fetch_debuginfo(urls: &[String], build_id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let cached = cache_dir().map(|dir| dir.join(build_id).join("debuginfo"));
+    if let Some(data) = cached.as_ref().and_then(|path| std::fs::read(path).ok()) {
+        return Ok(data);
+    }
+
+    let mut last_error: Box<dyn Error> = "no debuginfod server configured".into();
+    for url in urls {
+        let request = format!("{}/buildid/{}/debuginfo", url, build_id);
+        match ureq::get(&request).call() {
+            Ok(response) => {
+                let mut data = Vec::new();
+                response.into_reader().read_to_end(&mut data)?;
+                if let Some(path) = cached.as_ref() {
+                    // caching is best effort, a failure here only costs a
+                    // download next time
+                    if let Some(parent) = path.parent() {
+                        let _ = std::fs::create_dir_all(parent);
+                    }
+                    let _ = std::fs::write(path, &data);
+                }
+                return Ok(data);
+            }
+            Err(error) => last_error = error.into(),
+        }
+    }
+    Err(last_error)
+}
@@ -0,0 +1,64 @@
+/// glibc interfaces that are deprecated, or only kept as compat symbols so
+/// that old binaries keep working. Entries ending in `*` match a prefix.
+#[rustfmt::skip]
+const DEPRECATED: &[(&str, &str)] = &[
+    ("gets", "removed from the API in glibc 2.16"),
+    ("sys_errlist", "compat symbol only since glibc 2.32, use strerror"),
+    ("_sys_errlist", "compat symbol only since glibc 2.32, use strerror"),
+    ("sys_nerr", "compat symbol only since glibc 2.32, use strerror"),
+    ("_sys_nerr", "compat symbol only since glibc 2.32, use strerror"),
+    ("sys_siglist", "compat symbol only since glibc 2.32, use strsignal"),
+    ("_sys_siglist", "compat symbol only since glibc 2.32, use strsignal"),
+    ("sys_sigabbrev", "compat symbol only since glibc 2.32, use sigabbrev_np"),
+    ("xdr_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("clnt_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("clntudp_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("clnttcp_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("svc_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("svcudp_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("svctcp_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("pmap_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("authunix_*", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("authnone_create", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("callrpc", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("registerrpc", "Sun RPC was removed in glibc 2.32, use libtirpc"),
+    ("yp_*", "NIS was removed in glibc 2.28, use libnsl2"),
+    ("nis_*", "NIS+ was removed in glibc 2.28, use libnsl2"),
+    ("setkey", "compat symbol only since glibc 2.28"),
+    ("encrypt", "compat symbol only since glibc 2.28"),
+    ("encrypt_r", "compat symbol only since glibc 2.28"),
+    ("ustat", "compat symbol only since glibc 2.28, use statfs"),
+    ("stime", "compat symbol only since glibc 2.31, use clock_settime"),
+    ("ftime", "compat symbol only since glibc 2.33, use clock_gettime"),
+    ("sigvec", "compat symbol only since glibc 2.21, use sigaction"),
+    ("matherr", "compat symbol only since glibc 2.27"),
+    ("_LIB_VERSION", "compat symbol only since glibc 2.27"),
+    ("__malloc_hook", "removed in glibc 2.34"),
+    ("__free_hook", "removed in glibc 2.34"),
+    ("__realloc_hook", "removed in glibc 2.34"),
+    ("__memalign_hook", "removed in glibc 2.34"),
+    ("__after_morecore_hook", "removed in glibc 2.34"),
+    ("__morecore", "removed in glibc 2.34"),
+    ("__malloc_initialize_hook", "removed in glibc 2.24"),
+    ("malloc_get_state", "removed in glibc 2.25"),
+    ("malloc_set_state", "removed in glibc 2.25"),
+    ("mallinfo", "deprecated since glibc 2.33, use mallinfo2"),
+    ("pthread_yield", "deprecated since glibc 2.34, use sched_yield"),
+    ("__ctype_b", "compat symbol only since glibc 2.3, use __ctype_b_loc"),
+    ("__ctype_tolower", "compat symbol only since glibc 2.3, use __ctype_tolower_loc"),
+    ("__ctype_toupper", "compat symbol only since glibc 2.3, use __ctype_toupper_loc"),
+    ("inet6_option_*", "deprecated RFC 2292 API, use inet6_opt_*"),
+    ("getwd", "deprecated, use getcwd"),
+];
+
+/// Returns why the given glibc interface should no longer be used, if it is
+/// a deprecated or compat-only one.
+pub fn deprecation(name: &str) -> Option<&'static str> {
+    DEPRECATED
+        .iter()
+        .find(|(pattern, _)| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => *pattern == name,
+        })
+        .map(|(_, note)| *note)
+}
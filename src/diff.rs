@@ -0,0 +1,325 @@
+use crate::{distro, schema};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+/// What `lddcheck diff` does with an analysis instead of reporting it.
+#[derive(Debug, Clone)]
+pub enum Stage {
+    /// Send the `--envelope` JSON document of the old files.
+    Old(Sender<Value>),
+    /// Compare the new files to the `--envelope` JSON document of the old
+    /// ones.
+    New(Value),
+    /// Compare the files to a report saved earlier, failing on regressions.
+    Baseline(Value),
+}
+
+/// What changed from one report to the other.
+#[derive(Debug, Default, Serialize)]
+pub struct Changes {
+    pub added: Vec<Change>,
+    pub removed: Vec<Change>,
+    /// The sonames of the libraries loaded from the system only by the new
+    /// inputs.
+    pub new_libraries: Vec<String>,
+    /// The sonames of the libraries only the old inputs loaded.
+    pub dropped_libraries: Vec<String>,
+    /// The namespaces whose highest version required changed.
+    pub floors: Vec<Floor>,
+}
+
+impl Changes {
+    /// Whether the new report requires more than the old one: a newer
+    /// version than the old one required from a namespace, or a library of
+    /// the system it did not load.
+    pub fn has_regressions(&self) -> bool {
+        self.added.iter().any(|change| change.raises_floor) || !self.new_libraries.is_empty()
+    }
+}
+
+/// A versioned symbol required by one report only.
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub namespace: String,
+    pub version: String,
+    pub function: String,
+    /// The inputs importing it, in the report requiring it.
+    pub files: Vec<String>,
+    /// Whether the version is newer than the old report required from the
+    /// namespace.
+    pub raises_floor: bool,
+}
+
+/// The highest version required from a namespace, in the old and the new
+/// report.
+#[derive(Debug, Serialize)]
+pub struct Floor {
+    pub namespace: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    /// Whether the new report requires a newer version.
+    pub raised: bool,
+}
+
+/// What the inputs of an `--envelope` JSON document require themselves,
+/// whatever the libraries of the system they load do, and those libraries.
+#[derive(Default)]
+struct Report {
+    /// (namespace, version, function) => the inputs importing it
+    requirements: BTreeMap<(String, String, String), Vec<String>>,
+    libraries: BTreeSet<String>,
+}
+
+impl Report {
+    fn new(envelope: &Value) -> Report {
+        let str_of = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        let mut report = Report::default();
+        let inputs = envelope["inputs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|input| str_of(&input["path"]))
+            .collect::<BTreeSet<String>>();
+        for requirement in envelope["findings"]["requirements"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let files = requirement["files"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(str_of)
+                .filter(|file| inputs.contains(file))
+                .collect::<Vec<String>>();
+            if files.is_empty() {
+                continue;
+            }
+            report.requirements.insert(
+                (
+                    str_of(&requirement["namespace"]),
+                    str_of(&requirement["version"]),
+                    str_of(&requirement["function"]),
+                ),
+                files,
+            );
+        }
+        for input in envelope["inputs"].as_array().into_iter().flatten() {
+            report.libraries.extend(
+                input["libraries"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(str_of),
+            );
+        }
+        report
+    }
+
+    /// namespace => the highest version required from it
+    fn floors(&self) -> BTreeMap<&str, &str> {
+        let mut floors: BTreeMap<&str, &str> = BTreeMap::new();
+        for (namespace, version, _) in self.requirements.keys() {
+            if namespace == "GLIBC" && version == "PRIVATE" {
+                continue;
+            }
+            let floor = floors.entry(namespace).or_insert(version);
+            if distro::version_key(version) > distro::version_key(floor) {
+                *floor = version;
+            }
+        }
+        floors
+    }
+}
+
+/// Reads a report saved with `--envelope`, e.g. by `lddcheck -p build/
+/// --envelope --save-json-to last-release.json`.
+pub fn read_baseline(path: &Path) -> Result<Value, Box<dyn Error>> {
+    let data =
+        std::fs::read(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    let report: Value = serde_json::from_slice(&data)
+        .map_err(|err| format!("cannot parse {}: {}", path.display(), err))?;
+    match report["schema_version"].as_u64() {
+        Some(version) if version > schema::SCHEMA_VERSION => Err(format!(
+            "{} was written by a newer lddcheck (schema version {})",
+            path.display(),
+            version
+        )
+        .into()),
+        Some(_) => Ok(report),
+        None => Err(format!("{} is not a report saved with --envelope", path.display()).into()),
+    }
+}
+
+/// Compares two `--envelope` JSON documents, `old` and `new`.
+pub fn compare(old: &Value, new: &Value) -> Changes {
+    let (old, new) = (Report::new(old), Report::new(new));
+    let (old_floors, new_floors) = (old.floors(), new.floors());
+    let raises_floor = |namespace: &str, version: &str| {
+        !(namespace == "GLIBC" && version == "PRIVATE")
+            && old_floors
+                .get(namespace)
+                .is_none_or(|floor| distro::version_key(version) > distro::version_key(floor))
+    };
+    let only_in = |report: &Report, other: &Report, check_floor: bool| {
+        let mut changes = report
+            .requirements
+            .iter()
+            .filter(|(key, _)| !other.requirements.contains_key(*key))
+            .map(|((namespace, version, function), files)| Change {
+                namespace: namespace.clone(),
+                version: version.clone(),
+                function: function.clone(),
+                files: files.clone(),
+                raises_floor: check_floor && raises_floor(namespace, version),
+            })
+            .collect::<Vec<Change>>();
+        changes.sort_by_cached_key(|change| {
+            (
+                change.namespace.clone(),
+                distro::version_key(&change.version),
+                change.function.clone(),
+            )
+        });
+        changes
+    };
+    let mut changes = Changes {
+        added: only_in(&new, &old, true),
+        removed: only_in(&old, &new, false),
+        new_libraries: new.libraries.difference(&old.libraries).cloned().collect(),
+        dropped_libraries: old.libraries.difference(&new.libraries).cloned().collect(),
+        floors: Vec::new(),
+    };
+    let namespaces = old_floors
+        .keys()
+        .chain(new_floors.keys())
+        .collect::<BTreeSet<_>>();
+    for namespace in namespaces {
+        let (old, new) = (old_floors.get(namespace), new_floors.get(namespace));
+        if old != new {
+            changes.floors.push(Floor {
+                namespace: namespace.to_string(),
+                old: old.map(|version| version.to_string()),
+                new: new.map(|version| version.to_string()),
+                raised: new.is_some_and(|version| raises_floor(namespace, version)),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Returns an `--envelope` document of the input `app` loading
+    /// `libraries`, with the (namespace, version, function, file)
+    /// `requirements`.
+    fn envelope(libraries: &[&str], requirements: &[(&str, &str, &str, &str)]) -> Value {
+        let requirements = requirements
+            .iter()
+            .map(|(namespace, version, function, file)| {
+                json!({
+                    "namespace": namespace,
+                    "version": version,
+                    "function": function,
+                    "files": [file],
+                })
+            })
+            .collect::<Vec<Value>>();
+        json!({
+            "schema_version": schema::SCHEMA_VERSION,
+            "inputs": [{ "path": "app", "libraries": libraries }],
+            "findings": { "requirements": requirements },
+        })
+    }
+
+    #[test]
+    fn compares_what_the_inputs_require_themselves() {
+        let old = envelope(
+            &["libc.so.6"],
+            &[
+                ("GLIBC", "2.2.5", "memcpy", "app"),
+                ("GLIBC", "2.17", "clock_gettime", "app"),
+                // what the libraries of the system require is up to them
+                ("GLIBC", "2.38", "strlcpy", "/usr/lib/libfoo.so.1"),
+            ],
+        );
+        let new = envelope(
+            &["libc.so.6", "libssl.so.3"],
+            &[
+                ("GLIBC", "2.17", "clock_gettime", "app"),
+                ("GLIBC", "2.34", "pthread_create", "app"),
+                ("GLIBC", "2.14", "memcpy", "app"),
+            ],
+        );
+        let changes = compare(&old, &new);
+        let added = changes
+            .added
+            .iter()
+            .map(|change| (change.version.as_str(), change.raises_floor))
+            .collect::<Vec<_>>();
+        assert_eq!(added, [("2.14", false), ("2.34", true)]);
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.removed[0].function, "memcpy");
+        assert_eq!(changes.removed[0].version, "2.2.5");
+        assert_eq!(changes.new_libraries, ["libssl.so.3"]);
+        assert!(changes.dropped_libraries.is_empty());
+        assert_eq!(changes.floors.len(), 1);
+        assert_eq!(changes.floors[0].old.as_deref(), Some("2.17"));
+        assert_eq!(changes.floors[0].new.as_deref(), Some("2.34"));
+        assert!(changes.floors[0].raised);
+        assert!(changes.has_regressions());
+
+        // the other way round nothing is required that was not before
+        let changes = compare(&new, &old);
+        assert!(changes.added.iter().all(|change| !change.raises_floor));
+        assert_eq!(changes.dropped_libraries, ["libssl.so.3"]);
+        assert!(!changes.floors[0].raised);
+        assert!(!changes.has_regressions());
+    }
+
+    #[test]
+    fn does_not_count_glibc_private_as_a_floor() {
+        let old = envelope(&[], &[("GLIBC", "2.17", "clock_gettime", "app")]);
+        let new = envelope(
+            &[],
+            &[
+                ("GLIBC", "2.17", "clock_gettime", "app"),
+                ("GLIBC", "PRIVATE", "__libc_dlopen_mode", "app"),
+            ],
+        );
+        let changes = compare(&old, &new);
+        assert_eq!(changes.added.len(), 1);
+        assert!(!changes.added[0].raises_floor);
+        assert!(changes.floors.is_empty());
+        assert!(!changes.has_regressions());
+    }
+
+    #[test]
+    fn reads_baselines_saved_with_envelope() {
+        let dir = tempfile::tempdir().unwrap();
+        let read = |name: &str, report: Value| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, report.to_string()).unwrap();
+            read_baseline(&path).map_err(|err| err.to_string())
+        };
+        assert!(read("baseline.json", envelope(&[], &[])).is_ok());
+        let newer = json!({ "schema_version": schema::SCHEMA_VERSION + 1 });
+        assert!(read("newer.json", newer)
+            .unwrap_err()
+            .contains("written by a newer lddcheck"));
+        assert!(read("versions.json", json!({ "GLIBC_2.17": {} }))
+            .unwrap_err()
+            .ends_with("is not a report saved with --envelope"));
+        assert!(read_baseline(&dir.path().join("missing.json"))
+            .unwrap_err()
+            .to_string()
+            .starts_with("cannot read"));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::libstdcxx;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+
+/// A distribution release and the glibc, libstdc++ and kernel versions it
+/// ships.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DistroRelease {
+    pub distro: String,
+    pub release: String,
+    pub glibc: String,
+    /// The newest `GLIBCXX_` version of the system libstdc++, if known.
+    #[serde(default)]
+    pub glibcxx: Option<String>,
+    /// The kernel version the release shipped with, if known.
+    #[serde(default)]
+    pub kernel: Option<String>,
+}
+
+/// The built-in table, oldest release first within each distribution.
+const DISTRO_RELEASES: &[(&str, &str, &str, &str, &str)] = &[
+    ("RHEL", "7", "2.17", "3.4.19", "3.10"),
+    ("RHEL", "8", "2.28", "3.4.25", "4.18"),
+    ("RHEL", "9", "2.34", "3.4.29", "5.14"),
+    ("RHEL", "10", "2.39", "3.4.33", "6.12"),
+    ("Ubuntu", "14.04", "2.19", "3.4.19", "3.13"),
+    ("Ubuntu", "16.04", "2.23", "3.4.21", "4.4"),
+    ("Ubuntu", "18.04", "2.27", "3.4.25", "4.15"),
+    ("Ubuntu", "18.10", "2.28", "3.4.25", "4.18"),
+    ("Ubuntu", "19.04", "2.29", "3.4.26", "5.0"),
+    ("Ubuntu", "19.10", "2.30", "3.4.27", "5.3"),
+    ("Ubuntu", "20.04", "2.31", "3.4.28", "5.4"),
+    ("Ubuntu", "20.10", "2.32", "3.4.28", "5.8"),
+    ("Ubuntu", "21.04", "2.33", "3.4.29", "5.11"),
+    ("Ubuntu", "21.10", "2.34", "3.4.29", "5.13"),
+    ("Ubuntu", "22.04", "2.35", "3.4.30", "5.15"),
+    ("Ubuntu", "22.10", "2.36", "3.4.30", "5.19"),
+    ("Ubuntu", "23.04", "2.37", "3.4.31", "6.2"),
+    ("Ubuntu", "23.10", "2.38", "3.4.32", "6.5"),
+    ("Ubuntu", "24.04", "2.39", "3.4.33", "6.8"),
+    ("Ubuntu", "24.10", "2.40", "3.4.33", "6.11"),
+    ("Ubuntu", "25.04", "2.41", "3.4.34", "6.14"),
+    ("Debian", "8", "2.19", "3.4.20", "3.16"),
+    ("Debian", "9", "2.24", "3.4.22", "4.9"),
+    ("Debian", "10", "2.28", "3.4.25", "4.19"),
+    ("Debian", "11", "2.31", "3.4.28", "5.10"),
+    ("Debian", "12", "2.36", "3.4.30", "6.1"),
+    ("Debian", "13", "2.41", "3.4.33", "6.12"),
+    ("Fedora", "28", "2.27", "3.4.25", "4.16"),
+    ("Fedora", "29", "2.28", "3.4.25", "4.18"),
+    ("Fedora", "30", "2.29", "3.4.26", "5.0"),
+    ("Fedora", "31", "2.30", "3.4.27", "5.3"),
+    ("Fedora", "32", "2.31", "3.4.28", "5.6"),
+    ("Fedora", "33", "2.32", "3.4.28", "5.8"),
+    ("Fedora", "34", "2.33", "3.4.29", "5.11"),
+    ("Fedora", "35", "2.34", "3.4.29", "5.14"),
+    ("Fedora", "36", "2.35", "3.4.30", "5.17"),
+    ("Fedora", "37", "2.36", "3.4.30", "6.0"),
+    ("Fedora", "38", "2.37", "3.4.31", "6.2"),
+    ("Fedora", "39", "2.38", "3.4.32", "6.5"),
+    ("Fedora", "40", "2.39", "3.4.33", "6.8"),
+    ("Fedora", "41", "2.40", "3.4.33", "6.11"),
+    ("Fedora", "42", "2.41", "3.4.34", "6.14"),
+    ("SLES", "12", "2.22", "3.4.19", "3.12"),
+    ("SLES", "15", "2.26", "3.4.24", "4.12"),
+    ("SLES", "15.4", "2.31", "3.4.29", "5.14"),
+    ("Amazon Linux", "2", "2.26", "3.4.24", "4.14"),
+    ("Amazon Linux", "2023", "2.34", "3.4.29", "6.1"),
+];
+
+/// Returns the built-in distribution table.
+pub fn builtin_table() -> Vec<DistroRelease> {
+    DISTRO_RELEASES
+        .iter()
+        .map(|(distro, release, glibc, glibcxx, kernel)| DistroRelease {
+            distro: distro.to_string(),
+            release: release.to_string(),
+            glibc: glibc.to_string(),
+            glibcxx: Some(glibcxx.to_string()),
+            kernel: Some(kernel.to_string()),
+        })
+        .collect()
+}
+
+/// Reads a distribution table from a JSON file holding an array of
+/// `{"distro": ..., "release": ..., "glibc": ..., "glibcxx": ..., "kernel": ...}`
+/// objects, oldest release first within each distribution. `glibcxx` and
+/// `kernel` are optional.
+pub fn read_table(path: &Path) -> Result<Vec<DistroRelease>, Box<dyn Error>> {
+    let data = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Returns the first release of every distribution whose glibc is at least
+/// `glibc`, as `"<distro> <release>"`.
+pub fn first_releases(table: &[DistroRelease], glibc: &str) -> Vec<String> {
+    let wanted = version_key(glibc);
+    let mut releases: Vec<(&str, String)> = Vec::new();
+    for entry in table {
+        if releases.iter().any(|(distro, _)| *distro == entry.distro) {
+            continue;
+        }
+        if version_key(&entry.glibc) >= wanted {
+            releases.push((&entry.distro, format!("{} {}", entry.distro, entry.release)));
+        }
+    }
+    releases.into_iter().map(|(_, release)| release).collect()
+}
+
+/// What a binary needs from the system it runs on.
+#[derive(Debug, Default, Serialize)]
+pub struct Requirements {
+    pub glibc: Option<String>,
+    /// The GCC release whose libstdc++ provides the `GLIBCXX`/`CXXABI`
+    /// versions required.
+    pub libstdcxx: Option<String>,
+    /// The GCC release whose libgcc_s provides the `GCC` versions required.
+    pub libgcc: Option<String>,
+    /// The minimum kernel version from the ABI tag notes.
+    pub kernel: Option<String>,
+}
+
+/// Returns the first release of every distribution that meets all of
+/// `requirements`, as `"<distro> <release>"`. Requirements the table has no
+/// data for (e.g. a custom table without `glibcxx`) are not checked.
+pub fn viable_releases(table: &[DistroRelease], requirements: &Requirements) -> Vec<String> {
+    let at_least = |have: Option<&str>, wanted: &Option<String>| match (have, wanted) {
+        (Some(have), Some(wanted)) => version_key(have) >= version_key(wanted),
+        _ => true,
+    };
+    let mut releases: Vec<(&str, String)> = Vec::new();
+    for entry in table {
+        if releases.iter().any(|(distro, _)| *distro == entry.distro) {
+            continue;
+        }
+        // libstdc++ and libgcc_s come from the same GCC on a distribution
+        let gcc = entry
+            .glibcxx
+            .as_deref()
+            .and_then(|glibcxx| libstdcxx::minimum_gcc("GLIBCXX", glibcxx));
+        if at_least(Some(&entry.glibc), &requirements.glibc)
+            && at_least(gcc, &requirements.libstdcxx)
+            && at_least(gcc, &requirements.libgcc)
+            && at_least(entry.kernel.as_deref(), &requirements.kernel)
+        {
+            releases.push((&entry.distro, format!("{} {}", entry.distro, entry.release)));
+        }
+    }
+    releases.into_iter().map(|(_, release)| release).collect()
+}
+
+/// Looks up a release given as `distro:release`, e.g. `ubuntu:20.04` or
+/// `amazon-linux:2023`. Distribution names are matched case-insensitively
+/// and regardless of spaces and dashes.
+pub fn find_release<'a>(table: &'a [DistroRelease], spec: &str) -> Option<&'a DistroRelease> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let (distro, release) = spec.split_once(':')?;
+    table
+        .iter()
+        .find(|entry| normalize(&entry.distro) == normalize(distro) && entry.release == release)
+}
+
+/// A part of a version between dots or underscores, as compared.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionPart {
+    /// A part that does not start with a digit, e.g. `PRIVATE`, compared as
+    /// text and before any number.
+    Text(String),
+    /// The number a part starts with and what follows it, e.g. `1` and `a`
+    /// for `1a`.
+    Number(u64, String),
+}
+
+/// Splits a version such as `2.34`, `3.4.29` or `1_1_1` into its parts for
+/// comparison, numbers comparing by value.
+pub fn version_key(version: &str) -> Vec<VersionPart> {
+    version
+        .split(['.', '_'])
+        .map(|part| {
+            let digits = part.len() - part.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match part[..digits].parse::<u64>() {
+                Ok(number) => VersionPart::Number(number, part[digits..].to_string()),
+                Err(_) => VersionPart::Text(part.to_string()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_versions_by_their_numbers() {
+        let mut versions = vec![
+            "OPENSSL_3.0.0",
+            "OPENSSL_1_1_1",
+            "OPENSSL_1_1_0",
+            "OPENSSL_1_1_1d",
+            "OPENSSL_1_1_0f",
+        ];
+        versions.sort_by_key(|version| version_key(version));
+        assert_eq!(
+            versions,
+            [
+                "OPENSSL_1_1_0",
+                "OPENSSL_1_1_0f",
+                "OPENSSL_1_1_1",
+                "OPENSSL_1_1_1d",
+                "OPENSSL_3.0.0"
+            ]
+        );
+        assert!(version_key("2.10") > version_key("2.9"));
+        assert!(version_key("3.4.29") > version_key("3.4"));
+        assert_ne!(version_key("PRIVATE"), version_key("0"));
+    }
+
+    #[test]
+    fn finds_the_first_releases_providing_a_version() {
+        let table = builtin_table();
+        let releases = first_releases(&table, "2.28");
+        assert!(releases.contains(&"RHEL 8".to_string()));
+        assert!(releases.contains(&"Debian 10".to_string()));
+        assert!(releases.contains(&"Ubuntu 18.10".to_string()));
+        // a newer libstdc++ than RHEL 8 ships moves it on to RHEL 9
+        let requirements = Requirements {
+            glibc: Some("2.28".to_string()),
+            libstdcxx: libstdcxx::minimum_gcc("GLIBCXX", "3.4.29").map(str::to_string),
+            ..Requirements::default()
+        };
+        let releases = viable_releases(&table, &requirements);
+        assert!(releases.contains(&"RHEL 9".to_string()));
+        assert!(!releases.contains(&"RHEL 8".to_string()));
+        assert!(first_releases(&table, "9.99").is_empty());
+    }
+
+    #[test]
+    fn looks_releases_up_by_name() {
+        let table = builtin_table();
+        let release = find_release(&table, "amazon-linux:2023").unwrap();
+        assert_eq!(release.glibc, "2.34");
+        assert_eq!(find_release(&table, "Ubuntu:20.04").unwrap().glibc, "2.31");
+        assert!(find_release(&table, "ubuntu:20.05").is_none());
+        assert!(find_release(&table, "ubuntu").is_none());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::inputs;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// The directories of the runtime that flatpak links to the root of the
+/// sandbox, e.g. `/lib64 -> usr/lib64`.
+const LINKED_DIRS: [&str; 6] = ["bin", "sbin", "lib", "lib32", "lib64", "etc"];
+
+/// An application as it is laid out in the flatpak sandbox.
+#[derive(Debug)]
+pub struct Sandbox {
+    /// A directory standing in for the root of the sandbox: the runtime
+    /// mounted at `/usr` and the application at `/app`.
+    pub root: PathBuf,
+    /// The files of the application, what the sandbox sees at `/app`.
+    pub app_files: PathBuf,
+    /// The application ID from the metadata.
+    pub app_id: String,
+}
+
+/// The `[Application]` (or `[Runtime]`) group of a flatpak `metadata` file.
+#[derive(Debug, Default)]
+struct Metadata {
+    name: Option<String>,
+    runtime: Option<String>,
+}
+
+impl Metadata {
+    fn read(path: &Path) -> std::io::Result<Metadata> {
+        let mut metadata = Metadata::default();
+        let mut in_main_group = false;
+        for line in std::fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                in_main_group = line == "[Application]" || line == "[Runtime]";
+                continue;
+            }
+            if !in_main_group {
+                continue;
+            }
+            match line.split_once('=') {
+                Some(("name", value)) => metadata.name = Some(value.trim().to_string()),
+                Some(("runtime", value)) => metadata.runtime = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+/// Lays out the flatpak build directory (or deployed application) at `dir`
+/// as the sandbox would, against the runtime `runtime` (by default the one
+/// the metadata names) or the runtime files in `runtime_dir`.
+pub fn sandbox(
+    dir: &Path,
+    runtime: Option<&str>,
+    runtime_dir: Option<&Path>,
+    workspace: &mut inputs::Workspace,
+) -> Result<Sandbox, Box<dyn Error>> {
+    if dir.is_file() {
+        return Err("flatpak bundles cannot be read directly, install the bundle with flatpak install --bundle and pass its deploy directory, e.g. /var/lib/flatpak/app/<id>/<arch>/<branch>/active".into());
+    }
+    let metadata = Metadata::read(&dir.join("metadata"))
+        .map_err(|err| format!("cannot read the flatpak metadata: {}", err))?;
+    let app_files = dir.join("files");
+    if !app_files.is_dir() {
+        return Err("no files directory in the flatpak build".into());
+    }
+    let Some(runtime) = runtime.map(str::to_string).or(metadata.runtime) else {
+        return Err("the metadata names no runtime, pass --runtime".into());
+    };
+    let runtime_files = match runtime_dir {
+        // the deploy directory of a runtime holds its files in files/
+        Some(runtime_dir) if runtime_dir.join("files").is_dir() => runtime_dir.join("files"),
+        Some(runtime_dir) => runtime_dir.to_path_buf(),
+        None => find_runtime(&runtime)
+            .ok_or_else(|| format!("runtime {} is not installed, pass --runtime-dir", runtime))?,
+    };
+    let app_id = metadata.name.unwrap_or_else(|| dir.display().to_string());
+
+    let root = workspace.new_dir(Path::new(&app_id))?;
+    #[cfg(unix)]
+    {
+        let runtime_files = std::fs::canonicalize(&runtime_files)?;
+        std::os::unix::fs::symlink(&runtime_files, root.join("usr"))?;
+        std::os::unix::fs::symlink(std::fs::canonicalize(&app_files)?, root.join("app"))?;
+        for name in LINKED_DIRS {
+            if runtime_files.join(name).exists() {
+                std::os::unix::fs::symlink(Path::new("usr").join(name), root.join(name))?;
+            }
+        }
+    }
+    // the libraries of the application come first, then the runtime's, in
+    // their multiarch directories too
+    workspace.add_package_library_paths(&app_files);
+    workspace.add_package_library_paths(&runtime_files);
+    Ok(Sandbox {
+        root,
+        app_files,
+        app_id,
+    })
+}
+
+/// Looks for the files of an installed runtime, given as
+/// `[runtime/]<id>/<arch>/<branch>`, in the per-user installation and then
+/// in the system-wide one.
+fn find_runtime(reference: &str) -> Option<PathBuf> {
+    let reference = reference.strip_prefix("runtime/").unwrap_or(reference);
+    let mut parts = reference.split('/');
+    let id = parts.next()?;
+    let host_arch = match std::env::consts::ARCH {
+        "x86" => "i386",
+        arch => arch,
+    };
+    let arch = parts
+        .next()
+        .filter(|arch| !arch.is_empty())
+        .unwrap_or(host_arch);
+    let branch = parts
+        .next()
+        .filter(|branch| !branch.is_empty())
+        .unwrap_or("master");
+
+    // an empty variable counts as unset, as for flatpak itself
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let user = var("FLATPAK_USER_DIR").map(PathBuf::from).or_else(|| {
+        var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .map(|data| data.join("flatpak"))
+    });
+    let system = var("FLATPAK_SYSTEM_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/var/lib/flatpak"));
+    user.into_iter()
+        .chain(std::iter::once(system))
+        .map(|installation| {
+            installation
+                .join("runtime")
+                .join(id)
+                .join(arch)
+                .join(branch)
+                .join("active/files")
+        })
+        .find(|files| files.is_dir())
+}
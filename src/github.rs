@@ -0,0 +1,40 @@
+use crate::violations::{Violation, RULES};
+
+/// Returns a GitHub Actions `::error` workflow command for every violation,
+/// which the Actions UI shows as an annotation on the file at fault.
+pub fn annotations(violations: &[Violation]) -> Vec<String> {
+    violations
+        .iter()
+        .map(|violation| {
+            let title = RULES
+                .iter()
+                .find(|(rule, _)| *rule == violation.rule)
+                .map_or(violation.rule, |(_, description)| description);
+            let mut properties = Vec::new();
+            if let Some(file) = &violation.file {
+                properties.push(format!(
+                    "file={}",
+                    escape_property(&file.display().to_string())
+                ));
+            }
+            properties.push(format!("title={}", escape_property(title)));
+            format!(
+                "::error {}::{}",
+                properties.join(","),
+                escape_data(&violation.message)
+            )
+        })
+        .collect()
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes the value of a workflow command property.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,42 @@
+use crate::violations::Violation;
+use sha2::{Digest, Sha256};
+
+/// Returns a GitLab Code Quality report: every violation as a `major`
+/// issue, and the glibc version every input requires, `(input, version)`,
+/// as an `info` one, so merge requests show regressions in the widget.
+pub fn report(violations: &[Violation], requirements: &[(String, String)]) -> serde_json::Value {
+    let mut issues = violations
+        .iter()
+        .map(|violation| {
+            let path = violation
+                .file
+                .as_ref()
+                .map(|file| file.display().to_string())
+                .unwrap_or_default();
+            issue(violation.rule, &violation.message, &path, "major")
+        })
+        .collect::<Vec<serde_json::Value>>();
+    issues.extend(requirements.iter().map(|(input, version)| {
+        let description = format!("{} requires {}", input, version);
+        issue("glibc-requirement", &description, input, "info")
+    }));
+    serde_json::Value::Array(issues)
+}
+
+fn issue(check_name: &str, description: &str, path: &str, severity: &str) -> serde_json::Value {
+    // the fingerprint tells GitLab which issues are the same across runs
+    let fingerprint = Sha256::digest(format!("{}\0{}\0{}", check_name, description, path))
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    serde_json::json!({
+        "description": description,
+        "check_name": check_name,
+        "fingerprint": fingerprint,
+        "severity": severity,
+        "location": {
+            "path": path,
+            "lines": { "begin": 1 },
+        },
+    })
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+/// A file of the dependency graph.
+#[derive(Debug)]
+pub struct Node {
+    /// The path of the file, which also identifies it.
+    pub path: String,
+    /// The newest glibc version the file itself requires, e.g. `GLIBC_2.34`.
+    pub glibc: Option<String>,
+    /// Whether the file was given as an input rather than loaded by one.
+    pub is_input: bool,
+    /// Whether the file requires a newer glibc than `--max-glibc`.
+    pub is_offending: bool,
+}
+
+/// The resolved dependency graph of the inputs, with an edge from every file
+/// to each library it needs (`DT_NEEDED`).
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    /// (file, library it needs), by path.
+    pub edges: Vec<(String, String)>,
+}
+
+impl Node {
+    /// The file name, then the glibc version on the next line.
+    fn label(&self, line_break: &str) -> String {
+        let name = self.path.rsplit('/').next().unwrap_or(&self.path);
+        match &self.glibc {
+            Some(glibc) => format!("{}{}{}", name, line_break, glibc),
+            None => name.to_string(),
+        }
+    }
+}
+
+impl Graph {
+    /// Returns the graph in the Graphviz DOT language: inputs are boxes and
+    /// the files needing a newer glibc than `--max-glibc` are red.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph lddcheck {\n    rankdir=LR;\n    node [shape=ellipse];\n");
+        for node in &self.nodes {
+            let mut attributes = vec![
+                format!("label={}", dot_string(&node.label("\n"))),
+                format!("tooltip={}", dot_string(&node.path)),
+            ];
+            if node.is_input {
+                attributes.push("shape=box".to_string());
+            }
+            if node.is_offending {
+                attributes.push("color=red".to_string());
+                attributes.push("fontcolor=red".to_string());
+            }
+            dot.push_str(&format!(
+                "    {} [{}];\n",
+                dot_string(&node.path),
+                attributes.join(", ")
+            ));
+        }
+        for (from, to) in &self.edges {
+            dot.push_str(&format!(
+                "    {} -> {};\n",
+                dot_string(from),
+                dot_string(to)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the graph as a Mermaid `graph TD` diagram in a fenced code
+    /// block, which GitHub and GitLab render in Markdown: inputs are
+    /// rectangles, libraries rounded and the files needing a newer glibc than
+    /// `--max-glibc` are red.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("```mermaid\ngraph TD\n");
+        // the paths are not valid IDs, the nodes are numbered instead
+        let mut ids = HashMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let id = format!("n{}", index);
+            let label = mermaid_string(&node.label("<br>"));
+            if node.is_input {
+                mermaid.push_str(&format!("    {}[{}]\n", id, label));
+            } else {
+                mermaid.push_str(&format!("    {}({})\n", id, label));
+            }
+            ids.insert(node.path.as_str(), id);
+        }
+        for (from, to) in &self.edges {
+            if let (Some(from), Some(to)) = (ids.get(from.as_str()), ids.get(to.as_str())) {
+                mermaid.push_str(&format!("    {} --> {}\n", from, to));
+            }
+        }
+        let offending = self
+            .nodes
+            .iter()
+            .filter(|node| node.is_offending)
+            .map(|node| ids[node.path.as_str()].as_str())
+            .collect::<Vec<&str>>();
+        if !offending.is_empty() {
+            mermaid.push_str("    classDef offending stroke:#d00,color:#d00\n");
+            mermaid.push_str(&format!("    class {} offending\n", offending.join(",")));
+        }
+        mermaid.push_str("```\n");
+        mermaid
+    }
+}
+
+/// Quotes an ID or a label, with `\n` for its line breaks.
+fn dot_string(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Quotes a label, with the characters Mermaid would parse as syntax written
+/// as entity codes.
+fn mermaid_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "#quot;"))
+}
@@ -0,0 +1,137 @@
+use crate::violations::Violation;
+use std::collections::BTreeMap;
+
+/// The functions needing a version, with the files importing each of them.
+pub type VersionFunctions = Vec<(String, Vec<String>)>;
+
+/// Filters the entries by the search box, hiding the sections left empty and
+/// opening the others so the matches are seen.
+const SCRIPT: &str = r#"
+document.getElementById("search").addEventListener("input", (event) => {
+  const query = event.target.value.toLowerCase();
+  for (const entry of document.querySelectorAll(".entry")) {
+    entry.hidden = query !== "" && !entry.textContent.toLowerCase().includes(query);
+    entry.open = query !== "" && !entry.hidden;
+  }
+  for (const section of document.querySelectorAll("details:not(.entry)")) {
+    const title = section.querySelector("summary").textContent.toLowerCase();
+    const shown = section.querySelector(".entry:not([hidden])") !== null;
+    section.hidden = query !== "" && !shown && !title.includes(query);
+    section.open = query !== "" && !section.hidden;
+  }
+});
+"#;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; }
+code { font-family: monospace; }
+summary { cursor: pointer; padding: 0.2em 0; }
+details details { margin-left: 1.5em; }
+#search { font-size: 1em; padding: 0.3em; width: 30em; margin-bottom: 1em; }
+.violation { color: #b00020; }
+.count { color: #666; }
+"#;
+
+/// Returns a single-file HTML report to share with people not running
+/// lddcheck: the inputs and the newest glibc version each requires, the
+/// violations, then collapsible sections per version (its functions and the
+/// files importing them) and per file (the versions and functions it
+/// imports), filtered by a search box.
+pub fn report(
+    inputs: &[(String, Option<String>)],
+    violations: &[Violation],
+    versions: &[(String, VersionFunctions)],
+) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n<title>lddcheck report</title>\n");
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str("<h1>lddcheck report</h1>\n");
+
+    html.push_str("<h2>Inputs</h2>\n<ul>\n");
+    for (input, glibc) in inputs {
+        let glibc = glibc
+            .as_ref()
+            .map_or("no glibc version".to_string(), |glibc| code(glibc));
+        html.push_str(&format!("<li>{} requires {}</li>\n", code(input), glibc));
+    }
+    html.push_str("</ul>\n");
+
+    html.push_str(&format!("<h2>Violations ({})</h2>\n", violations.len()));
+    if !violations.is_empty() {
+        html.push_str("<ul>\n");
+        for violation in violations {
+            let file = violation
+                .file
+                .as_ref()
+                .map(|file| format!(" {}", code(&file.display().to_string())))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<li class=\"entry violation\"><b>{}</b>{}: {}</li>\n",
+                escape(violation.rule),
+                file,
+                escape(&violation.message)
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<input id=\"search\" type=\"search\" placeholder=\"Search versions, functions and files\">\n");
+    html.push_str("<h2>By version</h2>\n");
+    for (version, functions) in versions {
+        html.push_str(&format!(
+            "<details>\n<summary>{} <span class=\"count\">({} functions)</span></summary>\n",
+            code(version),
+            functions.len()
+        ));
+        for (function, files) in functions {
+            html.push_str(&format!(
+                "<details class=\"entry\">\n<summary>{}</summary>\n<ul>\n",
+                code(function)
+            ));
+            for file in files {
+                html.push_str(&format!("<li>{}</li>\n", code(file)));
+            }
+            html.push_str("</ul>\n</details>\n");
+        }
+        html.push_str("</details>\n");
+    }
+
+    html.push_str("<h2>By file</h2>\n");
+    let mut by_file: BTreeMap<&String, Vec<(&String, &String)>> = BTreeMap::new();
+    for (version, functions) in versions {
+        for (function, files) in functions {
+            for file in files {
+                by_file.entry(file).or_default().push((version, function));
+            }
+        }
+    }
+    for (file, imports) in by_file {
+        html.push_str(&format!(
+            "<details>\n<summary>{} <span class=\"count\">({} functions)</span></summary>\n<ul>\n",
+            code(file),
+            imports.len()
+        ));
+        for (version, function) in imports {
+            html.push_str(&format!(
+                "<li class=\"entry\">{} {}</li>\n",
+                code(version),
+                code(function)
+            ));
+        }
+        html.push_str("</ul>\n</details>\n");
+    }
+
+    html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    html
+}
+
+fn code(text: &str) -> String {
+    format!("<code>{}</code>", escape(text))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,523 @@
+use crate::{archive, compression, inputs};
+use serde::Deserialize;
+use std::error::Error;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+/// The prefix of the whiteout files that delete a file of the layers below.
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// The whiteout file that hides everything the layers below put into its
+/// directory.
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// How many symlinks are followed when resolving a path in the image, like
+/// the kernel's limit.
+const MAX_SYMLINKS: usize = 40;
+
+/// A container image whose layers were applied to a directory.
+#[derive(Debug)]
+pub struct Image {
+    /// The merged filesystem of the image.
+    pub root: PathBuf,
+    /// The programs the ENTRYPOINT and CMD of the image run, as paths under
+    /// `root`.
+    pub entrypoint: Vec<PathBuf>,
+}
+
+/// An entry of the `manifest.json` written by `docker save`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SavedManifest {
+    config: String,
+    layers: Vec<String>,
+}
+
+/// A content descriptor of an OCI image index or manifest.
+#[derive(Debug, Deserialize)]
+pub struct Descriptor {
+    #[serde(rename = "mediaType", default)]
+    pub media_type: String,
+    pub digest: String,
+    platform: Option<Platform>,
+}
+
+impl Descriptor {
+    /// Whether the descriptor points to an index (or a Docker manifest
+    /// list) rather than to an image manifest.
+    pub fn is_index(&self) -> bool {
+        self.media_type.ends_with(".index.v1+json")
+            || self.media_type.ends_with(".manifest.list.v2+json")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+    os: String,
+    architecture: String,
+}
+
+/// An OCI image index, listing the manifests of an image, one per platform.
+/// Docker manifest lists have the same shape.
+#[derive(Debug, Deserialize)]
+pub struct OciIndex {
+    pub manifests: Vec<Descriptor>,
+}
+
+impl OciIndex {
+    /// Returns the manifest for the platform lddcheck runs on, or the first
+    /// one.
+    pub fn select(&self) -> Option<&Descriptor> {
+        let architecture = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "aarch64" => "arm64",
+            arch => arch,
+        };
+        self.manifests
+            .iter()
+            .find(|descriptor| {
+                descriptor.platform.as_ref().is_some_and(|platform| {
+                    platform.os == "linux" && platform.architecture == architecture
+                })
+            })
+            .or_else(|| self.manifests.first())
+    }
+}
+
+/// An OCI image manifest, or a Docker image manifest (v2, schema 2), which
+/// has the same shape.
+#[derive(Debug, Deserialize)]
+pub struct OciManifest {
+    pub config: Descriptor,
+    pub layers: Vec<Descriptor>,
+}
+
+/// The parts of an image configuration that decide what a container runs.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    config: ContainerConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerConfig {
+    entrypoint: Option<Vec<String>>,
+    cmd: Option<Vec<String>>,
+    env: Option<Vec<String>>,
+    working_dir: Option<String>,
+}
+
+/// Reads an image saved by `docker save` or an OCI image layout tarball,
+/// applying its layers to a new directory in `workspace`.
+pub fn from_tar(path: &Path, workspace: &mut inputs::Workspace) -> Result<Image, Box<dyn Error>> {
+    let unpacked = workspace.new_dir(path)?;
+    let data = std::fs::read(path)?;
+    tar::Archive::new(compression::decompress(&data)?.as_ref()).unpack(&unpacked)?;
+    let (config, layers) = if unpacked.join("manifest.json").is_file() {
+        let manifests: Vec<SavedManifest> =
+            serde_json::from_slice(&std::fs::read(unpacked.join("manifest.json"))?)?;
+        let Some(manifest) = manifests.into_iter().next() else {
+            return Err("no image in manifest.json".into());
+        };
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|layer| unpacked.join(layer))
+            .collect::<Vec<PathBuf>>();
+        (unpacked.join(manifest.config), layers)
+    } else if unpacked.join("index.json").is_file() {
+        let manifest = oci_manifest(&unpacked, &unpacked.join("index.json"))?;
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|layer| blob_path(&unpacked, &layer.digest))
+            .collect::<Result<Vec<PathBuf>, Box<dyn Error>>>()?;
+        (blob_path(&unpacked, &manifest.config.digest)?, layers)
+    } else {
+        return Err("neither manifest.json nor index.json in the image".into());
+    };
+    build(path, &config, &layers, workspace)
+}
+
+/// Applies the `layers` of the image `name`, compressed or not, to a new
+/// directory in `workspace`, and finds the programs the image configuration
+/// at `config` runs.
+pub fn build(
+    name: &Path,
+    config: &Path,
+    layers: &[PathBuf],
+    workspace: &mut inputs::Workspace,
+) -> Result<Image, Box<dyn Error>> {
+    let config: Config = serde_json::from_slice(&std::fs::read(config)?)?;
+    let root = workspace.new_dir(name)?;
+    for layer in layers {
+        apply_layer(&compression::decompress(&std::fs::read(layer)?)?, &root)?;
+    }
+    // like for the files of a distribution package, the libraries of the
+    // image are looked up in its multiarch directories too
+    workspace.add_package_library_paths(&root);
+    Ok(Image {
+        entrypoint: entrypoint(&root, &config),
+        root,
+    })
+}
+
+/// Follows the OCI image index at `index` down to the image manifest for
+/// the platform lddcheck runs on, or the first one.
+fn oci_manifest(layout: &Path, index: &Path) -> Result<OciManifest, Box<dyn Error>> {
+    let index: OciIndex = serde_json::from_slice(&std::fs::read(index)?)?;
+    let Some(descriptor) = index.select() else {
+        return Err("no manifest in index.json".into());
+    };
+    let path = blob_path(layout, &descriptor.digest)?;
+    if descriptor.is_index() {
+        return oci_manifest(layout, &path);
+    }
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+/// Returns the path of the blob with the given digest (`sha256:...`) in an
+/// OCI image layout.
+fn blob_path(layout: &Path, digest: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let Some((algorithm, hash)) = digest.split_once(':') else {
+        return Err(format!("invalid digest {}", digest).into());
+    };
+    archive::safe_join(&layout.join("blobs").join(algorithm), hash)
+        .ok_or_else(|| format!("invalid digest {}", digest).into())
+}
+
+/// Applies a layer, an uncompressed tarball, to the filesystem in `root`.
+/// Absolute symlinks, and relative ones climbing out of the image, are made
+/// relative to point into `root` rather than to the host's files.
+///
+/// The symlinks of the layers below are followed inside `root` only: the
+/// directory of every file removed or written is resolved like the image
+/// sees it, so a layer cannot reach outside of `root` through them.
+pub fn apply_layer(data: &[u8], root: &Path) -> Result<(), Box<dyn Error>> {
+    // whiteouts only hide the files of the layers below, so they are applied
+    // before the files of the layer are unpacked
+    for entry in tar::Archive::new(data).entries()? {
+        let entry = entry?;
+        let path = entry.path()?.into_owned();
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            continue;
+        };
+        let Some(dir) = resolve(root, path.parent().unwrap_or(Path::new(""))) else {
+            continue;
+        };
+        if name == OPAQUE_WHITEOUT {
+            for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+                remove(&entry.path())?;
+            }
+        } else if let Some(hidden) = name.strip_prefix(WHITEOUT_PREFIX) {
+            remove(&dir.join(hidden))?;
+        }
+    }
+    for entry in tar::Archive::new(data).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        if name.to_string_lossy().starts_with(WHITEOUT_PREFIX) {
+            continue;
+        }
+        let Some(dir) = resolve(root, path.parent().unwrap_or(Path::new(""))) else {
+            continue;
+        };
+        let target = dir.join(name);
+        let entry_type = entry.header().entry_type();
+        // device nodes and fifos are of no interest, and cannot be created
+        // without privileges
+        if !(entry_type.is_file()
+            || entry_type.is_dir()
+            || entry_type.is_symlink()
+            || entry_type.is_hard_link())
+        {
+            continue;
+        }
+        // a file of a layer below is replaced, not written through
+        if std::fs::symlink_metadata(&target)
+            .is_ok_and(|metadata| !(metadata.is_dir() && entry_type.is_dir()))
+        {
+            remove(&target)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+        if entry_type.is_symlink() {
+            #[cfg(unix)]
+            if let Some(link) = entry.link_name()? {
+                std::os::unix::fs::symlink(confined_link(&path, &link), &target)?;
+            }
+            continue;
+        }
+        if entry_type.is_hard_link() {
+            let source = entry
+                .link_name()?
+                .and_then(|link| resolve(root, &link))
+                .ok_or_else(|| format!("invalid hard link {}", path.display()))?;
+            std::fs::hard_link(source, &target)?;
+            continue;
+        }
+        entry.unpack(&target)?;
+    }
+    Ok(())
+}
+
+/// Removes a file or a whole directory, if it exists.
+fn remove(path: &Path) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Returns the target to give the symlink at `path` (relative to the image
+/// root) pointing at `link` so that it stays inside the image: absolute
+/// targets are made relative to the symlink's directory, and relative ones
+/// climbing above the root are clamped to it, as `..` of `/` is `/`.
+pub fn confined_link(path: &Path, link: &Path) -> PathBuf {
+    if link.is_absolute() {
+        return relative_link(path, link);
+    }
+    let mut resolved = path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect::<Vec<Component>>();
+    let mut escapes = false;
+    for component in link.components() {
+        match component {
+            Component::Normal(_) => resolved.push(component),
+            Component::ParentDir => escapes |= resolved.pop().is_none(),
+            _ => {}
+        }
+    }
+    if !escapes {
+        return link.to_path_buf();
+    }
+    let absolute = Path::new("/").join(resolved.iter().collect::<PathBuf>());
+    relative_link(path, &absolute)
+}
+
+/// Turns the absolute target of the symlink at `path` (relative to the
+/// image root) into one relative to the symlink's directory.
+fn relative_link(path: &Path, link: &Path) -> PathBuf {
+    let depth = path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count()
+        .saturating_sub(1);
+    let mut relative = PathBuf::new();
+    for _ in 0..depth {
+        relative.push("..");
+    }
+    relative.push(link.strip_prefix("/").unwrap_or(link));
+    relative
+}
+
+/// Returns the programs the image runs, the first word of its ENTRYPOINT and
+/// of its CMD, found in the image's PATH. Scripts are replaced by their
+/// interpreter.
+fn entrypoint(root: &Path, config: &Config) -> Vec<PathBuf> {
+    let config = &config.config;
+    let search_path = config
+        .env
+        .iter()
+        .flatten()
+        .find_map(|var| var.strip_prefix("PATH="))
+        .unwrap_or("/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin");
+    let working_dir = config.working_dir.as_deref().unwrap_or("/");
+    let mut programs = Vec::new();
+    for command in [&config.entrypoint, &config.cmd].into_iter().flatten() {
+        let Some(program) = command.first() else {
+            continue;
+        };
+        let Some(mut program) = find_program(root, program, search_path, working_dir) else {
+            continue;
+        };
+        if let Some(interpreter) = interpreter(root, &program, search_path) {
+            program = interpreter;
+        }
+        if !programs.contains(&program) {
+            programs.push(program);
+        }
+    }
+    programs
+}
+
+/// Looks for the program `name` in the image like a shell would, in the
+/// directories of `search_path` unless it contains a slash.
+fn find_program(root: &Path, name: &str, search_path: &str, working_dir: &str) -> Option<PathBuf> {
+    let candidates = if name.contains('/') {
+        vec![Path::new(working_dir).join(name)]
+    } else {
+        search_path
+            .split(':')
+            .map(|dir| Path::new(dir).join(name))
+            .collect()
+    };
+    candidates
+        .iter()
+        .filter_map(|candidate| resolve(root, candidate))
+        .find(|path| path.is_file())
+}
+
+/// For a script starting with `#!`, returns its interpreter, the program
+/// after `/usr/bin/env` if it is run through it.
+fn interpreter(root: &Path, script: &Path, search_path: &str) -> Option<PathBuf> {
+    let data = std::fs::read(script).ok()?;
+    let line = data
+        .strip_prefix(b"#!")?
+        .split(|byte| *byte == b'\n')
+        .next()?;
+    let line = String::from_utf8_lossy(line);
+    let mut words = line.split_whitespace();
+    let interpreter = words.next()?;
+    if Path::new(interpreter)
+        .file_name()
+        .is_some_and(|name| name == "env")
+    {
+        let program = words.find(|word| !word.starts_with('-'))?;
+        return find_program(root, program, search_path, "/");
+    }
+    resolve(root, Path::new(interpreter))
+}
+
+/// Resolves `path` as the image (or any root filesystem) sees it, following its symlinks inside
+/// `root`, and returns the file it names under `root`.
+pub fn resolve(root: &Path, path: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    let mut pending = path
+        .components()
+        .rev()
+        .map(|component| component.as_os_str().to_os_string())
+        .collect::<Vec<OsString>>();
+    let mut symlinks = 0;
+    while let Some(part) = pending.pop() {
+        if part == "/" || part == "." {
+            continue;
+        }
+        if part == ".." {
+            resolved.pop();
+            continue;
+        }
+        let candidate = root.join(&resolved).join(&part);
+        match std::fs::read_link(&candidate) {
+            Ok(target) => {
+                symlinks += 1;
+                if symlinks > MAX_SYMLINKS {
+                    return None;
+                }
+                if target.is_absolute() {
+                    resolved.clear();
+                }
+                pending.extend(
+                    target
+                        .components()
+                        .rev()
+                        .map(|component| component.as_os_str().to_os_string()),
+                );
+            }
+            Err(_) => resolved.push(part),
+        }
+    }
+    Some(root.join(resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A layer tarball with the `files` (path, contents) and the `symlinks`
+    /// (path, target).
+    fn layer(files: &[(&str, &str)], symlinks: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        for (path, target) in symlinks {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, path, target).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn applies_whiteouts_and_symlinks_inside_the_root() {
+        let root = tempfile::tempdir().unwrap();
+        let lower = layer(
+            &[("usr/lib/libfoo.so.1", "foo"), ("etc/secret", "")],
+            &[("lib", "usr/lib"), ("usr/bin/sh", "/bin/busybox")],
+        );
+        apply_layer(&lower, root.path()).unwrap();
+        let upper = layer(&[("lib/libbar.so.1", "bar"), ("etc/.wh.secret", "")], &[]);
+        apply_layer(&upper, root.path()).unwrap();
+
+        let usr_lib = root.path().join("usr/lib");
+        assert_eq!(
+            std::fs::read_to_string(usr_lib.join("libbar.so.1")).unwrap(),
+            "bar"
+        );
+        assert!(!root.path().join("etc/secret").exists());
+        let sh = std::fs::read_link(root.path().join("usr/bin/sh")).unwrap();
+        assert_eq!(sh, Path::new("../../bin/busybox"));
+    }
+
+    #[test]
+    fn does_not_follow_symlinks_out_of_the_root() {
+        let victim = tempfile::tempdir().unwrap();
+        std::fs::write(victim.path().join("secret"), "").unwrap();
+        let escape = format!("{}{}", "../".repeat(16), victim.path().display());
+        let root = tempfile::tempdir().unwrap();
+
+        apply_layer(&layer(&[], &[("evil", &escape)]), root.path()).unwrap();
+        let whiteout = layer(&[("evil/.wh.secret", "")], &[]);
+        apply_layer(&whiteout, root.path()).unwrap();
+        let overwrite = layer(&[("evil/secret", "pwned")], &[]);
+        apply_layer(&overwrite, root.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(victim.path().join("secret")).unwrap(),
+            ""
+        );
+        // the symlink is clamped to the root, like .. of / is /
+        let evil = std::fs::read_link(root.path().join("evil")).unwrap();
+        assert_eq!(evil, victim.path().strip_prefix("/").unwrap());
+    }
+
+    #[test]
+    fn does_not_follow_chains_of_symlinks_out_of_the_root() {
+        let victim = tempfile::tempdir().unwrap();
+        std::fs::write(victim.path().join("secret"), "").unwrap();
+        let root = tempfile::tempdir().unwrap();
+        // as deep in the image as the root is on the host, so that the
+        // targets do not climb above the image when read as written
+        let depth = root.path().components().count() - 1;
+        let dir = vec!["dir"; depth].join("/");
+        let up = "../".repeat(depth);
+        let to_root = format!("{}/up", dir);
+        let evil = format!("{}/evil", dir);
+        // up leads to the root, from where evil climbs out of it
+        let through = format!(
+            "up/{}{}",
+            up,
+            victim.path().strip_prefix("/").unwrap().display()
+        );
+        let lower = layer(&[], &[(&to_root, &up), (&evil, &through)]);
+        apply_layer(&lower, root.path()).unwrap();
+        let upper = layer(&[(&format!("{}/.wh.secret", evil), "")], &[]);
+        apply_layer(&upper, root.path()).unwrap();
+
+        assert!(victim.path().join("secret").exists());
+    }
+}
@@ -0,0 +1,631 @@
+use crate::{archive, compression, rpm, squashfs};
+use std::error::Error;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Magic bytes at the start of an ELF file.
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// The AppImage magic, at offset 8 of the ELF header of its runtime,
+/// followed by the AppImage type.
+const APPIMAGE_MAGIC: &[u8] = b"AI";
+
+/// Magic bytes at the start of a gzip stream.
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+/// The file name suffixes of the tarballs unpacked as inputs.
+const TARBALL_SUFFIXES: [&str; 7] = [
+    ".tar", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.zst", ".tzst",
+];
+
+/// The name the file read from stdin (`-p -`) is reported by.
+pub const STDIN_NAME: &str = "<stdin>";
+
+/// A file to analyze.
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub path: String,
+    /// For files found by walking a directory given on the command line,
+    /// the path relative to that directory, for files matching a glob
+    /// pattern, the path as matched, and for archive members,
+    /// `archive!member`.
+    pub relative: Option<String>,
+    /// The archive (e.g. a wheel) the file was unpacked from, or the
+    /// installed package (e.g. a gem) it belongs to.
+    pub archive: Option<String>,
+}
+
+/// The temporary directories archives are unpacked into, removed when the
+/// workspace is dropped.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    dirs: Vec<tempfile::TempDir>,
+    /// directory => the archive unpacked into it, the canonical path of the
+    /// directory is listed too
+    archives: Vec<(PathBuf, String)>,
+    /// The library directories of the unpacked distribution packages.
+    library_paths: Vec<PathBuf>,
+    /// Whether any Alpine package was unpacked.
+    alpine_packages: bool,
+    /// The AppImages unpacked, as given.
+    appimages: Vec<String>,
+    /// The archives that could not be unpacked, as given.
+    unpack_failures: Vec<String>,
+}
+
+impl Workspace {
+    /// Creates a new temporary directory to unpack the archive at `path`
+    /// into.
+    pub fn new_dir(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let dir = tempfile::Builder::new().prefix("lddcheck-").tempdir()?;
+        let unpacked = dir.path().to_path_buf();
+        let archive = path.display().to_string();
+        if let Ok(canonical) = std::fs::canonicalize(&unpacked) {
+            if canonical != unpacked {
+                self.archives.push((canonical, archive.clone()));
+            }
+        }
+        self.archives.push((unpacked.clone(), archive));
+        self.dirs.push(dir);
+        Ok(unpacked)
+    }
+
+    /// Returns the name to report a file by: `archive!member` for the files
+    /// unpacked from an archive, `<stdin>` for the file read from stdin, the
+    /// path itself otherwise.
+    pub fn display_path(&self, path: &Path) -> PathBuf {
+        self.archives
+            .iter()
+            .find_map(|(dir, archive)| {
+                let member = path.strip_prefix(dir).ok()?;
+                if member.as_os_str().is_empty() {
+                    return Some(PathBuf::from(archive));
+                }
+                Some(PathBuf::from(format!("{}!{}", archive, member.display())))
+            })
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Unpacks the zip archive at `path` into a new temporary directory.
+    fn unpack_zip(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+        let unpacked = self.new_dir(path)?;
+        archive.extract(&unpacked)?;
+        Ok(unpacked)
+    }
+
+    /// Unpacks the tarball at `path`, uncompressed or compressed with gzip,
+    /// xz or zstd, into a new temporary directory.
+    fn unpack_tar(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        let unpacked = self.new_dir(path)?;
+        tar::Archive::new(compression::decompress(&data)?.as_ref()).unpack(&unpacked)?;
+        Ok(unpacked)
+    }
+}
+
+impl Workspace {
+    /// The library directories of the unpacked distribution packages, so
+    /// that their binaries resolve the libraries shipped along.
+    pub fn library_paths(&self) -> &[PathBuf] {
+        &self.library_paths
+    }
+
+    /// Unpacks the files of the Debian package at `path`, an `ar` archive
+    /// whose `data.tar` member (compressed with gzip, xz or zstd) holds them,
+    /// into a new temporary directory.
+    fn unpack_deb(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        let Some((_, payload)) = archive::ar_members(&data)?
+            .into_iter()
+            .find(|(name, _)| name.trim_end_matches('/').starts_with("data.tar"))
+        else {
+            return Err("no data.tar in the package".into());
+        };
+        let unpacked = self.new_dir(path)?;
+        tar::Archive::new(compression::decompress(&payload)?.as_ref()).unpack(&unpacked)?;
+        self.add_package_library_paths(&unpacked);
+        Ok(unpacked)
+    }
+
+    /// Whether any of the inputs is an Alpine package, whose binaries have to
+    /// run against musl.
+    pub fn has_alpine_packages(&self) -> bool {
+        self.alpine_packages
+    }
+
+    /// Unpacks the Alpine package at `path` into a new temporary directory.
+    /// An `.apk` is a series of gzip-compressed tar segments (signature,
+    /// control and data) that read as a single tarball once decompressed.
+    fn unpack_apk(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        if !data.starts_with(GZIP_MAGIC) {
+            return Err("only the gzip-based (v2) package format is supported".into());
+        }
+        let unpacked = self.new_dir(path)?;
+        let mut archive = tar::Archive::new(flate2::read::MultiGzDecoder::new(data.as_slice()));
+        archive.set_ignore_zeros(true);
+        archive.unpack(&unpacked)?;
+        self.add_package_library_paths(&unpacked);
+        self.alpine_packages = true;
+        Ok(unpacked)
+    }
+
+    /// Unpacks the payload of the RPM package at `path` into a new temporary
+    /// directory.
+    fn unpack_rpm(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        let unpacked = self.new_dir(path)?;
+        rpm::unpack(&data, &unpacked)?;
+        self.add_package_library_paths(&unpacked);
+        Ok(unpacked)
+    }
+
+    /// Copies the file piped to stdin into a new temporary directory, so it
+    /// can be analyzed like any other file.
+    fn read_stdin(&mut self) -> Result<PathBuf, Box<dyn Error>> {
+        let dir = tempfile::Builder::new().prefix("lddcheck-").tempdir()?;
+        let path = dir.path().join("stdin");
+        std::io::copy(
+            &mut std::io::stdin().lock(),
+            &mut std::fs::File::create(&path)?,
+        )?;
+        if let Ok(canonical) = std::fs::canonicalize(&path) {
+            if canonical != path {
+                self.archives.push((canonical, STDIN_NAME.to_string()));
+            }
+        }
+        self.archives.push((path.clone(), STDIN_NAME.to_string()));
+        self.dirs.push(dir);
+        Ok(path)
+    }
+
+    /// Adds the standard library directories of a package unpacked into
+    /// `dir`, including the multiarch ones such as
+    /// `usr/lib/x86_64-linux-gnu`.
+    pub fn add_package_library_paths(&mut self, dir: &Path) {
+        for base in ["lib", "lib64", "usr/lib", "usr/lib64", "usr/local/lib"] {
+            let base = dir.join(base);
+            if !base.is_dir() {
+                continue;
+            }
+            let mut multiarch = std::fs::read_dir(&base)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_dir()
+                        && path
+                            .file_name()
+                            .is_some_and(|name| name.to_string_lossy().contains("-linux-"))
+                })
+                .collect::<Vec<PathBuf>>();
+            multiarch.sort();
+            self.library_paths.extend(multiarch);
+            self.library_paths.push(base);
+        }
+    }
+
+    /// The AppImages among the inputs.
+    pub fn appimages(&self) -> &[String] {
+        &self.appimages
+    }
+
+    /// The archives that could not be unpacked, as given.
+    pub fn unpack_failures(&self) -> &[String] {
+        &self.unpack_failures
+    }
+
+    /// Records that the archive at `path` could not be unpacked, which
+    /// leaves nothing of it to analyze.
+    fn unpack_failed(&mut self, path: &str) -> Vec<Input> {
+        self.unpack_failures.push(path.to_string());
+        Vec::new()
+    }
+
+    /// Unpacks the AppImage at `path` into a new temporary directory. A
+    /// (type 2) AppImage is an ELF runtime with a squashfs filesystem, the
+    /// AppDir, appended to it.
+    fn unpack_appimage(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let mut header = [0u8; 64];
+        std::fs::File::open(path)?.read_exact(&mut header)?;
+        if header[10] != 2 {
+            return Err("only type 2 (squashfs) AppImages are supported".into());
+        }
+        let offset = elf_end(&header).ok_or("cannot find the end of the runtime")?;
+        let mut magic = [0u8; 4];
+        let mut file = std::fs::File::open(path)?;
+        std::io::Seek::seek(&mut file, std::io::SeekFrom::Start(offset))?;
+        file.read_exact(&mut magic)?;
+        if magic != squashfs::SQUASHFS_MAGIC {
+            return Err("no squashfs filesystem after the runtime".into());
+        }
+        let unpacked = self.new_dir(path)?;
+        squashfs::unpack(path, offset, &unpacked)?;
+        self.add_package_library_paths(&unpacked);
+        self.appimages.push(path.display().to_string());
+        Ok(unpacked)
+    }
+
+    /// Unpacks the files of the Ruby gem at `path`, a tarball whose
+    /// `data.tar.gz` member holds the files, into a new temporary directory.
+    fn unpack_gem(&mut self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let mut gem = tar::Archive::new(std::fs::File::open(path)?);
+        for entry in gem.entries()? {
+            let entry = entry?;
+            if entry.path()?.as_os_str() != "data.tar.gz" {
+                continue;
+            }
+            let unpacked = self.new_dir(path)?;
+            tar::Archive::new(flate2::read::GzDecoder::new(entry)).unpack(&unpacked)?;
+            return Ok(unpacked);
+        }
+        Err("no data.tar.gz in the gem".into())
+    }
+}
+
+/// Expands a precompiled NIF artifact, a tarball holding the NIF library
+/// (and possibly a whole `priv` directory), into its ELF files.
+pub fn expand_nif_tarball(path: &str, workspace: &mut Workspace) -> Vec<Input> {
+    match workspace.unpack_tar(Path::new(path)) {
+        Ok(unpacked) => archive_members(path, &unpacked),
+        Err(_) => workspace.unpack_failed(path),
+    }
+}
+
+/// Reads a list of paths from the file at `source`, or from stdin if it is
+/// `-`. Paths are separated by newlines, or by NUL characters if `nul` is
+/// set, and empty entries are skipped.
+pub fn read_list(source: &str, nul: bool) -> std::io::Result<Vec<String>> {
+    let mut data = Vec::new();
+    if source == "-" {
+        std::io::stdin().read_to_end(&mut data)?;
+    } else {
+        data = std::fs::read(source)?;
+    }
+    let separator = if nul { b'\0' } else { b'\n' };
+    Ok(data
+        .split(|byte| *byte == separator)
+        .map(|path| String::from_utf8_lossy(path))
+        .map(|path| {
+            if nul {
+                path.to_string()
+            } else {
+                path.trim_end_matches('\r').to_string()
+            }
+        })
+        .filter(|path| !path.is_empty())
+        .collect())
+}
+
+/// Expands an input given on the command line. Glob patterns (`*`, `?`,
+/// `[...]` and `**` for any number of directories) are expanded here rather
+/// than by the shell, directories are walked recursively for ELF files,
+/// archives (zip, wheels, JARs, tarballs, gems, Debian, RPM and Alpine
+/// packages, AppImages) are unpacked into `workspace` and anything else is
+/// taken as is.
+pub fn expand(path: &str, workspace: &mut Workspace) -> Vec<Input> {
+    if is_glob_pattern(path) {
+        let matches = glob::glob(path)
+            .map(|paths| paths.filter_map(|path| path.ok()).collect::<Vec<PathBuf>>())
+            .unwrap_or_default();
+        if !matches.is_empty() {
+            return matches
+                .into_iter()
+                .flat_map(|path| {
+                    let path = path.display().to_string();
+                    let mut inputs = expand_path(&path, workspace);
+                    for input in inputs.iter_mut().filter(|input| input.relative.is_none()) {
+                        input.relative = Some(path.clone());
+                    }
+                    inputs
+                })
+                .collect();
+        }
+    }
+    expand_path(path, workspace)
+}
+
+/// Whether `path` is a glob pattern rather than the name of an existing file.
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '[']) && !Path::new(path).exists()
+}
+
+/// Expands a path that is not a glob pattern.
+fn expand_path(path: &str, workspace: &mut Workspace) -> Vec<Input> {
+    if path == "-" {
+        return match workspace.read_stdin() {
+            Ok(stdin) => vec![Input {
+                path: stdin.display().to_string(),
+                relative: None,
+                archive: None,
+            }],
+            Err(err) => {
+                eprintln!("cannot read stdin: {}", err);
+                Vec::new()
+            }
+        };
+    }
+    let dir = Path::new(path);
+    if is_zip_archive(dir) {
+        match workspace.unpack_zip(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if is_tarball(dir) {
+        match workspace.unpack_tar(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if is_appimage(dir) {
+        match workspace.unpack_appimage(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if is_deb(dir) {
+        match workspace.unpack_deb(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if is_rpm(dir) {
+        match workspace.unpack_rpm(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if is_apk(dir) {
+        match workspace.unpack_apk(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if is_gem(dir) {
+        match workspace.unpack_gem(dir) {
+            Ok(unpacked) => return archive_members(path, &unpacked),
+            Err(_) => return workspace.unpack_failed(path),
+        }
+    }
+    if !dir.is_dir() {
+        return vec![Input {
+            path: path.to_string(),
+            relative: None,
+            archive: None,
+        }];
+    }
+    // the files of an installed gem are reported as a whole too
+    let package = is_installed_gem(dir).then(|| path.to_string());
+    let mut found = Vec::new();
+    walk(dir, &mut found);
+    found
+        .into_iter()
+        .map(|file| {
+            let relative = file.strip_prefix(dir).ok();
+            Input {
+                archive: package
+                    .clone()
+                    .or_else(|| prebuild_platform(dir, relative?)),
+                relative: relative.map(|relative| relative.display().to_string()),
+                path: file.display().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// For the `.node` addons of an npm package laid out by prebuildify
+/// (`prebuilds/<platform>-<arch>/*.node`), returns the platform directory,
+/// so that the requirements of each platform are reported as a whole.
+fn prebuild_platform(dir: &Path, relative: &Path) -> Option<String> {
+    let components = relative.components().collect::<Vec<_>>();
+    let index = components
+        .iter()
+        .position(|component| component.as_os_str() == "prebuilds")?;
+    // the addon itself has to be inside the platform directory
+    if index + 2 >= components.len() {
+        return None;
+    }
+    let platform = components[..index + 2].iter().collect::<PathBuf>();
+    Some(dir.join(platform).display().to_string())
+}
+
+/// Whether `path` is a tarball, plain or compressed.
+fn is_tarball(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    TARBALL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) && path.is_file()
+}
+
+/// Whether the file at `path` is an AppImage, by the magic in its ELF
+/// header.
+fn is_appimage(path: &Path) -> bool {
+    let mut header = [0u8; 11];
+    path.is_file()
+        && std::fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut header))
+            .is_ok()
+        && header.starts_with(ELF_MAGIC)
+        && &header[8..10] == APPIMAGE_MAGIC
+}
+
+/// Returns the end of an ELF file from its header: the end of the section
+/// header table, which comes last.
+fn elf_end(header: &[u8; 64]) -> Option<u64> {
+    let little_endian = header[5] == 1;
+    let read = |offset: usize, size: usize| {
+        let bytes = &header[offset..offset + size];
+        let mut value = 0u64;
+        for index in 0..size {
+            let byte = if little_endian {
+                bytes[size - 1 - index]
+            } else {
+                bytes[index]
+            };
+            value = (value << 8) | u64::from(byte);
+        }
+        value
+    };
+    let (shoff, shentsize, shnum) = match header[4] {
+        // ELFCLASS32
+        1 => (read(0x20, 4), read(0x2e, 2), read(0x30, 2)),
+        // ELFCLASS64
+        2 => (read(0x28, 8), read(0x3a, 2), read(0x3c, 2)),
+        _ => return None,
+    };
+    // a header pointing past the end of the address space is malformed
+    shentsize.checked_mul(shnum)?.checked_add(shoff)
+}
+
+/// Whether `path` is a Debian package.
+fn is_deb(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "deb") && path.is_file()
+}
+
+/// Whether `path` is an RPM package.
+fn is_rpm(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rpm") && path.is_file()
+}
+
+/// Whether `path` is an Alpine package.
+fn is_apk(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "apk") && path.is_file()
+}
+
+/// Whether `path` is a built Ruby gem.
+fn is_gem(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gem") && path.is_file()
+}
+
+/// Whether `dir` is an installed gem, `gems/<name>-<version>` in a gem
+/// home.
+fn is_installed_gem(dir: &Path) -> bool {
+    let in_gem_home = std::fs::canonicalize(dir)
+        .ok()
+        .and_then(|dir| dir.parent().map(|parent| parent.ends_with("gems")))
+        .unwrap_or(false);
+    in_gem_home && dir.join("lib").is_dir()
+}
+
+/// Whether `path` is a zip archive that may hold native libraries: a Python
+/// wheel (extension modules and, in `<package>.libs`, the libraries vendored
+/// by auditwheel) or a JAR with JNI libraries among its resources (e.g. in
+/// `linux-x86-64/`).
+fn is_zip_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "whl" || ext == "jar" || ext == "zip")
+        && path.is_file()
+}
+
+/// Returns the ELF files of an archive unpacked into `dir`, named
+/// `archive!member`.
+pub fn archive_members(archive: &str, dir: &Path) -> Vec<Input> {
+    let mut found = Vec::new();
+    walk(dir, &mut found);
+    found
+        .into_iter()
+        .map(|file| Input {
+            relative: file
+                .strip_prefix(dir)
+                .ok()
+                .map(|member| format!("{}!{}", archive, member.display())),
+            path: file.display().to_string(),
+            archive: Some(archive.to_string()),
+        })
+        .collect()
+}
+
+/// Collects the ELF files under `dir`, in file name order. Symlinks are not
+/// followed, the files they point to inside the tree are found anyway.
+fn walk(dir: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    for path in paths {
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            walk(&path, found);
+        } else if metadata.is_file() && is_elf_file(&path) {
+            found.push(path);
+        }
+    }
+}
+
+/// Whether the file at `path` starts with the ELF magic.
+pub fn is_elf_file(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && magic == ELF_MAGIC
+}
+
+/// The glob patterns given with `--exclude`.
+#[derive(Debug, Default)]
+pub struct Exclusions {
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Exclusions {
+    pub fn new(patterns: &[String]) -> Result<Exclusions, Box<dyn Error>> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .map_err(|err| format!("invalid --exclude pattern {}: {}", pattern, err))
+            })
+            .collect::<Result<Vec<glob::Pattern>, String>>()?;
+        Ok(Exclusions { patterns })
+    }
+
+    /// Whether the path or the file name matches any of the patterns.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let name = path.file_name().map(Path::new);
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_path(path) || name.is_some_and(|name| pattern.matches_path(name))
+        })
+    }
+
+    /// Like `is_excluded`, also matching the path relative to the directory
+    /// the input was found in.
+    pub fn is_excluded_input(&self, input: &Input) -> bool {
+        self.is_excluded(Path::new(&input.path))
+            || input.relative.as_ref().is_some_and(|relative| {
+                self.patterns
+                    .iter()
+                    .any(|pattern| pattern.matches_path(Path::new(relative)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_section_headers_past_the_address_space() {
+        let mut header = [0u8; 64];
+        header[..4].copy_from_slice(ELF_MAGIC);
+        // ELFCLASS64, little endian
+        header[4] = 2;
+        header[5] = 1;
+        header[0x28..0x30].copy_from_slice(&0x1000u64.to_le_bytes());
+        header[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        header[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+        assert_eq!(elf_end(&header), Some(0x1000 + 64 * 3));
+
+        header[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(elf_end(&header), None);
+    }
+}
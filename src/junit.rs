@@ -0,0 +1,58 @@
+use crate::violations::Violation;
+
+/// Returns a JUnit XML report with a test case per input, failed by the
+/// violations of the files in its dependency closure, so CI test panels
+/// (Jenkins, GitLab) show every input as passed or failed.
+pub fn report(cases: &[(String, Vec<&Violation>)]) -> String {
+    let failures = cases
+        .iter()
+        .filter(|(_, violations)| !violations.is_empty())
+        .count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"lddcheck\" tests=\"{}\" failures=\"{}\">\n",
+        cases.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"lddcheck\" tests=\"{}\" failures=\"{}\">\n",
+        cases.len(),
+        failures
+    ));
+    for (name, violations) in cases {
+        xml.push_str(&format!(
+            "    <testcase classname=\"lddcheck\" name=\"{}\"",
+            escape(name)
+        ));
+        let Some(first) = violations.first() else {
+            xml.push_str("/>\n");
+            continue;
+        };
+        let details = violations
+            .iter()
+            .map(|violation| match &violation.file {
+                Some(file) => format!("{}: {}", file.display(), violation.message),
+                None => violation.message.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        xml.push_str(">\n");
+        xml.push_str(&format!(
+            "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+            first.rule,
+            escape(&first.message),
+            escape(&details)
+        ));
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Escapes text for an XML attribute or element.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,111 @@
+/// The GCC release that first shipped each `GLIBCXX_` symbol version, as
+/// listed in the libstdc++ ABI policy.
+const GLIBCXX_RELEASES: &[(&str, &str)] = &[
+    ("3.4", "3.4.0"),
+    ("3.4.1", "3.4.1"),
+    ("3.4.2", "3.4.2"),
+    ("3.4.3", "3.4.3"),
+    ("3.4.4", "4.0.0"),
+    ("3.4.5", "4.0.1"),
+    ("3.4.6", "4.0.2"),
+    ("3.4.7", "4.0.3"),
+    ("3.4.8", "4.1.1"),
+    ("3.4.9", "4.2.0"),
+    ("3.4.10", "4.3.0"),
+    ("3.4.11", "4.4.0"),
+    ("3.4.12", "4.4.1"),
+    ("3.4.13", "4.4.2"),
+    ("3.4.14", "4.5.0"),
+    ("3.4.15", "4.6.0"),
+    ("3.4.16", "4.6.1"),
+    ("3.4.17", "4.7.0"),
+    ("3.4.18", "4.8.0"),
+    ("3.4.19", "4.8.3"),
+    ("3.4.20", "4.9.0"),
+    ("3.4.21", "5.1.0"),
+    ("3.4.22", "6.1.0"),
+    ("3.4.23", "7.1.0"),
+    ("3.4.24", "7.2.0"),
+    ("3.4.25", "8.1.0"),
+    ("3.4.26", "9.1.0"),
+    ("3.4.27", "9.2.0"),
+    ("3.4.28", "9.3.0"),
+    ("3.4.29", "11.1.0"),
+    ("3.4.30", "12.1.0"),
+    ("3.4.31", "13.1.0"),
+    ("3.4.32", "13.2.0"),
+    ("3.4.33", "14.1.0"),
+    ("3.4.34", "15.1.0"),
+];
+
+/// The GCC release that first shipped each `CXXABI_` symbol version.
+const CXXABI_RELEASES: &[(&str, &str)] = &[
+    ("1.3", "3.4.0"),
+    ("1.3.1", "4.0.0"),
+    ("1.3.2", "4.3.0"),
+    ("1.3.3", "4.4.0"),
+    ("1.3.4", "4.5.0"),
+    ("1.3.5", "4.6.0"),
+    ("1.3.6", "4.7.0"),
+    ("1.3.7", "4.8.0"),
+    ("1.3.8", "4.9.0"),
+    ("1.3.9", "5.1.0"),
+    ("1.3.10", "6.1.0"),
+    ("1.3.11", "7.1.0"),
+    ("1.3.12", "9.1.0"),
+    ("1.3.13", "11.1.0"),
+    ("1.3.14", "12.1.0"),
+    ("1.3.15", "13.1.0"),
+    ("1.3.16", "14.1.0"),
+];
+
+/// The GCC release that first shipped each `GCC_` symbol version of
+/// libgcc_s (the unwinder and the soft-float and atomic helpers).
+const LIBGCC_RELEASES: &[(&str, &str)] = &[
+    ("3.0", "3.0.0"),
+    ("3.3", "3.3.0"),
+    ("3.3.1", "3.3.1"),
+    ("3.3.4", "3.3.4"),
+    ("3.4", "3.4.0"),
+    ("3.4.2", "3.4.2"),
+    ("3.4.4", "3.4.4"),
+    ("3.5", "4.0.0"),
+    ("4.0.0", "4.0.0"),
+    ("4.2.0", "4.2.0"),
+    ("4.3.0", "4.3.0"),
+    ("4.4.0", "4.4.0"),
+    ("4.5.0", "4.5.0"),
+    ("4.6.0", "4.6.0"),
+    ("4.7.0", "4.7.0"),
+    ("4.8.0", "4.8.0"),
+    ("7.0.0", "7.1.0"),
+    ("12.0.0", "12.1.0"),
+    ("13.0.0", "13.1.0"),
+    ("14.0.0", "14.1.0"),
+];
+
+/// Returns the first GCC release whose libstdc++ provides the given
+/// `GLIBCXX` or `CXXABI` version, or whose libgcc_s provides the given
+/// `GCC` version, if it is a known one.
+pub fn minimum_gcc(namespace: &str, version: &str) -> Option<&'static str> {
+    let releases = match namespace {
+        "GLIBCXX" => GLIBCXX_RELEASES,
+        "CXXABI" => CXXABI_RELEASES,
+        "GCC" => LIBGCC_RELEASES,
+        _ => return None,
+    };
+    releases
+        .iter()
+        .find(|(known, _)| *known == version)
+        .map(|(_, gcc)| *gcc)
+}
+
+/// Returns the newest of the given GCC releases.
+pub fn newest_gcc<'a>(releases: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    releases.max_by_key(|release| {
+        release
+            .split('.')
+            .map(|part| part.parse::<u32>().unwrap_or(0))
+            .collect::<Vec<u32>>()
+    })
+}
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+/// Whether the given bytes look like a GNU ld script rather than an object.
+///
+/// Distributions commonly ship `libc.so`, `libm.so` and friends as small
+/// text files that point the linker at the real shared objects.
+pub fn is_linker_script(data: &[u8]) -> bool {
+    if data.starts_with(b"\x7fELF") || data.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(data) {
+        Ok(text) => !parse_linker_script(text).is_empty(),
+        Err(_) => false,
+    }
+}
+
+/// Returns the files referenced by the `GROUP` and `INPUT` directives of a
+/// linker script, including the ones nested in `AS_NEEDED`.
+pub fn parse_linker_script(text: &str) -> Vec<String> {
+    let text = strip_comments(text);
+    let mut files = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(start) = find_directive(rest) {
+        rest = &rest[start..];
+        let Some(open) = rest.find('(') else {
+            break;
+        };
+        let mut depth = 0;
+        let mut end = rest.len();
+        for (index, c) in rest[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = open + index;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        files.extend(
+            rest[open + 1..end]
+                .split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')')
+                .filter(|token| !token.is_empty() && *token != "AS_NEEDED")
+                .map(|token| token.to_string()),
+        );
+        rest = &rest[end..];
+    }
+    files
+}
+
+/// Resolves a file referenced by a linker script to a path on disk.
+///
+/// Absolute paths are looked up under `root`, bare file names next to the
+/// script itself, and `-lname` references in `search_dirs` as ld does
+/// (`libname.so` before `libname.a` in every directory), then next to the
+/// script.
+pub fn resolve_linker_script_input(
+    reference: &str,
+    script: &Path,
+    root: &Path,
+    search_dirs: &[PathBuf],
+) -> PathBuf {
+    let dir = script.parent().unwrap_or(Path::new(""));
+    if let Some(name) = reference.strip_prefix("-l") {
+        let candidates = search_dirs
+            .iter()
+            .map(PathBuf::as_path)
+            .chain([dir])
+            .flat_map(|dir| {
+                [
+                    dir.join(format!("lib{}.so", name)),
+                    dir.join(format!("lib{}.a", name)),
+                ]
+            });
+        for candidate in candidates {
+            if candidate.exists() {
+                return candidate;
+            }
+        }
+        return dir.join(format!("lib{}.a", name));
+    }
+    match reference.strip_prefix('/') {
+        Some(relative) => root.join(relative),
+        None => dir.join(reference),
+    }
+}
+
+fn find_directive(text: &str) -> Option<usize> {
+    ["GROUP", "INPUT"]
+        .iter()
+        .filter_map(|directive| {
+            text.match_indices(directive)
+                .find(|(index, _)| {
+                    let after = text[index + directive.len()..].trim_start();
+                    let before = text[..*index].chars().last();
+                    after.starts_with('(')
+                        && !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                })
+                .map(|(index, _)| index)
+        })
+        .min()
+}
+
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        match rest[start + 2..].find("*/") {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => rest = "",
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_libraries_in_the_search_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let lib = root.path().join("lib");
+        let usr_lib = root.path().join("usr/lib");
+        std::fs::create_dir_all(&lib).unwrap();
+        std::fs::create_dir_all(&usr_lib).unwrap();
+        std::fs::write(usr_lib.join("libfoo.so"), b"").unwrap();
+        let script = lib.join("libbar.so");
+        std::fs::write(&script, "GROUP ( AS_NEEDED ( -lfoo ) )").unwrap();
+
+        let search_dirs = [lib.clone(), usr_lib.clone()];
+        let inputs = parse_linker_script(&std::fs::read_to_string(&script).unwrap());
+        assert_eq!(inputs, ["-lfoo"]);
+        assert_eq!(
+            resolve_linker_script_input(&inputs[0], &script, root.path(), &search_dirs),
+            usr_lib.join("libfoo.so")
+        );
+        // without the directory it is only looked for next to the script
+        assert_eq!(
+            resolve_linker_script_input(&inputs[0], &script, root.path(), &[]),
+            lib.join("libfoo.a")
+        );
+    }
+}
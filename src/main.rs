@@ -1,14 +1,67 @@
-use clap::Parser;
+mod abidb;
+mod android;
+mod archive;
+mod color;
+mod compression;
+mod conda;
+mod csv;
+mod debuginfo;
+mod debuginfod;
+mod deprecated;
+mod diff;
+mod distro;
+mod flatpak;
+mod github;
+mod gitlab;
+mod graph;
+mod html;
+mod image;
+mod inputs;
+mod junit;
+mod libstdcxx;
+mod linker_script;
+mod manifest;
+mod markdown;
+mod msgpack;
+mod notes;
+mod policy;
+mod policy_file;
+mod process;
+mod proto;
+mod registry;
+mod repair;
+mod rpm;
+mod sarif;
+mod sbom;
+mod schema;
+mod snap;
+mod squashfs;
+mod symbols;
+mod system;
+mod table;
+mod template;
+mod violations;
+mod yaml;
+
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use elf::abi::{
+    DF_1_PIE, DT_FLAGS_1, DT_SONAME, EI_CLASS, EI_DATA, ELFDATA2MSB, ET_REL, PT_DYNAMIC, PT_INTERP,
+    STB_WEAK, STT_GNU_IFUNC, VER_FLG_BASE,
+};
 use elf::endian::AnyEndian;
 use elf::parse::ParsingTable;
 use elf::string_table::StringTable;
-use elf::symbol::Symbol;
+use elf::symbol::{Symbol, SymbolTable};
 use elf::ElfBytes;
-use lddtree::{DependencyAnalyzer, Library};
-use serde_json;
-use std::collections::{HashMap, HashSet};
+use lddtree::ld_so_conf::parse_ld_so_conf;
+use lddtree::{DependencyAnalyzer, DependencyTree, Library};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 use strum::{Display, EnumCount, EnumDiscriminants, EnumString, VariantNames};
 use strum_macros::EnumIs;
@@ -67,6 +120,107 @@ enum StdoutFormat {
     #[strum(serialize = "text")]
     #[default]
     Text,
+    #[strum(serialize = "yaml")]
+    Yaml,
+    #[strum(serialize = "csv")]
+    Csv,
+    #[strum(serialize = "tsv")]
+    Tsv,
+    #[strum(serialize = "ndjson")]
+    Ndjson,
+    #[strum(serialize = "sarif")]
+    Sarif,
+    #[strum(serialize = "github")]
+    Github,
+    #[strum(serialize = "markdown")]
+    Markdown,
+    #[strum(serialize = "mermaid")]
+    Mermaid,
+    #[strum(serialize = "spdx")]
+    Spdx,
+    #[strum(serialize = "cyclonedx")]
+    Cyclonedx,
+    #[strum(serialize = "template")]
+    Template,
+    #[strum(serialize = "table")]
+    Table,
+    #[strum(serialize = "proto")]
+    Proto,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumString,
+    EnumCount,
+    EnumDiscriminants,
+    EnumIs,
+    Display,
+    VariantNames,
+    Default,
+)]
+enum ColorChoice {
+    #[strum(serialize = "auto")]
+    #[default]
+    Auto,
+    #[strum(serialize = "always")]
+    Always,
+    #[strum(serialize = "never")]
+    Never,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumString,
+    EnumCount,
+    EnumDiscriminants,
+    EnumIs,
+    Display,
+    VariantNames,
+    Default,
+)]
+enum ErrorFormat {
+    #[strum(serialize = "text")]
+    #[default]
+    Text,
+    #[strum(serialize = "json")]
+    Json,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumString,
+    EnumCount,
+    EnumDiscriminants,
+    EnumIs,
+    Display,
+    VariantNames,
+    Default,
+)]
+enum CheckFormat {
+    #[strum(serialize = "text")]
+    #[default]
+    Text,
+    #[strum(serialize = "json")]
+    Json,
+    #[strum(serialize = "sarif")]
+    Sarif,
+    #[strum(serialize = "github")]
+    Github,
 }
 
 #[derive(
@@ -91,6 +245,14 @@ enum PrintError {
     CannotRead,
     #[strum(serialize = "not-found")]
     NotFound,
+    #[strum(serialize = "wrong-arch")]
+    WrongArch,
+    #[strum(serialize = "soname-mismatch")]
+    SonameMismatch,
+    #[strum(serialize = "bad-interpreter")]
+    BadInterpreter,
+    #[strum(serialize = "cannot-unpack")]
+    CannotUnpack,
     #[strum(serialize = "none")]
     None,
     #[strum(serialize = "all")]
@@ -98,24 +260,322 @@ enum PrintError {
     All,
 }
 
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumString,
+    EnumCount,
+    EnumDiscriminants,
+    EnumIs,
+    Display,
+    VariantNames,
+    Default,
+)]
+enum TargetLibc {
+    #[strum(serialize = "glibc")]
+    #[default]
+    Glibc,
+    #[strum(serialize = "musl")]
+    Musl,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Analyze the given files and the libraries they load, what lddcheck
+    /// does without a command
+    Scan {
+        #[command(flatten)]
+        input: Box<InputArgs>,
+    },
+    /// Generate a versioned symbol database from glibc's abilist files or
+    /// from installed libraries, for use with --abidb
+    GenAbidb {
+        #[arg(
+            required = true,
+            help = "abilist files, directories holding them, or shared libraries"
+        )]
+        inputs: Vec<String>,
+
+        #[arg(short, long, help = "Where to write the database")]
+        output: String,
+    },
+    /// Record every versioned symbol the libraries of the sysroot (--root)
+    /// export
+    Manifest {
+        #[arg(short, long, help = "Where to write the manifest")]
+        output: String,
+    },
+    /// Print the JSON Schema of the --envelope JSON document
+    Schema {
+        #[arg(long, help = "Print the protobuf definition of --stdout proto instead")]
+        proto: bool,
+    },
+    /// Check that a manifest (see manifest) provides every versioned symbol
+    /// the given files import, or that they follow a policy file
+    #[command(group(clap::ArgGroup::new("rules").required(true).args(["against", "policy"])))]
+    Check {
+        #[arg(long, help = "The manifest to check against")]
+        against: Option<String>,
+
+        #[arg(
+            long,
+            help = "The policy file (TOML) to check against: the newest version allowed in every namespace, the libraries that may be loaded from outside the files, the RPATHs and RUNPATHs allowed and the exceptions"
+        )]
+        policy: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            requires = "policy",
+            help = "Additional LD_LIBRARY_PATH to use when resolving paths"
+        )]
+        ld_library_path: Vec<String>,
+
+        #[arg(long = "stdout", ignore_case = true, requires = "policy", value_parser = clap_enum_variants!(CheckFormat), help = "The format to print the violations of the policy file in [default: text]")]
+        stdout_format: Option<CheckFormat>,
+
+        #[arg(required = true, help = "The files to check")]
+        paths: Vec<String>,
+    },
+    /// Copy the libraries a policy does not allow next to the given files
+    /// and point the files at the copies, like auditwheel repair
+    Repair {
+        #[arg(
+            long,
+            help = "The PyPA platform tag policy deciding which libraries may be loaded from the system, e.g. manylinux_2_28"
+        )]
+        policy: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Additional LD_LIBRARY_PATH to use when resolving paths"
+        )]
+        ld_library_path: Vec<String>,
+
+        #[arg(required = true, help = "The files to repair")]
+        paths: Vec<String>,
+    },
+    /// Analyze the programs of a container image, resolving their libraries
+    /// in the image
+    Image {
+        #[arg(
+            required_unless_present = "from_tar",
+            conflicts_with = "from_tar",
+            help = "The image to pull from its registry, e.g. ghcr.io/org/app:tag or ubuntu:22.04"
+        )]
+        reference: Option<String>,
+
+        #[arg(
+            long = "from-tar",
+            help = "Read the image from a tarball saved by docker save, or an OCI image layout tarball"
+        )]
+        from_tar: Option<String>,
+
+        #[arg(
+            long,
+            help = "Analyze every ELF file in the image rather than the programs its ENTRYPOINT and CMD run"
+        )]
+        all: bool,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Check that the binaries of a flatpak build only need what its runtime
+    /// provides
+    Flatpak {
+        #[arg(help = "The build directory, or the deploy directory of an installed application")]
+        dir: String,
+
+        #[arg(
+            long,
+            help = "The runtime to check against, e.g. org.freedesktop.Platform/x86_64/23.08 [default: the one in the metadata]"
+        )]
+        runtime: Option<String>,
+
+        #[arg(
+            long = "runtime-dir",
+            help = "The files of the runtime, when it is not installed"
+        )]
+        runtime_dir: Option<String>,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Check which packages of a conda environment need a newer glibc than
+    /// conda-forge's sysroot
+    CondaEnv {
+        #[arg(help = "The prefix of the environment")]
+        prefix: String,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Analyze the executable and every library a running process has
+    /// loaded, including those loaded with dlopen
+    Pid {
+        #[arg(help = "The ID of the process")]
+        pid: u32,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Scan every ELF file of an extracted root filesystem against its own
+    /// libraries and summarize the glibc version each one needs
+    Rootfs {
+        #[arg(help = "The root directory of the filesystem")]
+        dir: String,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Check that the binaries of a snap only need what the snap and its base
+    /// provide
+    Snap {
+        #[arg(help = "The .snap file, or the directory of its files (e.g. prime/)")]
+        snap: String,
+
+        #[arg(
+            long,
+            help = "The files of the base, or its .snap file, when it is not installed [default: /snap/<base>/current]"
+        )]
+        base: Option<String>,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Print how a version or a symbol is required: the input, the libraries
+    /// it loads through DT_NEEDED down to the one importing the symbol
+    Why {
+        #[arg(help = "The version, e.g. GLIBC_2.33, or the symbol, e.g. memfd_create")]
+        query: String,
+
+        #[command(flatten)]
+        input: Box<InputArgs>,
+    },
+    /// Compare what two builds of a binary, or two artifacts, require: the
+    /// versioned symbols added and removed and the libraries loaded
+    Diff {
+        #[arg(
+            required_unless_present = "baseline",
+            conflicts_with = "baseline",
+            requires = "new",
+            help = "The old file, directory or archive"
+        )]
+        old: Option<String>,
+
+        #[arg(help = "The new file, directory or archive")]
+        new: Option<String>,
+
+        #[arg(
+            long,
+            requires = "paths",
+            help = "Compare the --paths to this report (--envelope JSON) instead, and fail only when they require more than it does"
+        )]
+        baseline: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            requires = "baseline",
+            conflicts_with = "old",
+            help = "The path(s) to compare to the --baseline, as for the analysis"
+        )]
+        paths: Vec<String>,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+    /// Print the libraries the given files load as a tree, like lddtree,
+    /// with the path and soname of each one and the newest glibc version it
+    /// imports
+    Tree {
+        #[arg(
+            required = true,
+            help = "The files to print the tree of, directories are searched recursively as for --paths"
+        )]
+        paths: Vec<String>,
+
+        #[command(flatten)]
+        scan: Box<ScanArgs>,
+    },
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "The root path to use when resolving paths [default: /]"
+    )]
+    root: Option<String>,
+
+    // without a command, the options of `scan`, as before there were
+    // commands
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+// what to analyze, and how
+#[derive(clap::Args, Debug, Clone)]
+struct InputArgs {
     #[arg(
         short,
         long,
-        required = true,
-        help = "The path(s) to the file(s) for analysis"
+        required_unless_present_any = ["paths_from", "nif_tarballs", "from_path", "from_systemd"],
+        help = "The path(s) to the file(s) for analysis, directories are searched recursively for ELF files and glob patterns are expanded, - reads a file from stdin"
     )]
     paths: Vec<String>,
 
     #[arg(
-        long,
-        default_value = "/",
-        help = "The root path to use when resolving paths"
+        long = "paths-from",
+        help = "Also analyze the paths listed in this file, one per line, or read them from stdin with -"
+    )]
+    paths_from: Option<String>,
+
+    #[arg(
+        short = '0',
+        long = "null",
+        requires = "paths_from",
+        help = "The paths in --paths-from are separated by NUL characters, as printed by find -print0"
+    )]
+    null: bool,
+
+    #[arg(
+        long = "nif-tarball",
+        help = "Also analyze the libraries in this precompiled NIF tarball (.tar.gz), reporting the artifact as a whole"
+    )]
+    nif_tarballs: Vec<String>,
+
+    #[arg(
+        long = "from-path",
+        help = "Also analyze every executable in the directories of PATH, looked up under --root"
+    )]
+    from_path: bool,
+
+    #[arg(
+        long = "from-systemd",
+        help = "Also analyze every executable the ExecStart= lines of the systemd service units under --root run"
     )]
-    root: String,
+    from_systemd: bool,
 
+    #[command(flatten)]
+    scan: ScanArgs,
+}
+
+// the options of an analysis, shared by the commands that run one (a doc
+// comment would end up as the about text of those commands)
+#[derive(clap::Args, Debug, Clone)]
+struct ScanArgs {
     #[arg(
         short,
         long,
@@ -131,15 +591,97 @@ struct Args {
     )]
     scopes: Vec<String>,
 
+    #[arg(
+        long = "exclude",
+        help = "Skip the inputs and libraries whose path, path relative to the directory searched or file name matches this glob pattern"
+    )]
+    excludes: Vec<String>,
+
     #[arg(long="stdout", default_value_t, ignore_case = true, value_parser = clap_enum_variants!(StdoutFormat), help="The format to use when printing to stdout")]
     stdout_format: StdoutFormat,
 
+    #[arg(
+        long,
+        value_parser = template::Template::parse,
+        help = "Print a line per requirement shaped by this template instead of the text output, e.g. '{version}\\t{function}\\t{file}', with the fields {namespace}, {version}, {function}, {file}, {referenced_by}, {provided_by} and {weak}"
+    )]
+    format: Option<template::Template>,
+
     #[arg(long = "save-json-to", help = "Save the json to a file")]
     save_json_to: Option<String>,
 
+    #[arg(long = "save-yaml-to", help = "Save the json as yaml to a file")]
+    save_yaml_to: Option<String>,
+
+    #[arg(
+        long = "save-msgpack-to",
+        help = "Save the json as MessagePack to a file"
+    )]
+    save_msgpack_to: Option<String>,
+
+    #[arg(
+        long = "save-dot-to",
+        help = "Save the dependency graph in the Graphviz DOT language to a file, with the newest glibc version each file requires"
+    )]
+    save_dot_to: Option<String>,
+
+    #[arg(
+        long = "save-html-to",
+        help = "Save a single-file HTML report to a file, with collapsible sections per version and per file and a search box"
+    )]
+    save_html_to: Option<String>,
+
+    #[arg(
+        long = "save-codequality-to",
+        help = "Save a GitLab Code Quality report to a file, with the violations as major issues and the glibc version each input requires as info"
+    )]
+    save_codequality_to: Option<String>,
+
+    #[arg(
+        long = "save-junit-to",
+        help = "Save a JUnit XML report to a file, with a test case per input that fails on the violations of its dependency closure"
+    )]
+    save_junit_to: Option<String>,
+
+    #[arg(
+        long,
+        help = "Print how many distinct symbols require each glibc version, as a bar chart on a terminal"
+    )]
+    stats: bool,
+
+    #[arg(
+        long = "why-max",
+        help = "Print what requires the highest version of every namespace, the files importing symbols at that version and those symbols"
+    )]
+    why_max: bool,
+
+    #[arg(
+        long = "find-symbol",
+        help = "Print every file analyzed importing this symbol and at what version (can be repeated)"
+    )]
+    find_symbols: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Print a summary first: the number of inputs, files and libraries analyzed, the highest version of every namespace, the errors by kind and the runtime"
+    )]
+    summary: bool,
+
+    #[arg(
+        long,
+        help = "Report what all the inputs require together rather than what each one requires, in the json keyed by input"
+    )]
+    merge: bool,
+
     #[arg(long = "pretty-json", help = "Pretty print the json")]
     pretty_json: bool,
 
+    #[arg(
+        long,
+        help = "Wrap the json (and yaml) in a versioned envelope with the inputs, every requirement and the errors, see the schema command"
+    )]
+    envelope: bool,
+
     #[arg(
         long,
         default_value = "1",
@@ -150,240 +692,4848 @@ struct Args {
     #[arg(long="detail-level", default_value_t, ignore_case = true, value_parser = clap_enum_variants!(DetailLevel), help="The detail level to use when printing to stdout")]
     detail_level: DetailLevel,
 
+    #[arg(long="color", default_value_t, ignore_case = true, value_parser = clap_enum_variants!(ColorChoice), help="When to color the text output, auto colors it on terminals unless NO_COLOR is set")]
+    color: ColorChoice,
+
     #[arg(long="print-error", default_value_t, ignore_case = true, value_parser = clap_enum_variants!(PrintError), help="If and what errors to print to stderr")]
     print_error: PrintError,
-}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let parsed_args = Args::parse();
-    let mut wants: HashMap<String, HashMap<String, HashSet<PathBuf>>> = HashMap::new();
-    let mut errored: HashMap<PathBuf, (String, HashSet<String>)> = HashMap::new();
+    #[arg(long="error-format", default_value_t, ignore_case = true, value_parser = clap_enum_variants!(ErrorFormat), help="How to print the errors to stderr, json prints an object with the kind, path and referenced_by of each error per line")]
+    error_format: ErrorFormat,
 
-    let root = PathBuf::try_from(parsed_args.root).unwrap_or(PathBuf::from("/"));
-    let lib_paths: Vec<_> = parsed_args
-        .ld_library_path
-        .iter()
-        .map(|s| PathBuf::from(s))
-        .collect::<Vec<PathBuf>>();
-    let scopes: Vec<_> = parsed_args
-        .scopes
-        .iter()
-        .map(|s| PathBuf::from(s))
-        .collect::<Vec<PathBuf>>();
-    let analyzer = DependencyAnalyzer::new(root.clone()).library_paths(lib_paths.clone());
-    let mut visited = HashSet::new();
+    #[arg(long="target-libc", default_value_t, ignore_case = true, value_parser = clap_enum_variants!(TargetLibc), help="The C library the binaries should run against, musl also reports the glibc-only symbols they use")]
+    target_libc: TargetLibc,
 
-    for pathname in &parsed_args.paths {
-        let deps = analyzer.clone().analyze(&pathname)?;
-        for needed in deps.needed {
-            gather_deps_required_libc_version(
-                &pathname,
-                &needed,
-                &deps.libraries,
-                &scopes,
-                &mut wants,
-                &mut visited,
-                &mut errored,
-            );
-        }
-    }
+    #[arg(
+        long = "distros",
+        help = "Also print the first release of common distributions that ships each reported glibc version"
+    )]
+    distros: bool,
 
-    let wants_json = if parsed_args.detail_level.is_version() {
-        let mut versions = wants.keys().collect::<Vec<&String>>();
-        versions.sort();
-        versions.reverse();
-        let user_wants = versions
-            .iter()
-            .take(parsed_args.versions)
-            .map(|x| *x)
-            .collect::<Vec<&String>>();
+    #[arg(
+        long = "distro-table",
+        help = "Read the glibc version => distribution release table used by --distros from this JSON file"
+    )]
+    distro_table: Option<String>,
 
-        if parsed_args.stdout_format.is_text() {
-            for version in &user_wants {
-                println!("{}", version);
-            }
-        }
+    #[arg(
+        long = "abidb",
+        help = "Report required GLIBC symbol versions that this database (see gen-abidb) does not list"
+    )]
+    abidb: Option<String>,
 
-        if parsed_args.pretty_json {
-            Some(serde_json::to_string_pretty(&user_wants)?)
-        } else {
-            Some(serde_json::to_string(&user_wants)?)
-        }
-    } else if parsed_args.detail_level.is_function() {
-        let mut user_wants: HashMap<String, HashSet<String>> = HashMap::new();
-        let mut versions = wants.keys().collect::<Vec<&String>>();
-        versions.sort();
-        versions.reverse();
-        let versions = versions
-            .iter()
-            .take(parsed_args.versions)
-            .map(|x| *x)
-            .collect::<Vec<&String>>();
+    #[arg(
+        long = "max-glibc",
+        help = "Fail if anything requires a glibc version newer than this one, listing the symbols and files that do"
+    )]
+    max_glibc: Option<String>,
 
-        for version in versions {
-            user_wants.insert(
-                version.to_string(),
-                wants
-                    .get(version)
-                    .unwrap()
-                    .keys()
-                    .map(|x| x.to_string())
-                    .collect::<HashSet<String>>(),
-            );
-        }
+    #[arg(
+        long = "min-version",
+        help = "Only report the symbols requiring a glibc version newer than this one, e.g. 2.17"
+    )]
+    min_version: Option<String>,
 
-        if parsed_args.stdout_format.is_text() {
-            for (version, functions) in &user_wants {
-                for function in functions {
-                    println!("{} => {}", version, function);
-                }
-            }
-        }
+    #[arg(
+        long = "target-distro",
+        help = "Fail if anything requires a newer glibc or libstdc++ than this release ships, e.g. ubuntu:20.04"
+    )]
+    target_distro: Option<String>,
 
-        if parsed_args.pretty_json {
-            Some(serde_json::to_string_pretty(&user_wants)?)
-        } else {
-            Some(serde_json::to_string(&user_wants)?)
-        }
-    } else if parsed_args.detail_level.is_file() {
-        let mut user_wants: HashMap<String, HashMap<String, HashSet<PathBuf>>> = HashMap::new();
-        let mut versions = wants.keys().collect::<Vec<&String>>();
-        versions.sort();
-        versions.reverse();
-        let versions = versions
-            .iter()
-            .take(parsed_args.versions)
-            .map(|x| *x)
-            .collect::<Vec<&String>>();
+    #[arg(
+        long,
+        help = "Fail if the inputs break this PyPA platform tag policy (e.g. manylinux_2_28, manylinux2014, musllinux_1_2), listing the symbols and libraries that do"
+    )]
+    policy: Option<String>,
 
-        for version in versions {
-            user_wants.insert(version.to_string(), wants.get(version).unwrap().clone());
-        }
+    #[arg(
+        long = "runs-on",
+        help = "Print the oldest release of each distribution every input runs on, judging by its glibc, libstdc++, libgcc_s and kernel requirements"
+    )]
+    runs_on: bool,
 
-        if parsed_args.stdout_format.is_text() {
-            for (version, functions) in &user_wants {
-                for (function, files) in functions {
-                    for file in files {
-                        println!("{} => {} => {}", version, function, file.display());
-                    }
-                }
-            }
-        }
+    #[arg(
+        long = "android-api",
+        help = "Resolve against an NDK sysroot given as --root and report the Bionic symbols introduced after this Android API level"
+    )]
+    android_api: Option<u32>,
 
-        if parsed_args.pretty_json {
-            Some(serde_json::to_string_pretty(&user_wants)?)
-        } else {
-            Some(serde_json::to_string(&user_wants)?)
-        }
-    } else {
-        None
-    };
+    #[arg(
+        long = "namespace",
+        help = "Report the requirements of these symbol version namespaces (e.g. OPENSSL, ZLIB) next to GLIBC [default: GLIBCXX, CXXABI, GCC]"
+    )]
+    namespaces: Vec<String>,
 
-    if let Some(json) = wants_json {
-        if parsed_args.save_json_to.is_some() {
-            std::fs::write(parsed_args.save_json_to.unwrap(), &json)?;
-        }
-        if parsed_args.stdout_format.is_json() {
-            println!("{}", json);
-        }
-    }
+    #[arg(
+        long = "demangle",
+        help = "Demangle C++ and Rust symbol names in the output"
+    )]
+    demangle: bool,
 
-    match parsed_args.print_error {
-        PrintError::All => {
-            for (path, (error, names)) in &errored {
-                for name in names {
-                    eprintln!(
-                        "file={}, reason={}, referenced_by={}",
-                        path.display(),
-                        error,
-                        name
-                    );
-                }
-            }
-        }
-        PrintError::CannotParse => {
-            for (path, (error, names)) in &errored {
-                if error == "cannot_parse" {
-                    for name in names {
-                        eprintln!("{} => {} => {}", path.display(), error, name);
-                    }
-                }
-            }
-        }
-        PrintError::CannotRead => {
-            for (path, (error, names)) in &errored {
-                if error == "cannot_read" {
-                    for name in names {
-                        eprintln!("{} => {} => {}", path.display(), error, name);
-                    }
-                }
-            }
-        }
-        PrintError::NotFound => {
-            for (path, (error, names)) in &errored {
-                if error == "not_found" {
-                    for name in names {
-                        eprintln!("{} => {} => {}", path.display(), error, name);
+    #[arg(
+        long = "include-defined",
+        help = "Also count versioned symbols defined (exported) by the scanned files"
+    )]
+    include_defined: bool,
+
+    #[arg(
+        long = "include-libc",
+        help = "Keep the versioned symbols exported by libc.so.6 and ld-linux"
+    )]
+    include_libc: bool,
+
+    #[arg(
+        long = "ignore-weak",
+        help = "Ignore undefined weak symbols, which do not have to be provided at runtime"
+    )]
+    ignore_weak: bool,
+
+    #[arg(
+        long = "show-unversioned",
+        help = "Also report undefined symbols that carry no version at all"
+    )]
+    show_unversioned: bool,
+
+    #[arg(
+        long = "show-ifunc",
+        help = "Also report GNU indirect functions (STT_GNU_IFUNC) defined by the scanned files"
+    )]
+    show_ifunc: bool,
+
+    #[arg(
+        long = "show-deprecated",
+        help = "Also report imported glibc interfaces that are deprecated or only kept as compat symbols"
+    )]
+    show_deprecated: bool,
+
+    #[arg(
+        long = "show-kernel",
+        help = "Also report the minimum Linux kernel version declared in .note.ABI-tag"
+    )]
+    show_kernel: bool,
+
+    #[arg(
+        long = "show-properties",
+        help = "Also report the GNU property notes (IBT/SHSTK, BTI/PAC, x86 ISA level) and whether all files agree on them"
+    )]
+    show_properties: bool,
+
+    #[arg(
+        long = "show-build-id",
+        help = "Also report the GNU build-id of every scanned file"
+    )]
+    show_build_id: bool,
+
+    #[arg(
+        long = "glibc-summary",
+        help = "Also report the newest glibc version each input imports itself, the overall maximum and the inputs needing more than 90% of the others"
+    )]
+    glibc_summary: bool,
+
+    #[arg(
+        long = "per-library",
+        help = "Also report the newest version of every namespace each library loaded requires itself"
+    )]
+    per_library: bool,
+
+    #[arg(
+        long = "check-resolution",
+        help = "Report undefined symbols of the inputs that no library in their dependency closure defines (like ldd -r)"
+    )]
+    check_resolution: bool,
+
+    #[arg(
+        long = "check-duplicates",
+        help = "Report sonames found at several search paths with different contents, and which one is loaded"
+    )]
+    check_duplicates: bool,
+
+    #[arg(
+        long = "fail-on-private",
+        help = "Exit with a non-zero code if anything outside of glibc imports GLIBC_PRIVATE symbols"
+    )]
+    fail_on_private: bool,
+
+    #[arg(
+        long = "debuginfod",
+        help = "Fetch missing symbol tables by build-id from the servers in DEBUGINFOD_URLS"
+    )]
+    debuginfod: bool,
+
+    /// The version or symbol to trace instead of reporting, set by `why`.
+    #[arg(skip)]
+    why: Option<String>,
+
+    /// What to do with the analysis instead of reporting it, set by `diff`.
+    #[arg(skip)]
+    diff: Option<diff::Stage>,
+
+    /// Print the dependency tree instead of reporting, set by `tree`.
+    #[arg(skip)]
+    tree: bool,
+}
+
+/// A versioned symbol requirement as reported at the `file` detail level.
+#[derive(Debug, Default, Serialize)]
+struct Requirement {
+    /// Whether all references to the symbol are undefined weak ones.
+    weak: bool,
+    /// The files that import the symbol.
+    files: HashSet<PathBuf>,
+    /// The libraries in the dependency closure that define the symbol.
+    provided_by: HashSet<PathBuf>,
+    /// The demangled name of the symbol, with `--demangle`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    demangled: Option<String>,
+}
+
+/// The parts of an ELF header that must match for two objects to be loaded
+/// into the same process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ElfArch {
+    /// `EI_CLASS`, i.e. 32-bit or 64-bit.
+    class: u8,
+    /// `EI_DATA`, i.e. little or big endian.
+    data: u8,
+    /// `e_machine`.
+    machine: u16,
+}
+
+impl ElfArch {
+    /// Reads the architecture from the ELF header of the file at `path`.
+    fn read(path: &Path) -> Option<ElfArch> {
+        let mut header = [0u8; 20];
+        std::fs::File::open(path)
+            .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+            .ok()?;
+        if !header.starts_with(b"\x7fELF") {
+            return None;
+        }
+        let machine = [header[18], header[19]];
+        let machine = match header[EI_DATA] {
+            ELFDATA2MSB => u16::from_be_bytes(machine),
+            _ => u16::from_le_bytes(machine),
+        };
+        Some(ElfArch {
+            class: header[EI_CLASS],
+            data: header[EI_DATA],
+            machine,
+        })
+    }
+}
+
+/// The architecture dependencies of one input are resolved for, and the
+/// directories they are searched in.
+#[derive(Debug)]
+struct Resolution {
+    /// The architecture of the input, if it could be read.
+    arch: Option<ElfArch>,
+    /// The library search paths, roughly in the order ld.so uses them.
+    search_paths: Vec<PathBuf>,
+}
+
+impl Resolution {
+    fn new(input: &Path, root: &Path, lib_paths: &[PathBuf]) -> Resolution {
+        let mut search_paths = rooted_runpaths(input, root);
+        search_paths.extend(system_search_paths(root, lib_paths));
+        Resolution {
+            arch: ElfArch::read(input),
+            search_paths,
+        }
+    }
+
+    /// Whether the object at `path` was built for another architecture than
+    /// the input.
+    fn is_wrong_arch(&self, path: &Path) -> bool {
+        self.arch
+            .is_some_and(|arch| ElfArch::read(path).is_some_and(|lib_arch| lib_arch != arch))
+    }
+
+    /// Looks for a library named `name` in the search paths that exists but
+    /// was built for another architecture.
+    fn find_wrong_arch_candidate(&self, name: &str) -> Option<PathBuf> {
+        self.search_paths
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|candidate| self.is_wrong_arch(candidate))
+    }
+
+    /// Looks for a library named `name` in the search paths that was built
+    /// for the same architecture as the input.
+    fn find_matching_candidate(&self, name: &str) -> Option<PathBuf> {
+        let arch = self.arch?;
+        self.search_paths
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|candidate| ElfArch::read(candidate) == Some(arch))
+            .map(|candidate| std::fs::canonicalize(&candidate).unwrap_or(candidate))
+    }
+
+    /// Returns every distinct file named `name` in the search paths that was
+    /// built for the same architecture as the input, in search order.
+    fn find_matching_candidates(&self, name: &str) -> Vec<PathBuf> {
+        let Some(arch) = self.arch else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        for dir in &self.search_paths {
+            let candidate = dir.join(name);
+            if ElfArch::read(&candidate) != Some(arch) {
+                continue;
+            }
+            let candidate = std::fs::canonicalize(&candidate).unwrap_or(candidate);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    }
+}
+
+/// The `DT_RUNPATH` and `DT_RPATH` directories of `input` as seen from
+/// inside `root`: `$ORIGIN` is the directory of `input` within the root,
+/// and the result is joined under `root`.
+fn rooted_runpaths(input: &Path, root: &Path) -> Vec<PathBuf> {
+    let parent = input.parent().unwrap_or(Path::new(""));
+    let origin = match parent.strip_prefix(root) {
+        Ok(relative) => Path::new("/").join(relative),
+        // an input outside of the root is loaded from where it is
+        Err(_) => parent.to_path_buf(),
+    };
+    let origin = origin.to_string_lossy();
+    symbols::read_search_paths(input)
+        .into_iter()
+        .map(|path| {
+            let expanded = path
+                .replace("${ORIGIN}", &origin)
+                .replace("$ORIGIN", &origin);
+            if path.contains("ORIGIN") && !parent.starts_with(root) {
+                PathBuf::from(expanded)
+            } else {
+                root.join(expanded.trim_start_matches('/'))
+            }
+        })
+        .collect()
+}
+
+/// The directories libraries are looked up in after the `DT_RUNPATH` of
+/// the file: those of `ld.so.conf` and the trusted ones under `root`, then
+/// `lib_paths`.
+fn system_search_paths(root: &Path, lib_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut search_paths = Vec::new();
+    if let Ok(paths) = parse_ld_so_conf(root.join("etc/ld.so.conf"), root) {
+        search_paths.extend(paths.into_iter().map(PathBuf::from));
+    }
+    for path in ["lib", "lib64", "usr/lib", "usr/lib64"] {
+        search_paths.push(root.join(path));
+    }
+    search_paths.extend(lib_paths.iter().cloned());
+    search_paths
+}
+
+/// A soname found at several places in the search paths.
+#[derive(Debug, Serialize)]
+struct Duplicate {
+    /// The file the dependency resolved to.
+    loaded: PathBuf,
+    /// The other files with the same name but different contents.
+    shadowed: Vec<PathBuf>,
+}
+
+/// The symbol version namespaces reported next to `GLIBC` unless others are
+/// asked for with `--namespace`.
+const DEFAULT_NAMESPACES: [&str; 3] = ["GLIBCXX", "CXXABI", "GCC"];
+
+/// Everything collected while scanning the files in the dependency closure.
+#[derive(Debug, Default)]
+struct Findings {
+    /// namespace (`GLIBC`, `GLIBCXX`, ...) => version => function => the
+    /// files requiring it
+    wants: HashMap<String, HashMap<String, HashMap<String, HashSet<PathBuf>>>>,
+    /// undefined symbols that carry no version => the files importing them
+    unversioned: HashMap<String, HashSet<PathBuf>>,
+    /// (`NAMESPACE_version`, function, file) for references that are
+    /// undefined weak symbols only
+    weak: HashSet<(String, String, PathBuf)>,
+    /// GNU indirect function symbols => the files defining them
+    ifuncs: HashMap<String, HashSet<PathBuf>>,
+    /// input => the imports no library in its dependency closure defines
+    unresolved: HashMap<PathBuf, HashSet<String>>,
+    /// input => "static" or "static-pie" for statically linked inputs
+    static_inputs: HashMap<PathBuf, &'static str>,
+    /// soname => the file it resolved to and the others it shadows
+    duplicates: HashMap<String, Duplicate>,
+    /// file => the minimum kernel version its ABI tag note declares
+    kernel_versions: HashMap<PathBuf, notes::KernelVersion>,
+    /// file => the minimum Android API level its `.note.android.ident`
+    /// declares
+    android_api_levels: HashMap<PathBuf, u32>,
+    /// file => what its GNU property note declares
+    properties: HashMap<PathBuf, notes::GnuProperties>,
+    /// file => its GNU build-id
+    build_ids: HashMap<PathBuf, String>,
+    /// file => its `DT_SONAME`
+    sonames: HashMap<PathBuf, String>,
+    /// file => the SHA-256 of its contents, only collected for the SBOM
+    /// formats
+    checksums: HashMap<PathBuf, String>,
+    /// build-id => the first path of the object scanned with it, the same
+    /// object reached through another path (hardlink, bind mount, copy) is
+    /// only parsed once
+    scanned_build_ids: HashMap<String, PathBuf>,
+    /// input (as given to `--paths`) => the files in its dependency closure
+    closures: BTreeMap<String, HashSet<PathBuf>>,
+    /// the libraries neither bundled with the inputs nor allowed by the
+    /// `--policy`
+    disallowed_libraries: BTreeSet<String>,
+    /// the files of the libraries the `--policy` allows
+    allowed_library_files: HashSet<PathBuf>,
+    /// (file, library it needs) for every `DT_NEEDED` entry that resolved,
+    /// the edges of the dependency graph
+    needed: BTreeSet<(PathBuf, PathBuf)>,
+    /// (file, library it needs) for every `DT_NEEDED` entry that did not
+    /// resolve
+    not_found: BTreeSet<(PathBuf, String)>,
+    /// (namespace, version, function, file) for the entries of `wants` not
+    /// printed yet, only collected to stream them with `--stdout ndjson`
+    pending: Option<Vec<(String, String, String, PathBuf)>>,
+}
+
+/// A symbol required at a newer version than a target provides.
+#[derive(Debug, Serialize)]
+struct Offending {
+    /// The full version name, e.g. `GLIBC_2.34`.
+    version: String,
+    symbol: String,
+    /// The files that import the symbol at that version.
+    files: Vec<PathBuf>,
+}
+
+impl Findings {
+    /// Returns the symbols required from `namespace` at a version newer
+    /// than `max`, highest version first.
+    fn newer_than(&self, namespace: &str, max: &str) -> Vec<Offending> {
+        let max = distro::version_key(max);
+        self.offending(namespace, |version, _| distro::version_key(version) > max)
+    }
+
+    /// Returns the symbols required from `namespace` at a version for which
+    /// `is_offending(version, symbol)` holds, highest version first.
+    fn offending(
+        &self,
+        namespace: &str,
+        is_offending: impl Fn(&str, &str) -> bool,
+    ) -> Vec<Offending> {
+        let Some(wants) = self.wants.get(namespace) else {
+            return Vec::new();
+        };
+        let mut offending = Vec::new();
+        for (version, functions) in wants {
+            if namespace == "GLIBC" && version == "PRIVATE" {
+                continue;
+            }
+            for (function, files) in functions {
+                if !is_offending(version, function) {
+                    continue;
+                }
+                let mut files = files.iter().cloned().collect::<Vec<PathBuf>>();
+                files.sort();
+                offending.push(Offending {
+                    version: format!("{}_{}", namespace, version),
+                    symbol: function.clone(),
+                    files,
+                });
+            }
+        }
+        offending.sort_by(|a, b| {
+            let key = |o: &Offending| {
+                let (_, version) = o.version.rsplit_once('_').unwrap_or_default();
+                distro::version_key(version)
+            };
+            key(b).cmp(&key(a)).then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        offending
+    }
+
+    /// Returns the `count` highest versions required from `namespace`.
+    fn highest_versions(&self, namespace: &str, count: usize) -> Vec<&String> {
+        let Some(wants) = self.wants.get(namespace) else {
+            return Vec::new();
+        };
+        // GLIBC_PRIVATE is not a release, it is reported on its own
+        let mut versions = wants
+            .keys()
+            .filter(|version| !(namespace == "GLIBC" && *version == "PRIVATE"))
+            .collect::<Vec<&String>>();
+        // 2.34 is newer than 2.9, component by component
+        versions.sort_by_key(|version| std::cmp::Reverse(distro::version_key(version)));
+        versions.truncate(count);
+        versions
+    }
+
+    /// Returns the requirements of the files in `files` alone, what `wants`
+    /// and `weak` hold for them.
+    fn restricted_to(&self, files: &HashSet<PathBuf>) -> Findings {
+        let mut wants: HashMap<String, HashMap<String, HashMap<String, HashSet<PathBuf>>>> =
+            HashMap::new();
+        for (namespace, versions) in &self.wants {
+            for (version, functions) in versions {
+                for (function, found) in functions {
+                    let found = found
+                        .intersection(files)
+                        .cloned()
+                        .collect::<HashSet<PathBuf>>();
+                    if found.is_empty() {
+                        continue;
+                    }
+                    wants
+                        .entry(namespace.clone())
+                        .or_default()
+                        .entry(version.clone())
+                        .or_default()
+                        .insert(function.clone(), found);
+                }
+            }
+        }
+        Findings {
+            wants,
+            weak: self
+                .weak
+                .iter()
+                .filter(|(_, _, file)| files.contains(file))
+                .cloned()
+                .collect(),
+            ..Findings::default()
+        }
+    }
+
+    /// Returns the highest version of `namespace` required by the files in
+    /// `closure`.
+    fn highest_version_in(&self, namespace: &str, closure: &HashSet<PathBuf>) -> Option<&String> {
+        self.wants
+            .get(namespace)?
+            .iter()
+            .filter(|(version, _)| !(namespace == "GLIBC" && *version == "PRIVATE"))
+            .filter(|(_, functions)| {
+                functions
+                    .values()
+                    .flatten()
+                    .any(|file| is_in_closure(closure, file))
+            })
+            .map(|(version, _)| version)
+            .max_by_key(|version| distro::version_key(version))
+    }
+
+    /// Returns what the files in `closure` need from the system.
+    fn requirements_of(&self, closure: &HashSet<PathBuf>) -> distro::Requirements {
+        let gcc = |namespaces: &[&str]| {
+            libstdcxx::newest_gcc(namespaces.iter().filter_map(|namespace| {
+                let version = self.highest_version_in(namespace, closure)?;
+                libstdcxx::minimum_gcc(namespace, version)
+            }))
+            .map(|gcc| gcc.to_string())
+        };
+        distro::Requirements {
+            glibc: self.highest_version_in("GLIBC", closure).cloned(),
+            libstdcxx: gcc(&["GLIBCXX", "CXXABI"]),
+            libgcc: gcc(&["GCC"]),
+            kernel: self
+                .kernel_versions
+                .iter()
+                .filter(|(file, _)| is_in_closure(closure, file))
+                .map(|(_, version)| version)
+                .max()
+                .map(|version| version.to_string()),
+        }
+    }
+
+    /// Whether `file` only references `function` at `version` weakly.
+    fn is_weak_in(&self, namespace: &str, version: &str, function: &str, file: &Path) -> bool {
+        self.weak.contains(&(
+            format!("{}_{}", namespace, version),
+            function.to_string(),
+            file.to_path_buf(),
+        ))
+    }
+
+    /// Adds `copy` to everything `original` was found to require, import
+    /// and declare, for the copies of an object that are not parsed again.
+    fn copy_file_findings(&mut self, original: &Path, copy: &Path) {
+        let mut required = Vec::new();
+        for (namespace, versions) in &self.wants {
+            for (version, functions) in versions {
+                for (function, files) in functions {
+                    if files.contains(original) {
+                        let weak = self.is_weak_in(namespace, version, function, original);
+                        required.push((namespace.clone(), version.clone(), function.clone(), weak));
+                    }
+                }
+            }
+        }
+        for (namespace, version, function, weak) in required {
+            insert_required_version(self, &namespace, &version, &function, copy, weak);
+        }
+        for files in self
+            .unversioned
+            .values_mut()
+            .chain(self.ifuncs.values_mut())
+        {
+            if files.contains(original) {
+                files.insert(copy.to_path_buf());
+            }
+        }
+        if let Some(version) = self.kernel_versions.get(original).copied() {
+            self.kernel_versions.insert(copy.to_path_buf(), version);
+        }
+        if let Some(api) = self.android_api_levels.get(original).copied() {
+            self.android_api_levels.insert(copy.to_path_buf(), api);
+        }
+        if let Some(properties) = self.properties.get(original).cloned() {
+            self.properties.insert(copy.to_path_buf(), properties);
+        }
+    }
+
+    /// Whether every reference to `function` at `version` is weak.
+    fn is_weak(&self, namespace: &str, version: &str, function: &str) -> bool {
+        self.wants
+            .get(namespace)
+            .and_then(|versions| versions.get(version))
+            .and_then(|functions| functions.get(function))
+            .is_some_and(|files| {
+                files
+                    .iter()
+                    .all(|file| self.is_weak_in(namespace, version, function, file))
+            })
+    }
+
+    /// Renames every file in the findings with `rename`, e.g. to report
+    /// the files unpacked from an archive as `archive!member`.
+    fn map_paths(self, rename: impl Fn(&Path) -> PathBuf) -> Findings {
+        let files = |files: HashSet<PathBuf>| {
+            files
+                .into_iter()
+                .map(|file| rename(&file))
+                .collect::<HashSet<PathBuf>>()
+        };
+        Findings {
+            wants: self
+                .wants
+                .into_iter()
+                .map(|(namespace, versions)| {
+                    let versions = versions
+                        .into_iter()
+                        .map(|(version, functions)| {
+                            let functions = functions
+                                .into_iter()
+                                .map(|(function, found)| (function, files(found)))
+                                .collect();
+                            (version, functions)
+                        })
+                        .collect();
+                    (namespace, versions)
+                })
+                .collect(),
+            unversioned: self
+                .unversioned
+                .into_iter()
+                .map(|(symbol, found)| (symbol, files(found)))
+                .collect(),
+            weak: self
+                .weak
+                .into_iter()
+                .map(|(version, function, file)| (version, function, rename(&file)))
+                .collect(),
+            ifuncs: self
+                .ifuncs
+                .into_iter()
+                .map(|(symbol, found)| (symbol, files(found)))
+                .collect(),
+            unresolved: rename_keys(self.unresolved, &rename),
+            static_inputs: rename_keys(self.static_inputs, &rename),
+            duplicates: self
+                .duplicates
+                .into_iter()
+                .map(|(soname, duplicate)| {
+                    let duplicate = Duplicate {
+                        loaded: rename(&duplicate.loaded),
+                        shadowed: duplicate.shadowed.iter().map(|file| rename(file)).collect(),
+                    };
+                    (soname, duplicate)
+                })
+                .collect(),
+            kernel_versions: rename_keys(self.kernel_versions, &rename),
+            android_api_levels: rename_keys(self.android_api_levels, &rename),
+            properties: rename_keys(self.properties, &rename),
+            build_ids: rename_keys(self.build_ids, &rename),
+            sonames: rename_keys(self.sonames, &rename),
+            checksums: rename_keys(self.checksums, &rename),
+            scanned_build_ids: self
+                .scanned_build_ids
+                .into_iter()
+                .map(|(build_id, file)| (build_id, rename(&file)))
+                .collect(),
+            closures: self
+                .closures
+                .into_iter()
+                .map(|(input, closure)| {
+                    let input = rename(Path::new(&input)).display().to_string();
+                    (input, files(closure))
+                })
+                .collect(),
+            disallowed_libraries: self.disallowed_libraries,
+            allowed_library_files: files(self.allowed_library_files),
+            needed: self
+                .needed
+                .into_iter()
+                .map(|(file, library)| (rename(&file), rename(&library)))
+                .collect(),
+            not_found: self
+                .not_found
+                .into_iter()
+                .map(|(file, library)| (rename(&file), library))
+                .collect(),
+            pending: None,
+        }
+    }
+}
+
+/// Options that change which symbols are taken into account while scanning.
+#[derive(Debug, Clone, Default)]
+struct ScanOptions {
+    /// Count defined symbols in addition to undefined (imported) ones.
+    include_defined: bool,
+    /// Keep the symbols exported by the C library and the dynamic loader.
+    include_libc: bool,
+    /// Leave out undefined weak symbols, which may stay unresolved.
+    ignore_weak: bool,
+    /// Collect the GNU indirect functions (`STT_GNU_IFUNC`) defined by the
+    /// scanned files.
+    ifuncs: bool,
+    /// Also scan the separate debug file found via build-id or
+    /// `.gnu_debuglink`.
+    separate_debug_files: bool,
+    /// The root under which separate debug files are looked up.
+    root: PathBuf,
+    /// Hash the contents of the scanned files, for the SBOM formats.
+    checksums: bool,
+    /// The debuginfod servers to fetch missing symbol tables from, if any.
+    debuginfod_urls: Vec<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let matches = Args::command().get_matches();
+    // the global options aside, the options of scan given before a command
+    // are not the command's
+    if let Some((name, _)) = matches.subcommand() {
+        let mut command = Args::command();
+        let given = command.get_arguments().find(|arg| {
+            let id = arg.get_id().as_str();
+            !arg.is_global_set() && matches.value_source(id) == Some(ValueSource::CommandLine)
+        });
+        if let Some(arg) = given {
+            let option = match (arg.get_long(), arg.get_short()) {
+                (Some(long), _) => format!("--{}", long),
+                (None, Some(short)) => format!("-{}", short),
+                (None, None) => arg.get_id().to_string(),
+            };
+            let message = format!("{} cannot be used before the command {}", option, name);
+            command.error(ErrorKind::ArgumentConflict, message).exit();
+        }
+    }
+    let parsed_args = Args::from_arg_matches(&matches)?;
+    let root = parsed_args.root.as_deref();
+    match &parsed_args.command {
+        Some(command) => run_command(command, root),
+        None => scan_inputs(&parsed_args.input, Path::new(root.unwrap_or("/"))),
+    }
+}
+
+/// Expands the inputs given on the command line (directories, archives,
+/// lists of paths, ...) and analyzes them.
+fn scan_inputs(args: &InputArgs, root: &Path) -> Result<(), Box<dyn Error>> {
+    // archives are unpacked into temporary directories that have to stay
+    // around until the analysis is done
+    let mut workspace = inputs::Workspace::default();
+    let mut paths = args.paths.clone();
+    if args.paths_from.as_deref() == Some("-") && paths.iter().any(|path| path == "-") {
+        return Err("stdin cannot hold both a file (-p -) and the list of --paths-from -".into());
+    }
+    if let Some(source) = &args.paths_from {
+        paths.extend(inputs::read_list(source, args.null)?);
+    }
+    let mut inputs = paths
+        .iter()
+        .flat_map(|path| inputs::expand(path, &mut workspace))
+        .collect::<Vec<inputs::Input>>();
+    for tarball in &args.nif_tarballs {
+        inputs.extend(inputs::expand_nif_tarball(tarball, &mut workspace));
+    }
+    if args.from_path {
+        inputs.extend(system::path_programs(root));
+    }
+    if args.from_systemd {
+        inputs.extend(system::systemd_programs(root));
+    }
+    // the file read from stdin is reported as <stdin>
+    let required = args
+        .paths
+        .iter()
+        .map(|path| match path.as_str() {
+            "-" => inputs::STDIN_NAME.to_string(),
+            _ => path.clone(),
+        })
+        .collect::<Vec<String>>();
+    scan(&args.scan, root, inputs, &workspace, &required)
+}
+
+/// Analyzes `inputs`, resolving their dependencies under `root`, and prints
+/// the report. The run fails when any of the `required` paths cannot be
+/// analyzed.
+fn scan(
+    parsed_args: &ScanArgs,
+    root: &Path,
+    mut inputs: Vec<inputs::Input>,
+    workspace: &inputs::Workspace,
+    required: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let started = std::time::Instant::now();
+    // a template replaces the text output
+    let template_args;
+    let parsed_args = if parsed_args.format.is_some() && parsed_args.stdout_format.is_text() {
+        template_args = ScanArgs {
+            stdout_format: StdoutFormat::Template,
+            ..parsed_args.clone()
+        };
+        &template_args
+    } else {
+        parsed_args
+    };
+    if parsed_args.stdout_format.is_template() && parsed_args.format.is_none() {
+        return Err("--stdout template requires --format".into());
+    }
+    color::init(match parsed_args.color {
+        ColorChoice::Auto => None,
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+    });
+
+    let exclusions = inputs::Exclusions::new(&parsed_args.excludes)?;
+    let options = ScanOptions {
+        include_defined: parsed_args.include_defined,
+        include_libc: parsed_args.include_libc,
+        ignore_weak: parsed_args.ignore_weak,
+        ifuncs: parsed_args.show_ifunc,
+        separate_debug_files: !parsed_args.detail_level.is_version(),
+        root: root.to_path_buf(),
+        checksums: parsed_args.stdout_format.is_spdx() || parsed_args.stdout_format.is_cyclonedx(),
+        debuginfod_urls: if parsed_args.debuginfod {
+            debuginfod::server_urls()
+        } else {
+            Vec::new()
+        },
+    };
+    if parsed_args.debuginfod && options.debuginfod_urls.is_empty() {
+        return Err("--debuginfod requires DEBUGINFOD_URLS to be set".into());
+    }
+    let abidb = match &parsed_args.abidb {
+        Some(path) => Some(abidb::AbiDb::read(Path::new(path))?),
+        None => None,
+    };
+    let distro_table = match &parsed_args.distro_table {
+        Some(path) => distro::read_table(Path::new(path))?,
+        None => distro::builtin_table(),
+    };
+    let target_distro = match &parsed_args.target_distro {
+        Some(spec) => match distro::find_release(&distro_table, spec) {
+            Some(release) => Some(release),
+            None => return Err(format!("unknown distribution release {}", spec).into()),
+        },
+        None => None,
+    };
+    let policy = match &parsed_args.policy {
+        Some(name) => match policy::find_policy(name) {
+            Some(policy) => Some(policy),
+            None => return Err(format!("unknown policy {}", name).into()),
+        },
+        None => None,
+    };
+    inputs.retain(|input| !exclusions.is_excluded_input(input));
+
+    let mut analysis = Analysis::new(
+        parsed_args,
+        root,
+        &inputs,
+        workspace,
+        &options,
+        &exclusions,
+        policy,
+    );
+    for given in &inputs {
+        analysis.analyze_input(&given.path);
+    }
+    let Analysis {
+        mut findings,
+        errored,
+        library_exports,
+        ..
+    } = analysis.display_paths();
+    if let Some(min_version) = &parsed_args.min_version {
+        // what every system since the baseline provides goes without saying
+        let min_version = distro::version_key(min_version);
+        if let Some(versions) = findings.wants.get_mut("GLIBC") {
+            versions.retain(|version, _| {
+                version == "PRIVATE" || distro::version_key(version) > min_version
+            });
+        }
+    }
+
+    if let Some(query) = &parsed_args.why {
+        return print_why(query, &findings, parsed_args);
+    }
+    if parsed_args.tree {
+        return print_trees(&findings, parsed_args);
+    }
+    if let Some(stage) = &parsed_args.diff {
+        return print_diff(stage, &findings, &errored, required, parsed_args);
+    }
+
+    // the summary comes first, what CI logs show before anything else
+    let summary = if parsed_args.summary {
+        Some(summary_section(
+            &findings,
+            &errored,
+            started.elapsed(),
+            parsed_args,
+        )?)
+    } else {
+        None
+    };
+    let report = ScanReport {
+        args: parsed_args,
+        findings: &findings,
+        errored: &errored,
+        library_exports: &library_exports,
+        inputs: &inputs,
+        workspace,
+        distro_table: &distro_table,
+        target_distro,
+        abidb: abidb.as_ref(),
+        policy,
+    };
+    let offenses = report.offenses();
+    let (wants_json, sections) = report.sections(summary, &offenses)?;
+    let violations = report.violations(&offenses, required);
+    report.write_outputs(wants_json, sections, &violations)?;
+    print_errors(&errored, parsed_args);
+
+    if !findings.unresolved.is_empty() {
+        std::process::exit(1);
+    }
+    if !offenses.max_glibc.is_empty() || !offenses.target_distro.is_empty() {
+        std::process::exit(1);
+    }
+    if !offenses.policy.is_empty() || !findings.disallowed_libraries.is_empty() {
+        std::process::exit(1);
+    }
+    if parsed_args.fail_on_private && !offenses.glibc_private.is_empty() {
+        std::process::exit(1);
+    }
+    if !errored.is_empty()
+        && required
+            .iter()
+            .any(|x| errored.contains_key(&PathBuf::from(x)))
+    {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The analysis of the inputs of a scan, each adding to what was found in
+/// the ones before.
+struct Analysis<'a> {
+    args: &'a ScanArgs,
+    root: &'a Path,
+    workspace: &'a inputs::Workspace,
+    options: &'a ScanOptions,
+    exclusions: &'a inputs::Exclusions,
+    policy: Option<&'static policy::Policy>,
+    analyzer: DependencyAnalyzer,
+    /// `--ld-library-path`, the Android system libraries and those of the
+    /// unpacked packages
+    lib_paths: Vec<PathBuf>,
+    /// where ld looks the `-lname` of linker scripts up
+    linker_search_dirs: Vec<PathBuf>,
+    scopes: Vec<PathBuf>,
+    /// the inputs that are libraries loaded by the others
+    bundled: HashSet<PathBuf>,
+    findings: Findings,
+    errored: HashMap<PathBuf, (String, HashSet<String>)>,
+    /// the files scanned so far
+    visited: HashSet<PathBuf>,
+    library_exports: HashMap<PathBuf, symbols::Exports>,
+    /// what was printed so far with `--stdout ndjson`
+    streamed: HashSet<(String, Option<String>, Option<PathBuf>)>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(
+        args: &'a ScanArgs,
+        root: &'a Path,
+        inputs: &[inputs::Input],
+        workspace: &'a inputs::Workspace,
+        options: &'a ScanOptions,
+        exclusions: &'a inputs::Exclusions,
+        policy: Option<&'static policy::Policy>,
+    ) -> Analysis<'a> {
+        let mut findings = Findings::default();
+        // with ndjson, what each input requires is printed once it is
+        // analyzed
+        if args.stdout_format.is_ndjson() {
+            findings.pending = Some(Vec::new());
+        }
+        let mut errored: HashMap<PathBuf, (String, HashSet<String>)> = HashMap::new();
+        for archive in workspace.unpack_failures() {
+            errored
+                .entry(PathBuf::from(archive))
+                .or_insert(("cannot_unpack".to_string(), HashSet::new()))
+                .1
+                .insert(archive.clone());
+        }
+        let mut lib_paths = args
+            .ld_library_path
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<PathBuf>>();
+        if let Some(api) = args.android_api {
+            lib_paths.extend(android::library_paths(root, api));
+        }
+        // the libraries of unpacked packages are found after the system's
+        lib_paths.extend(workspace.library_paths().iter().cloned());
+        // libraries that are inputs themselves (e.g. vendored into a wheel)
+        // are not loaded from the system
+        let bundled = inputs
+            .iter()
+            .filter_map(|given| std::fs::canonicalize(&given.path).ok())
+            .collect::<HashSet<PathBuf>>();
+        Analysis {
+            args,
+            root,
+            workspace,
+            options,
+            exclusions,
+            policy,
+            analyzer: DependencyAnalyzer::new(root.to_path_buf()).library_paths(lib_paths.clone()),
+            // ld looks the -lname of linker scripts up where the loader would
+            linker_search_dirs: system_search_paths(root, &lib_paths),
+            lib_paths,
+            scopes: args.scopes.iter().map(PathBuf::from).collect(),
+            bundled,
+            findings,
+            errored,
+            visited: HashSet::new(),
+            library_exports: HashMap::new(),
+            streamed: HashSet::new(),
+        }
+    }
+
+    /// Analyzes the input `pathname`, the files its linker scripts name if
+    /// it is one, and their dependencies.
+    fn analyze_input(&mut self, pathname: &String) {
+        let mut scripts = HashSet::new();
+        for input in expand_linker_scripts(
+            pathname,
+            Path::new(pathname),
+            self.root,
+            &self.linker_search_dirs,
+            &mut scripts,
+            &mut self.errored,
+        ) {
+            self.analyze_file(pathname, &input);
+        }
+        if let Some(pending) = &mut self.findings.pending {
+            let referenced_by = self.workspace.display_path(Path::new(pathname));
+            stream_findings(
+                pending,
+                &referenced_by,
+                &mut self.streamed,
+                self.workspace,
+                self.args,
+            );
+        }
+    }
+
+    /// Scans `input`, reached from the input `pathname`, and the libraries
+    /// it loads.
+    fn analyze_file(&mut self, pathname: &String, input: &PathBuf) {
+        self.findings
+            .closures
+            .entry(pathname.clone())
+            .or_default()
+            .insert(input.clone());
+        if self.visited.insert(input.clone()) {
+            find_required_glibc_version_in_file(
+                pathname,
+                pathname,
+                input,
+                self.options,
+                &mut self.findings,
+                &mut self.errored,
+            );
+        }
+        if archive::is_ar_archive_file(input) || is_relocatable_file(input) {
+            // static archives and relocatable objects have no DT_NEEDED
+            // entries to follow
+            return;
+        }
+        if let Some(linkage) = static_linkage(input) {
+            // nothing is loaded at runtime, so there is no glibc requirement
+            // beyond what the binary itself references
+            self.findings.static_inputs.insert(input.clone(), linkage);
+            return;
+        }
+
+        let runpaths = rooted_runpaths(input, self.root);
+        // lddtree joins the runpaths under the root twice, so under --root
+        // they are passed in already resolved
+        let analyzer = if self.root == Path::new("/") {
+            self.analyzer.clone()
+        } else {
+            let paths = runpaths.iter().chain(&self.lib_paths).cloned().collect();
+            self.analyzer.clone().library_paths(paths)
+        };
+        let Ok(mut deps) = analyzer.analyze(input) else {
+            // a truncated or malformed file does not stop the scan of the
+            // others
+            self.errored
+                .entry(input.clone())
+                .or_insert(("cannot_parse".to_string(), HashSet::new()))
+                .1
+                .insert(pathname.to_string());
+            return;
+        };
+        // lddtree looks the interpreter up under --root but takes its
+        // realpath on the host
+        if let Some(interpreter) = &deps.interpreter {
+            if let Some(library) = deps.libraries.get_mut(interpreter) {
+                library.realpath = std::fs::canonicalize(&library.path).ok();
+            }
+        }
+        let resolution = Resolution::new(input, self.root, &self.lib_paths);
+        self.check_interpreter(pathname, &deps, &resolution);
+        let closure = deps
+            .libraries
+            .values()
+            .filter_map(|lib| lib.realpath.clone())
+            .collect::<Vec<PathBuf>>();
+        self.findings
+            .closures
+            .entry(pathname.clone())
+            .or_default()
+            .extend(closure.iter().cloned());
+        let resolved = |name: &String| deps.libraries.get(name)?.realpath.clone();
+        let mut edges = vec![(input, &deps.needed)];
+        for lib in deps.libraries.values() {
+            if let Some(file) = &lib.realpath {
+                edges.push((file, &lib.needed));
+            }
+        }
+        for (file, needed) in edges {
+            for name in needed {
+                match resolved(name) {
+                    Some(library) => self.findings.needed.insert((file.clone(), library)),
+                    None => self.findings.not_found.insert((file.clone(), name.clone())),
+                };
+            }
+        }
+        if let Some(policy) = self.policy {
+            let bundled = &self.bundled;
+            let check = policy.check_libraries(&deps, |path| {
+                std::fs::canonicalize(path).is_ok_and(|path| bundled.contains(&path))
+            });
+            self.findings.allowed_library_files.extend(check.allowed);
+            self.findings
+                .disallowed_libraries
+                .extend(check.disallowed.into_iter().map(|(name, _)| name));
+        }
+        if self.args.detail_level.is_file()
+            || self.args.check_resolution
+            || self.args.format.is_some()
+        {
+            for path in &closure {
+                self.library_exports
+                    .entry(path.clone())
+                    .or_insert_with(|| symbols::read_exports(path));
+            }
+        }
+        if self.args.check_duplicates {
+            self.find_duplicates(&deps, &resolution);
+        }
+        if self.args.check_resolution {
+            self.find_unresolved(input, &closure);
+        }
+        for needed in deps.needed {
+            gather_deps_required_libc_version(
+                pathname,
+                &needed,
+                &deps.libraries,
+                &self.scopes,
+                self.exclusions,
+                &resolution,
+                self.options,
+                &mut self.findings,
+                &mut self.visited,
+                &mut self.errored,
+            );
+        }
+    }
+
+    /// Records the loader named in `PT_INTERP` as a `bad_interpreter` when it
+    /// does not exist in the target system, as the binary cannot be started
+    /// at all.
+    fn check_interpreter(
+        &mut self,
+        pathname: &String,
+        deps: &DependencyTree,
+        resolution: &Resolution,
+    ) {
+        // an NDK sysroot only has the libraries to link against, not the
+        // Android loader
+        let is_ndk_sysroot = self.args.android_api.is_some() && !self.root.join("system").is_dir();
+        let Some(interpreter) = deps.interpreter.as_ref().filter(|_| !is_ndk_sysroot) else {
+            return;
+        };
+        let path = self.root.join(interpreter.trim_start_matches('/'));
+        if !path.exists() || resolution.is_wrong_arch(&path) {
+            self.errored
+                .entry(path)
+                .or_insert(("bad_interpreter".to_string(), HashSet::new()))
+                .1
+                .insert(pathname.to_string());
+        }
+    }
+
+    /// Records the libraries in the `--scope` directories that shadow others
+    /// of the same name the loader could have found.
+    fn find_duplicates(&mut self, deps: &DependencyTree, resolution: &Resolution) {
+        for (name, lib) in &deps.libraries {
+            let Some(loaded) = lib.realpath.as_ref() else {
+                continue;
+            };
+            if self.findings.duplicates.contains_key(name)
+                || !self.scopes.iter().any(|scope| loaded.starts_with(scope))
+                || self.exclusions.is_excluded(loaded)
+            {
+                continue;
+            }
+            let shadowed = resolution
+                .find_matching_candidates(name)
+                .into_iter()
+                .filter(|candidate| candidate != loaded && !have_same_contents(candidate, loaded))
+                .collect::<Vec<PathBuf>>();
+            if !shadowed.is_empty() {
+                self.findings.duplicates.insert(
+                    name.clone(),
+                    Duplicate {
+                        loaded: loaded.clone(),
+                        shadowed,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Like `ldd -r`: records the strong imports of `input` that no file of
+    /// its dependency `closure` defines.
+    fn find_unresolved(&mut self, input: &Path, closure: &[PathBuf]) {
+        for import in symbols::read_imports(input) {
+            let resolved = import.weak
+                || closure.iter().any(|path| {
+                    self.library_exports[path].provides(&import.name, import.version.as_deref())
+                });
+            if !resolved {
+                let symbol = match &import.version {
+                    Some(version) => format!("{}@{}", import.name, version),
+                    None => import.name.clone(),
+                };
+                self.findings
+                    .unresolved
+                    .entry(input.to_path_buf())
+                    .or_default()
+                    .insert(symbol);
+            }
+        }
+    }
+
+    /// Reports the files unpacked from archives as `archive!member` rather
+    /// than by their path in the workspace.
+    fn display_paths(self) -> Analysis<'a> {
+        let workspace = self.workspace;
+        let rename = |path: &Path| workspace.display_path(path);
+        let errored = self
+            .errored
+            .into_iter()
+            .map(|(path, (error, names))| {
+                let names = names
+                    .iter()
+                    .map(|name| rename(Path::new(name)).display().to_string())
+                    .collect::<HashSet<String>>();
+                (rename(&path), (error, names))
+            })
+            .collect::<HashMap<PathBuf, (String, HashSet<String>)>>();
+        Analysis {
+            findings: self.findings.map_paths(rename),
+            library_exports: rename_keys(self.library_exports, &rename),
+            errored,
+            ..self
+        }
+    }
+}
+
+/// Prints the chains of files leading to what requires `query`.
+fn print_why(query: &str, findings: &Findings, args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let chains = why_chains(query, findings, args);
+    if chains.is_empty() {
+        return Err(format!("nothing analyzed requires {}", query).into());
+    }
+    if args.stdout_format.is_text() {
+        for chain in &chains {
+            let files = chain
+                .files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<String>>();
+            println!(
+                "why => {} => {}@{}",
+                files.join(" => "),
+                chain.symbol,
+                chain.version
+            );
+        }
+    } else if args.pretty_json {
+        println!("{}", serde_json::to_string_pretty(&chains)?);
+    } else {
+        println!("{}", serde_json::to_string(&chains)?);
+    }
+    Ok(())
+}
+
+/// Prints the dependency tree of every input.
+fn print_trees(findings: &Findings, args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let trees = findings
+        .closures
+        .keys()
+        .map(|input| dependency_tree(findings, &PathBuf::from(input)))
+        .collect::<Vec<TreeNode>>();
+    if args.stdout_format.is_text() {
+        for tree in &trees {
+            print_tree(tree, 0);
+        }
+    } else if args.pretty_json {
+        println!("{}", serde_json::to_string_pretty(&trees)?);
+    } else {
+        println!("{}", serde_json::to_string(&trees)?);
+    }
+    Ok(())
+}
+
+/// Prints how the requirements changed since the old scan or the baseline
+/// of `stage`, or hands the report over to the new scan.
+fn print_diff(
+    stage: &diff::Stage,
+    findings: &Findings,
+    errored: &HashMap<PathBuf, (String, HashSet<String>)>,
+    required: &[String],
+    args: &ScanArgs,
+) -> Result<(), Box<dyn Error>> {
+    for path in required {
+        if let Some((error, _)) = errored.get(Path::new(path)) {
+            return Err(format!("{} => {}", error, path).into());
+        }
+    }
+    let sections = serde_json::Value::Object(serde_json::Map::new());
+    let report = envelope(findings, sections, errored, args);
+    let (old, is_baseline) = match stage {
+        diff::Stage::Old(sender) => return Ok(sender.send(report)?),
+        diff::Stage::New(old) => (old, false),
+        diff::Stage::Baseline(baseline) => (baseline, true),
+    };
+    let changes = diff::compare(old, &report);
+    // what the baseline already required is grandfathered in
+    let failed = is_baseline && changes.has_regressions();
+    if !args.stdout_format.is_text() {
+        if args.pretty_json {
+            println!("{}", serde_json::to_string_pretty(&changes)?);
+        } else {
+            println!("{}", serde_json::to_string(&changes)?);
+        }
+        if failed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    for (kind, requirements) in [("added", &changes.added), ("removed", &changes.removed)] {
+        for requirement in requirements {
+            let line = format!(
+                "{} => {}_{} => {} => {}",
+                kind,
+                requirement.namespace,
+                requirement.version,
+                requirement.function,
+                requirement.files.join(", ")
+            );
+            match requirement.raises_floor {
+                true => println!("{}", color::red(&line)),
+                false => println!("{}", line),
+            }
+        }
+    }
+    for library in &changes.new_libraries {
+        let line = format!("new library => {}", library);
+        match is_baseline {
+            true => println!("{}", color::red(&line)),
+            false => println!("{}", line),
+        }
+    }
+    for library in &changes.dropped_libraries {
+        println!("dropped library => {}", library);
+    }
+    for floor in &changes.floors {
+        let line = format!(
+            "floor => {} => {} => {}",
+            floor.namespace,
+            floor.old.as_deref().unwrap_or("none"),
+            floor.new.as_deref().unwrap_or("none")
+        );
+        match floor.raised {
+            true => println!("{}", color::red(&line)),
+            false => println!("{}", line),
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// What the findings of a scan violate, the reasons the run fails besides
+/// the inputs that cannot be analyzed.
+#[derive(Debug, Default)]
+struct Offenses {
+    max_glibc: Vec<Offending>,
+    target_distro: Vec<Offending>,
+    policy: Vec<Offending>,
+    /// `GLIBC_PRIVATE` symbol => the files other than glibc's importing it
+    glibc_private: HashMap<String, HashSet<PathBuf>>,
+}
+
+/// What the report of a scan is built from.
+struct ScanReport<'a> {
+    args: &'a ScanArgs,
+    findings: &'a Findings,
+    errored: &'a HashMap<PathBuf, (String, HashSet<String>)>,
+    library_exports: &'a HashMap<PathBuf, symbols::Exports>,
+    inputs: &'a [inputs::Input],
+    workspace: &'a inputs::Workspace,
+    distro_table: &'a [distro::DistroRelease],
+    target_distro: Option<&'a distro::DistroRelease>,
+    abidb: Option<&'a abidb::AbiDb>,
+    policy: Option<&'static policy::Policy>,
+}
+
+impl ScanReport<'_> {
+    /// Returns what violates `--max-glibc`, `--target-distro` and
+    /// `--policy`, and the `GLIBC_PRIVATE` imports.
+    fn offenses(&self) -> Offenses {
+        let findings = self.findings;
+        let mut offenses = Offenses::default();
+        if let Some(max_glibc) = &self.args.max_glibc {
+            offenses.max_glibc = findings.newer_than("GLIBC", max_glibc);
+        }
+        if let Some(release) = self.target_distro {
+            offenses.target_distro = findings.newer_than("GLIBC", &release.glibc);
+            if let Some(glibcxx) = &release.glibcxx {
+                offenses
+                    .target_distro
+                    .extend(findings.newer_than("GLIBCXX", glibcxx));
+            }
+        }
+        if let Some(policy) = self.policy {
+            offenses.policy = match policy.glibc {
+                Some(max) => findings.newer_than("GLIBC", max),
+                None => findings.offending("GLIBC", |_, _| true),
+            };
+            for namespace in ["GLIBCXX", "CXXABI", "GCC"] {
+                if let Some(max) = policy.max_version(namespace) {
+                    offenses.policy.extend(findings.newer_than(namespace, max));
+                }
+            }
+            // what the allowed system libraries require themselves is up to
+            // the system they come with
+            for offending in offenses.policy.iter_mut() {
+                offending
+                    .files
+                    .retain(|file| !findings.allowed_library_files.contains(file));
+            }
+            offenses
+                .policy
+                .retain(|offending| !offending.files.is_empty());
+        }
+        // GLIBC_PRIVATE symbols change between glibc point releases, only
+        // glibc itself may use them
+        offenses.glibc_private = findings
+            .wants
+            .get("GLIBC")
+            .and_then(|versions| versions.get("PRIVATE"))
+            .into_iter()
+            .flatten()
+            .filter_map(|(function, files)| {
+                let files = files
+                    .iter()
+                    .filter(|file| {
+                        !file
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(is_glibc_library)
+                    })
+                    .cloned()
+                    .collect::<HashSet<PathBuf>>();
+                (!files.is_empty()).then(|| (function.clone(), files))
+            })
+            .collect::<HashMap<String, HashSet<PathBuf>>>();
+        offenses
+    }
+
+    /// Prints the report in text mode and returns the versions `namespace`
+    /// requires, for each input unless `--merge` is given.
+    fn versions(
+        &self,
+        namespace: &str,
+        per_input: Option<&[(String, Findings)]>,
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        let Some(per_input) = per_input else {
+            return versions_report(
+                namespace,
+                self.findings,
+                self.library_exports,
+                self.args,
+                "",
+            );
+        };
+        let mut report = serde_json::Map::new();
+        for (input, input_findings) in per_input {
+            // a single input needs no telling apart
+            let prefix = if per_input.len() > 1 {
+                format!("{} => ", input)
+            } else {
+                String::new()
+            };
+            let versions = versions_report(
+                namespace,
+                input_findings,
+                self.library_exports,
+                self.args,
+                &prefix,
+            )?;
+            report.insert(input.clone(), versions);
+        }
+        Ok(serde_json::Value::Object(report))
+    }
+
+    /// Prints the report in text mode and returns the `GLIBC` versions
+    /// required, and the additional report sections asked for.
+    fn sections(
+        &self,
+        summary: Option<serde_json::Value>,
+        offenses: &Offenses,
+    ) -> Result<
+        (
+            serde_json::Value,
+            serde_json::Map<String, serde_json::Value>,
+        ),
+        Box<dyn Error>,
+    > {
+        let findings = self.findings;
+        let parsed_args = self.args;
+        // what each input requires on its own, unless asked for all together
+        let per_input = (!parsed_args.merge).then(|| {
+            findings
+                .closures
+                .iter()
+                .map(|(input, closure)| (input.clone(), findings.restricted_to(closure)))
+                .collect::<Vec<(String, Findings)>>()
+        });
+        let report = |namespace: &str| self.versions(namespace, per_input.as_deref());
+        let wants_json = report("GLIBC")?;
+
+        // additional report sections, the json only turns into an object
+        // keyed by section when at least one of them is requested
+        let mut sections = serde_json::Map::new();
+        if let Some(summary) = summary {
+            sections.insert("summary".to_string(), summary);
+        }
+        if parsed_args.stats {
+            sections.insert("stats".to_string(), stats_section(findings, parsed_args));
+        }
+        if parsed_args.why_max {
+            let why_max = why_max_section(findings, parsed_args)?;
+            sections.insert("why_max".to_string(), why_max);
+        }
+        if !parsed_args.find_symbols.is_empty() {
+            let importers = find_symbol_section(&parsed_args.find_symbols, findings, parsed_args)?;
+            sections.insert("find_symbol".to_string(), importers);
+        }
+        if parsed_args.distros {
+            let distros = distros_section(findings, self.distro_table, parsed_args)?;
+            sections.insert("distros".to_string(), distros);
+        }
+        let mut namespaces = serde_json::Map::new();
+        if parsed_args.namespaces.is_empty() {
+            for namespace in DEFAULT_NAMESPACES {
+                if findings.wants.contains_key(namespace) {
+                    namespaces.insert(namespace.to_string(), report(namespace)?);
+                }
+            }
+        } else {
+            // namespaces that were asked for are reported even when nothing
+            // requires them
+            for namespace in parsed_args.namespaces.iter().filter(|ns| *ns != "GLIBC") {
+                namespaces.insert(namespace.to_string(), report(namespace)?);
+            }
+        }
+        if !namespaces.is_empty() {
+            sections.insert(
+                "namespaces".to_string(),
+                serde_json::Value::Object(namespaces),
+            );
+            // the libstdc++ requirements boil down to the GCC release the
+            // build image has to ship
+            let gcc = gcc_section(findings, &["GLIBCXX", "CXXABI"], "libstdc++", parsed_args)?;
+            if let Some(gcc) = gcc {
+                sections.insert("gcc".to_string(), gcc);
+            }
+            let libgcc = gcc_section(findings, &["GCC"], "libgcc_s", parsed_args)?;
+            if let Some(libgcc) = libgcc {
+                sections.insert("libgcc".to_string(), libgcc);
+            }
+        }
+        if parsed_args.show_unversioned {
+            let unversioned = symbol_section("unversioned", &findings.unversioned, parsed_args)?;
+            sections.insert("unversioned".to_string(), unversioned);
+        }
+        if parsed_args.show_ifunc {
+            let ifuncs = symbol_section("ifunc", &findings.ifuncs, parsed_args)?;
+            sections.insert("ifuncs".to_string(), ifuncs);
+        }
+        if let Some(max_glibc) = &parsed_args.max_glibc {
+            let max_glibc = self.max_glibc_section(max_glibc, &offenses.max_glibc)?;
+            sections.insert("max_glibc".to_string(), max_glibc);
+        }
+        if let Some(abidb) = self.abidb {
+            let missing = findings.offending("GLIBC", |version, symbol| {
+                !abidb.provides(symbol, &format!("GLIBC_{}", version))
+            });
+            let missing = offending_section("not in abidb", &missing, parsed_args)?;
+            sections.insert("abidb_missing".to_string(), missing);
+        }
+        if let Some(release) = self.target_distro {
+            let target = target_distro_section(release, &offenses.target_distro, parsed_args)?;
+            sections.insert("target_distro".to_string(), target);
+        }
+        if parsed_args.runs_on {
+            let runs_on = runs_on_section(findings, self.distro_table, parsed_args);
+            sections.insert("runs_on".to_string(), runs_on);
+        }
+        if self.inputs.iter().any(|given| given.relative.is_some()) {
+            // files found in directories and archives are reported one by
+            // one as well, and archives as a whole
+            let (files, archives) = self.input_closures();
+            let files = closures_section("file", &files, findings, parsed_args)?;
+            sections.insert("files".to_string(), files);
+            if !archives.is_empty() {
+                let archives = closures_section("archive", &archives, findings, parsed_args)?;
+                sections.insert("archives".to_string(), archives);
+            }
+        }
+        if parsed_args.glibc_summary {
+            let summary =
+                glibc_summary_section(self.inputs, findings, self.workspace, parsed_args)?;
+            sections.insert("glibc_summary".to_string(), summary);
+        }
+        if parsed_args.per_library {
+            let libraries = libraries_section(findings, parsed_args)?;
+            sections.insert("libraries".to_string(), libraries);
+        }
+        if !self.workspace.appimages().is_empty() {
+            let host = host_section(self.workspace.appimages(), findings, parsed_args)?;
+            sections.insert("host".to_string(), host);
+        }
+        if let Some(policy) = self.policy {
+            let policy = policy_section(policy, &offenses.policy, findings, parsed_args)?;
+            sections.insert("policy".to_string(), policy);
+        }
+        // Alpine packages are meant for musl whatever --target-libc says
+        if parsed_args.target_libc.is_musl() || self.workspace.has_alpine_packages() {
+            let musl =
+                flagged_imports_section("musl", findings, parsed_args, musl_incompatibility)?;
+            sections.insert("musl".to_string(), musl);
+        }
+        if let Some(api) = parsed_args.android_api {
+            let android = android_section(api, findings, parsed_args)?;
+            sections.insert("android".to_string(), android);
+        }
+        if parsed_args.show_deprecated {
+            let deprecated =
+                flagged_imports_section("deprecated", findings, parsed_args, |name, _| {
+                    deprecated::deprecation(name)
+                })?;
+            sections.insert("deprecated".to_string(), deprecated);
+        }
+        if parsed_args.show_kernel {
+            let kernel = kernel_section(&findings.kernel_versions, parsed_args)?;
+            sections.insert("kernel".to_string(), kernel);
+        }
+        if parsed_args.show_build_id {
+            let build_ids = build_ids_section(&findings.build_ids, parsed_args)?;
+            sections.insert("build_ids".to_string(), build_ids);
+        }
+        if parsed_args.show_properties {
+            let properties = properties_section(&findings.properties, parsed_args)?;
+            sections.insert("properties".to_string(), properties);
+        }
+        if !offenses.glibc_private.is_empty() {
+            let label = color::yellow("GLIBC_PRIVATE");
+            let private = symbol_section(&label, &offenses.glibc_private, parsed_args)?;
+            sections.insert("glibc_private".to_string(), private);
+        }
+        if !findings.static_inputs.is_empty() {
+            let linkage = static_section(&findings.static_inputs, parsed_args)?;
+            sections.insert("static".to_string(), linkage);
+        }
+        if parsed_args.check_duplicates {
+            let duplicates = duplicates_section(&findings.duplicates, parsed_args)?;
+            sections.insert("duplicates".to_string(), duplicates);
+        }
+        if parsed_args.check_resolution {
+            let unresolved = unresolved_section(&findings.unresolved, parsed_args)?;
+            sections.insert("unresolved".to_string(), unresolved);
+        }
+        Ok((wants_json, sections))
+    }
+
+    /// Prints the symbols newer than `--max-glibc` in text mode, and the
+    /// archives (or installed packages) whose own files import them, with
+    /// the highest version they need.
+    fn max_glibc_section(
+        &self,
+        max_glibc: &str,
+        offending: &[Offending],
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        let heading = format!("too new for glibc {}", max_glibc);
+        let offending_json = offending_section(&heading, offending, self.args)?;
+        let mut section = serde_json::json!({
+            "max": max_glibc,
+            "offending": offending_json,
+        });
+        if !self.inputs.iter().any(|given| given.archive.is_some()) {
+            return Ok(section);
+        }
+        let owners = self
+            .inputs
+            .iter()
+            .filter_map(|given| {
+                let archive = given.archive.as_ref()?;
+                Some((self.workspace.display_path(Path::new(&given.path)), archive))
+            })
+            .collect::<HashMap<PathBuf, &String>>();
+        let mut archives = BTreeMap::new();
+        for entry in offending {
+            for file in &entry.files {
+                if let Some(archive) = owners.get(file) {
+                    archives
+                        .entry(archive.to_string())
+                        .or_insert_with(|| entry.version.clone());
+                }
+            }
+        }
+        if self.args.stdout_format.is_text() {
+            for (archive, version) in &archives {
+                let line = format!("{} => archive => {} => {}", heading, archive, version);
+                println!("{}", color::red(&line));
+            }
+        }
+        section["archives"] = serde_json::to_value(archives)?;
+        Ok(section)
+    }
+
+    /// Returns the closures of the files found in directories and archives,
+    /// by their path relative to them, and the closures of the archives as
+    /// a whole.
+    fn input_closures(
+        &self,
+    ) -> (
+        BTreeMap<String, HashSet<PathBuf>>,
+        BTreeMap<String, HashSet<PathBuf>>,
+    ) {
+        let mut files = BTreeMap::new();
+        let mut archives: BTreeMap<String, HashSet<PathBuf>> = BTreeMap::new();
+        for given in self.inputs {
+            let path = self.workspace.display_path(Path::new(&given.path));
+            let Some(closure) = self.findings.closures.get(&path.display().to_string()) else {
+                continue;
+            };
+            if let Some(relative) = &given.relative {
+                files.insert(relative.clone(), closure.clone());
+            }
+            if let Some(archive) = &given.archive {
+                archives
+                    .entry(archive.clone())
+                    .or_default()
+                    .extend(closure.iter().cloned());
+            }
+        }
+        (files, archives)
+    }
+
+    /// Returns the reasons the run fails, for the formats meant for CI
+    /// systems.
+    fn violations(&self, offenses: &Offenses, required: &[String]) -> Vec<violations::Violation> {
+        let demangle = self.args.demangle;
+        let mut violations = Vec::new();
+        let mut add_offending = |rule, offending: &[Offending], limit: &str| {
+            for entry in offending {
+                let symbol = display_symbol(&entry.symbol, demangle);
+                for file in &entry.files {
+                    violations.push(violations::Violation {
+                        rule,
+                        message: format!("{} requires {}, {}", symbol, entry.version, limit),
+                        file: Some(file.clone()),
+                    });
+                }
+            }
+        };
+        if let Some(max_glibc) = &self.args.max_glibc {
+            let limit = format!("newer than glibc {}", max_glibc);
+            add_offending("max-glibc", &offenses.max_glibc, &limit);
+        }
+        if let Some(release) = self.target_distro {
+            let limit = format!("newer than {} {} ships", release.distro, release.release);
+            add_offending("target-distro", &offenses.target_distro, &limit);
+        }
+        if let Some(policy) = self.policy {
+            let limit = format!("newer than {} allows", policy.name);
+            add_offending("policy", &offenses.policy, &limit);
+            for library in &self.findings.disallowed_libraries {
+                violations.push(violations::Violation {
+                    rule: "policy-library",
+                    message: format!("{} is not allowed by {}", library, policy.name),
+                    file: None,
+                });
+            }
+        }
+        let mut unresolved = self.findings.unresolved.iter().collect::<Vec<_>>();
+        unresolved.sort_by_key(|(input, _)| *input);
+        for (input, symbols) in unresolved {
+            let mut symbols = symbols.iter().collect::<Vec<&String>>();
+            symbols.sort();
+            for symbol in symbols {
+                violations.push(violations::Violation {
+                    rule: "unresolved-symbol",
+                    message: format!(
+                        "{} is not defined by any library in the dependency closure",
+                        display_symbol(symbol, demangle)
+                    ),
+                    file: Some(input.clone()),
+                });
+            }
+        }
+        if self.args.fail_on_private {
+            let mut private = offenses.glibc_private.iter().collect::<Vec<_>>();
+            private.sort_by_key(|(symbol, _)| *symbol);
+            for (symbol, files) in private {
+                let mut files = files.iter().collect::<Vec<&PathBuf>>();
+                files.sort();
+                for file in files {
+                    violations.push(violations::Violation {
+                        rule: "glibc-private",
+                        message: format!("{} is a GLIBC_PRIVATE symbol", symbol),
+                        file: Some(file.clone()),
+                    });
+                }
+            }
+        }
+        for path in required {
+            if let Some((error, _)) = self.errored.get(Path::new(path)) {
+                violations.push(violations::Violation {
+                    rule: "unreadable-input",
+                    message: format!("{} => {}", path, error),
+                    file: Some(PathBuf::from(path)),
+                });
+            }
+        }
+        violations
+    }
+
+    /// Prints the report in the `--stdout` format and saves it in the
+    /// formats asked for with the `--save-*-to` options.
+    fn write_outputs(
+        &self,
+        wants_json: serde_json::Value,
+        mut sections: serde_json::Map<String, serde_json::Value>,
+        violations: &[violations::Violation],
+    ) -> Result<(), Box<dyn Error>> {
+        let findings = self.findings;
+        let parsed_args = self.args;
+        // the protobuf report holds what the envelope does
+        let envelope = if parsed_args.envelope || parsed_args.stdout_format.is_proto() {
+            let sections = serde_json::Value::Object(sections.clone());
+            Some(envelope(findings, sections, self.errored, parsed_args))
+        } else {
+            None
+        };
+        if !self.errored.is_empty() {
+            sections.insert("errors".to_string(), errors_json(self.errored));
+        }
+        let wants_json = if sections.is_empty() {
+            wants_json
+        } else {
+            sections.insert("versions".to_string(), wants_json);
+            serde_json::Value::Object(sections)
+        };
+        let report = envelope
+            .as_ref()
+            .filter(|_| parsed_args.envelope)
+            .unwrap_or(&wants_json);
+        let json = if parsed_args.pretty_json {
+            serde_json::to_string_pretty(report)?
+        } else {
+            serde_json::to_string(report)?
+        };
+
+        if let Some(save_json_to) = &parsed_args.save_json_to {
+            std::fs::write(save_json_to, &json)?;
+        }
+        if parsed_args.stdout_format.is_json() {
+            println!("{}", json);
+        }
+        if let Some(save_junit_to) = &parsed_args.save_junit_to {
+            std::fs::write(
+                save_junit_to,
+                junit::report(&junit_cases(findings, violations)),
+            )?;
+        }
+        if let Some(save_html_to) = &parsed_args.save_html_to {
+            let inputs = findings
+                .closures
+                .iter()
+                .map(|(input, closure)| {
+                    let version = findings.highest_version_in("GLIBC", closure);
+                    (
+                        input.clone(),
+                        version.map(|version| format!("GLIBC_{}", version)),
+                    )
+                })
+                .collect::<Vec<(String, Option<String>)>>();
+            let versions = version_tree(findings, parsed_args);
+            std::fs::write(save_html_to, html::report(&inputs, violations, &versions))?;
+        }
+        if parsed_args.save_dot_to.is_some() || parsed_args.stdout_format.is_mermaid() {
+            let graph = dependency_graph(findings, parsed_args);
+            if let Some(save_dot_to) = &parsed_args.save_dot_to {
+                std::fs::write(save_dot_to, graph.to_dot())?;
+            }
+            if parsed_args.stdout_format.is_mermaid() {
+                print!("{}", graph.to_mermaid());
+            }
+        }
+        if let Some(save_codequality_to) = &parsed_args.save_codequality_to {
+            let requirements = findings
+                .closures
+                .iter()
+                .filter_map(|(input, closure)| {
+                    let version = findings.highest_version_in("GLIBC", closure)?;
+                    Some((input.clone(), format!("GLIBC_{}", version)))
+                })
+                .collect::<Vec<(String, String)>>();
+            let report = gitlab::report(violations, &requirements);
+            std::fs::write(save_codequality_to, serde_json::to_string_pretty(&report)?)?;
+        }
+        if parsed_args.stdout_format.is_github() {
+            for annotation in github::annotations(violations) {
+                println!("{}", annotation);
+            }
+        }
+        if let Some(template) = parsed_args.format.as_ref() {
+            if parsed_args.stdout_format.is_template() {
+                // the same line is printed once, for templates leaving out
+                // the fields that tell the requirements apart
+                let mut printed = HashSet::new();
+                for fields in template_rows(findings, self.library_exports, parsed_args) {
+                    let line = template.render(&fields);
+                    if printed.insert(line.clone()) {
+                        println!("{}", line);
+                    }
+                }
+            }
+        }
+        if parsed_args.stdout_format.is_spdx() || parsed_args.stdout_format.is_cyclonedx() {
+            let (components, dependencies) = sbom_components(findings);
+            let bom = if parsed_args.stdout_format.is_spdx() {
+                sbom::spdx(&components, &dependencies)
+            } else {
+                sbom::cyclonedx(&components, &dependencies)
+            };
+            if parsed_args.pretty_json {
+                println!("{}", serde_json::to_string_pretty(&bom)?);
+            } else {
+                println!("{}", bom);
+            }
+        }
+        if parsed_args.stdout_format.is_markdown() {
+            let newest_glibc = findings
+                .closures
+                .values()
+                .filter_map(|closure| findings.highest_version_in("GLIBC", closure))
+                .max_by_key(|version| distro::version_key(version))
+                .map(|version| format!("GLIBC_{}", version));
+            let (header, rows) = markdown_rows(findings, parsed_args);
+            let report = markdown::report(
+                findings.closures.len(),
+                newest_glibc.as_deref(),
+                violations,
+                &header,
+                &rows,
+            );
+            print!("{}", report);
+        }
+        if let Some(envelope) = envelope
+            .as_ref()
+            .filter(|_| parsed_args.stdout_format.is_proto())
+        {
+            std::io::Write::write_all(&mut std::io::stdout(), &proto::report(envelope))?;
+        }
+        if parsed_args.stdout_format.is_sarif() {
+            let sarif = sarif::report(violations);
+            if parsed_args.pretty_json {
+                println!("{}", serde_json::to_string_pretty(&sarif)?);
+            } else {
+                println!("{}", sarif);
+            }
+        }
+        if parsed_args.stdout_format.is_ndjson() {
+            // the versions were streamed, the other sections follow one per
+            // line
+            if let serde_json::Value::Object(sections) = &wants_json {
+                let is_streamed = |name: &str| name == "versions" || name == "namespaces";
+                for (name, value) in sections.iter().filter(|(name, _)| !is_streamed(name)) {
+                    let section = serde_json::json!({
+                        "type": "section",
+                        "name": name,
+                        "value": value,
+                    });
+                    println!("{}", section);
+                }
+            }
+        }
+        if parsed_args.stdout_format.is_table() {
+            // the columns beyond the detail level would be empty
+            let columns = match parsed_args.detail_level {
+                DetailLevel::Version => 1,
+                DetailLevel::Function => 2,
+                DetailLevel::File => 4,
+            };
+            let header = ["Version", "Function", "Library", "Referenced by"];
+            let rows = csv_rows(findings, parsed_args)
+                .into_iter()
+                .map(|row| row[..columns].to_vec())
+                .collect::<Vec<Vec<String>>>();
+            let width = table::terminal_width();
+            print!("{}", table::render(&header[..columns], &rows, width));
+        }
+        if let Some(separator) = match parsed_args.stdout_format {
+            StdoutFormat::Csv => Some(','),
+            StdoutFormat::Tsv => Some('\t'),
+            _ => None,
+        } {
+            println!("{}", csv::format_row(&csv::HEADER, separator));
+            for row in csv_rows(findings, parsed_args) {
+                println!("{}", csv::format_row(&row, separator));
+            }
+        }
+        if let Some(save_msgpack_to) = &parsed_args.save_msgpack_to {
+            std::fs::write(save_msgpack_to, msgpack::encode(report))?;
+        }
+        if parsed_args.save_yaml_to.is_some() || parsed_args.stdout_format.is_yaml() {
+            let yaml = yaml::to_string(report);
+            if let Some(save_yaml_to) = &parsed_args.save_yaml_to {
+                std::fs::write(save_yaml_to, &yaml)?;
+            }
+            if parsed_args.stdout_format.is_yaml() {
+                print!("{}", yaml);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the JUnit test cases: an input fails on the violations of the
+/// files it loads, and those without a file fail a test case of their own.
+fn junit_cases<'a>(
+    findings: &Findings,
+    violations: &'a [violations::Violation],
+) -> Vec<(String, Vec<&'a violations::Violation>)> {
+    let mut cases = findings
+        .closures
+        .iter()
+        .map(|(input, closure)| {
+            let failures = violations
+                .iter()
+                .filter(|violation| {
+                    violation
+                        .file
+                        .as_ref()
+                        .is_some_and(|file| closure.contains(file) || file == Path::new(input))
+                })
+                .collect::<Vec<&violations::Violation>>();
+            (input.clone(), failures)
+        })
+        .collect::<Vec<(String, Vec<&violations::Violation>)>>();
+    let unattributed = violations
+        .iter()
+        .filter(|violation| violation.file.is_none())
+        .collect::<Vec<&violations::Violation>>();
+    if !unattributed.is_empty() {
+        cases.push(("lddcheck".to_string(), unattributed));
+    }
+    cases
+}
+
+/// Prints the files that could not be analyzed, those of the kind
+/// `--print-error` asks for, on stderr.
+fn print_errors(errored: &HashMap<PathBuf, (String, HashSet<String>)>, args: &ScanArgs) {
+    let kind = match args.print_error {
+        PrintError::None => return,
+        PrintError::All => None,
+        PrintError::CannotParse => Some("cannot_parse"),
+        PrintError::CannotRead => Some("cannot_read"),
+        PrintError::NotFound => Some("not_found"),
+        PrintError::WrongArch => Some("wrong_arch"),
+        PrintError::SonameMismatch => Some("soname_mismatch"),
+        PrintError::CannotUnpack => Some("cannot_unpack"),
+        PrintError::BadInterpreter => Some("bad_interpreter"),
+    };
+    for (path, (error, names)) in errored {
+        if kind.is_some_and(|kind| kind != error) {
+            continue;
+        }
+        for name in names {
+            // one JSON object per line for the tools wrapping lddcheck
+            if args.error_format.is_json() {
+                let error = serde_json::json!({
+                    "kind": error,
+                    "path": path.display().to_string(),
+                    "referenced_by": name,
+                });
+                eprintln!("{}", error);
+                continue;
+            }
+            let line = match kind {
+                None => format!(
+                    "file={}, reason={}, referenced_by={}",
+                    path.display(),
+                    error,
+                    name
+                ),
+                Some(_) => format!("{} => {} => {}", path.display(), error, name),
+            };
+            eprintln!("{}", color::warning(&line));
+        }
+    }
+}
+
+/// Prints the distribution releases that first shipped each of the highest
+/// glibc versions required in text mode, and returns them as JSON.
+fn distros_section(
+    findings: &Findings,
+    distro_table: &[distro::DistroRelease],
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut distros = serde_json::Map::new();
+    for version in findings.highest_versions("GLIBC", args.versions) {
+        let releases = distro::first_releases(distro_table, version);
+        if args.stdout_format.is_text() {
+            let releases = releases
+                .iter()
+                .map(|release| format!("{}+", release))
+                .collect::<Vec<String>>();
+            println!("GLIBC_{} => {}", version, releases.join(", "));
+        }
+        distros.insert(version.to_string(), serde_json::to_value(releases)?);
+    }
+    Ok(serde_json::Value::Object(distros))
+}
+
+/// Prints the oldest GCC release whose `library` provides the versions of
+/// `namespaces` required in text mode, and returns it as JSON, if any.
+fn gcc_section(
+    findings: &Findings,
+    namespaces: &[&str],
+    library: &str,
+    args: &ScanArgs,
+) -> Result<Option<serde_json::Value>, serde_json::Error> {
+    let gcc = libstdcxx::newest_gcc(namespaces.iter().flat_map(|namespace| {
+        findings
+            .wants
+            .get(*namespace)
+            .into_iter()
+            .flat_map(|versions| versions.keys())
+            .filter_map(|version| libstdcxx::minimum_gcc(namespace, version))
+    }));
+    let Some(gcc) = gcc else {
+        return Ok(None);
+    };
+    if args.stdout_format.is_text() {
+        println!("requires {} from GCC >= {}", library, gcc);
+    }
+    serde_json::to_value(gcc).map(Some)
+}
+
+/// Prints the symbols newer than `release` ships in text mode, and returns
+/// them as JSON with the release.
+fn target_distro_section(
+    release: &distro::DistroRelease,
+    offending: &[Offending],
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let label = format!("{} {}", release.distro, release.release);
+    let heading = format!("too new for {}", label);
+    let offending = offending_section(&heading, offending, args)?;
+    Ok(serde_json::json!({
+        "release": label,
+        "glibc": release.glibc,
+        "glibcxx": release.glibcxx,
+        "offending": offending,
+    }))
+}
+
+/// Prints the oldest releases of each distribution every input runs on in
+/// text mode, and returns them as JSON with what the inputs require.
+fn runs_on_section(
+    findings: &Findings,
+    distro_table: &[distro::DistroRelease],
+    args: &ScanArgs,
+) -> serde_json::Value {
+    let mut runs_on = serde_json::Map::new();
+    for (input, closure) in &findings.closures {
+        let requirements = findings.requirements_of(closure);
+        let releases = distro::viable_releases(distro_table, &requirements);
+        if args.stdout_format.is_text() {
+            if releases.is_empty() {
+                println!("runs on => {} => no known release", input);
+            } else {
+                println!("runs on => {} => {} or newer", input, releases.join(" / "));
+            }
+        }
+        runs_on.insert(
+            input.clone(),
+            serde_json::json!({
+                "requires": requirements,
+                "releases": releases,
+            }),
+        );
+    }
+    serde_json::Value::Object(runs_on)
+}
+
+/// Prints what every library requires on its own in text mode, to tell
+/// which one raises the requirement, and returns it as JSON.
+fn libraries_section(
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let inputs = findings
+        .closures
+        .keys()
+        .map(PathBuf::from)
+        .collect::<HashSet<PathBuf>>();
+    let libraries = findings
+        .closures
+        .values()
+        .flatten()
+        .filter(|file| !inputs.contains(*file))
+        .map(|file| (file.display().to_string(), HashSet::from([file.clone()])))
+        .collect::<BTreeMap<String, HashSet<PathBuf>>>();
+    closures_section("library", &libraries, findings, args)
+}
+
+/// Prints what each AppImage still takes from the host in text mode: the
+/// libraries it does not bundle, and the glibc symbols its files import.
+/// Returns them as JSON.
+fn host_section(
+    appimages: &[String],
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut host = serde_json::Map::new();
+    for appimage in appimages {
+        let prefix = format!("{}!", appimage);
+        let is_bundled = |file: &Path| file.to_string_lossy().starts_with(&prefix);
+        let libraries = findings
+            .closures
+            .iter()
+            .filter(|(input, _)| input.starts_with(&prefix))
+            .flat_map(|(_, closure)| closure.iter())
+            .filter(|file| !is_bundled(file))
+            .cloned()
+            .collect::<BTreeSet<PathBuf>>();
+        if args.stdout_format.is_text() {
+            for library in &libraries {
+                println!("host library => {} => {}", appimage, library.display());
+            }
+        }
+        let mut leaks = findings.offending("GLIBC", |_, _| true);
+        for entry in &mut leaks {
+            entry.files.retain(|file| is_bundled(file));
+        }
+        leaks.retain(|entry| !entry.files.is_empty());
+        let heading = format!("host glibc => {}", appimage);
+        let leaks = offending_section(&heading, &leaks, args)?;
+        host.insert(
+            appimage.clone(),
+            serde_json::json!({
+                "libraries": libraries,
+                "glibc": leaks,
+            }),
+        );
+    }
+    Ok(serde_json::Value::Object(host))
+}
+
+/// Prints the symbols and the libraries `policy` does not allow in text
+/// mode, and returns them as JSON.
+fn policy_section(
+    policy: &policy::Policy,
+    offending: &[Offending],
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let heading = format!("violates {}", policy.name);
+    let offending = offending_section(&heading, offending, args)?;
+    if args.stdout_format.is_text() {
+        for library in &findings.disallowed_libraries {
+            let line = format!("not allowed by {} => {}", policy.name, library);
+            println!("{}", color::red(&line));
+        }
+    }
+    Ok(serde_json::json!({
+        "policy": policy.name,
+        "offending": offending,
+        "libraries": findings.disallowed_libraries,
+    }))
+}
+
+/// Prints the build-id of every file in text mode, and returns them as
+/// JSON.
+fn build_ids_section(
+    build_ids: &HashMap<PathBuf, String>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if args.stdout_format.is_text() {
+        let mut files = build_ids.iter().collect::<Vec<_>>();
+        files.sort();
+        for (file, build_id) in &files {
+            println!("build-id => {} => {}", build_id, file.display());
+        }
+    }
+    serde_json::to_value(build_ids)
+}
+
+/// Prints the statically linked inputs in text mode, and returns them as
+/// JSON.
+fn static_section(
+    static_inputs: &HashMap<PathBuf, &'static str>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if args.stdout_format.is_text() {
+        let mut inputs = static_inputs.iter().collect::<Vec<_>>();
+        inputs.sort();
+        for (input, linkage) in inputs {
+            println!("{} => {}", linkage, input.display());
+        }
+    }
+    serde_json::to_value(static_inputs)
+}
+
+/// Prints the libraries shadowing others of the same name in text mode,
+/// and returns them as JSON.
+fn duplicates_section(
+    duplicates: &HashMap<String, Duplicate>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if args.stdout_format.is_text() {
+        let mut names = duplicates.keys().collect::<Vec<&String>>();
+        names.sort();
+        for name in names {
+            let duplicate = &duplicates[name];
+            for shadowed in &duplicate.shadowed {
+                println!(
+                    "{} => {} => {} => shadows {}",
+                    color::yellow("duplicate"),
+                    name,
+                    duplicate.loaded.display(),
+                    shadowed.display()
+                );
+            }
+        }
+    }
+    serde_json::to_value(duplicates)
+}
+
+/// Prints the imports no library in the dependency closure defines in text
+/// mode, and returns them as JSON.
+fn unresolved_section(
+    unresolved: &HashMap<PathBuf, HashSet<String>>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if args.stdout_format.is_text() {
+        for (input, symbols) in unresolved {
+            for symbol in symbols {
+                let line = format!(
+                    "unresolved => {} => {}",
+                    display_symbol(symbol, args.demangle),
+                    input.display()
+                );
+                println!("{}", color::red(&line));
+            }
+        }
+    }
+    serde_json::to_value(unresolved)
+}
+
+/// Runs `command`, with the `--root` given, if any.
+fn run_command(command: &Command, root: Option<&str>) -> Result<(), Box<dyn Error>> {
+    // the commands analyzing a filesystem of their own take no --root
+    let own_root = |name: &str| match root {
+        Some(_) => Err(format!("{} does not take --root, it brings its own", name)),
+        None => Ok(()),
+    };
+    let root = root.unwrap_or("/");
+    match command {
+        Command::Scan { input } => scan_inputs(input, Path::new(root)),
+        Command::GenAbidb { inputs, output } => {
+            let mut db = abidb::AbiDb::default();
+            for input in inputs {
+                db.add_path(Path::new(input))?;
+            }
+            std::fs::write(output, serde_json::to_string_pretty(&db)?)?;
+            Ok(())
+        }
+        Command::Manifest { output } => {
+            let manifest = manifest::Manifest::generate(Path::new(root));
+            std::fs::write(output, serde_json::to_string_pretty(&manifest)?)?;
+            Ok(())
+        }
+        Command::Schema { proto } => {
+            if *proto {
+                print!("{}", proto::DEFINITION);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&schema::json_schema())?);
+            }
+            Ok(())
+        }
+        Command::Check {
+            against,
+            policy,
+            ld_library_path,
+            stdout_format,
+            paths,
+        } => match against {
+            Some(against) => check_manifest(Path::new(against), paths),
+            None => {
+                let policy = Path::new(policy.as_deref().unwrap_or_default());
+                let format = stdout_format.unwrap_or_default();
+                check_policy_file(policy, Path::new(root), ld_library_path, format, paths)
+            }
+        },
+        Command::Repair {
+            policy,
+            ld_library_path,
+            paths,
+        } => {
+            let Some(policy) = policy::find_policy(policy) else {
+                return Err(format!("unknown policy {}", policy).into());
+            };
+            let analyzer = DependencyAnalyzer::new(PathBuf::from(root))
+                .library_paths(ld_library_path.iter().map(PathBuf::from).collect());
+            for path in paths {
+                for (soname, copy) in repair::repair(Path::new(path), policy, analyzer.clone())? {
+                    println!("vendored => {} => {} => {}", path, soname, copy.display());
+                }
+            }
+            Ok(())
+        }
+        Command::Image {
+            reference,
+            from_tar,
+            all,
+            scan: scan_args,
+        } => {
+            own_root("image")?;
+            scan_image(reference.as_deref(), from_tar.as_deref(), *all, scan_args)
+        }
+        Command::Flatpak {
+            dir,
+            runtime,
+            runtime_dir,
+            scan: scan_args,
+        } => {
+            own_root("flatpak")?;
+            let runtime_dir = runtime_dir.as_deref().map(Path::new);
+            scan_flatpak(Path::new(dir), runtime.as_deref(), runtime_dir, scan_args)
+        }
+        Command::CondaEnv {
+            prefix,
+            scan: scan_args,
+        } => {
+            own_root("conda-env")?;
+            scan_conda_env(Path::new(prefix), scan_args)
+        }
+        Command::Pid {
+            pid,
+            scan: scan_args,
+        } => {
+            own_root("pid")?;
+            scan_process(*pid, scan_args)
+        }
+        Command::Rootfs {
+            dir,
+            scan: scan_args,
+        } => {
+            own_root("rootfs")?;
+            scan_rootfs(Path::new(dir), scan_args)
+        }
+        Command::Snap {
+            snap,
+            base,
+            scan: scan_args,
+        } => {
+            own_root("snap")?;
+            scan_snap(Path::new(snap), base.as_deref().map(Path::new), scan_args)
+        }
+        Command::Why { query, input } => {
+            let mut input = input.as_ref().clone();
+            input.scan.why = Some(query.clone());
+            scan_inputs(&input, Path::new(root))
+        }
+        Command::Diff {
+            old,
+            new,
+            baseline,
+            paths,
+            scan: scan_args,
+        } => {
+            let baseline = baseline.as_deref().map(Path::new);
+            let builds = old.as_ref().zip(new.as_ref());
+            scan_diff(baseline, builds, paths, Path::new(root), scan_args)
+        }
+        Command::Tree {
+            paths,
+            scan: scan_args,
+        } => {
+            let scan_args = ScanArgs {
+                tree: true,
+                ..scan_args.as_ref().clone()
+            };
+            let mut workspace = inputs::Workspace::default();
+            let inputs = paths
+                .iter()
+                .flat_map(|path| inputs::expand(path, &mut workspace))
+                .collect();
+            scan(&scan_args, Path::new(root), inputs, &workspace, paths)
+        }
+    }
+}
+
+/// Prints the imports of `paths` the manifest `against` does not list, and
+/// fails if there are any.
+fn check_manifest(against: &Path, paths: &[String]) -> Result<(), Box<dyn Error>> {
+    let manifest = manifest::Manifest::read(against)?;
+    let mut failed = false;
+    for path in paths {
+        if !Path::new(path).is_file() {
+            eprintln!("cannot read => {}", path);
+            failed = true;
+            continue;
+        }
+        for (version, symbol) in manifest.missing_imports(Path::new(path)) {
+            println!("not in manifest => {} => {} => {}", version, symbol, path);
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints what `paths` violate of the policy file `policy` in `format`,
+/// resolving their libraries under `root`, and fails if there is anything.
+fn check_policy_file(
+    policy: &Path,
+    root: &Path,
+    ld_library_path: &[String],
+    format: CheckFormat,
+    paths: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let policy = policy_file::PolicyFile::read(policy)?;
+    let analyzer = DependencyAnalyzer::new(root.to_path_buf())
+        .library_paths(ld_library_path.iter().map(PathBuf::from).collect());
+    let violations = policy_file_violations(&policy, paths, analyzer);
+    match format {
+        CheckFormat::Text => {
+            for violation in &violations {
+                let file = violation.file.as_deref().unwrap_or(Path::new(""));
+                println!(
+                    "{} => {} => {}",
+                    violation.rule,
+                    violation.message,
+                    file.display()
+                );
+            }
+        }
+        CheckFormat::Json => {
+            let violations = violations
+                .iter()
+                .map(|violation| {
+                    serde_json::json!({
+                        "rule": violation.rule,
+                        "message": violation.message,
+                        "file": violation.file,
+                    })
+                })
+                .collect::<Vec<serde_json::Value>>();
+            println!("{}", serde_json::json!({ "violations": violations }));
+        }
+        CheckFormat::Sarif => println!("{}", sarif::report(&violations)),
+        CheckFormat::Github => {
+            for annotation in github::annotations(&violations) {
+                println!("{}", annotation);
+            }
+        }
+    }
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Scans the programs of the container image pulled as `reference` or
+/// read from the tarball `from_tar`, or all of its files.
+fn scan_image(
+    reference: Option<&str>,
+    from_tar: Option<&str>,
+    all: bool,
+    scan_args: &ScanArgs,
+) -> Result<(), Box<dyn Error>> {
+    let mut workspace = inputs::Workspace::default();
+    let (name, image) = match (reference, from_tar) {
+        (_, Some(from_tar)) => (
+            from_tar,
+            image::from_tar(Path::new(from_tar), &mut workspace)?,
+        ),
+        (Some(reference), None) => (reference, registry::pull(reference, &mut workspace)?),
+        (None, None) => return Err("no image given".into()),
+    };
+    if all {
+        let inputs = inputs::archive_members(name, &image.root);
+        return scan(scan_args, &image.root, inputs, &workspace, &[]);
+    }
+    if image.entrypoint.is_empty() {
+        return Err("the image has no ENTRYPOINT or CMD, use --all".into());
+    }
+    let required = image
+        .entrypoint
+        .iter()
+        .map(|program| program.display().to_string())
+        .collect::<Vec<String>>();
+    let inputs = required
+        .iter()
+        .map(|program| inputs::Input {
+            path: program.clone(),
+            relative: Some(
+                workspace
+                    .display_path(Path::new(program))
+                    .display()
+                    .to_string(),
+            ),
+            archive: Some(name.to_string()),
+        })
+        .collect();
+    scan(scan_args, &image.root, inputs, &workspace, &required)
+}
+
+/// Scans the flatpak build in `dir` against its runtime.
+fn scan_flatpak(
+    dir: &Path,
+    runtime: Option<&str>,
+    runtime_dir: Option<&Path>,
+    scan_args: &ScanArgs,
+) -> Result<(), Box<dyn Error>> {
+    let mut workspace = inputs::Workspace::default();
+    let sandbox = flatpak::sandbox(dir, runtime, runtime_dir, &mut workspace)?;
+    // every import has to be satisfied by the application or the runtime
+    let mut scan_args = scan_args.clone();
+    scan_args.check_resolution = true;
+    let mut inputs = inputs::expand(&sandbox.app_files.display().to_string(), &mut workspace);
+    for input in &mut inputs {
+        input.archive = Some(sandbox.app_id.clone());
+    }
+    scan(&scan_args, &sandbox.root, inputs, &workspace, &[])
+}
+
+/// Scans the conda environment in `prefix`, reporting its files by the
+/// package they belong to.
+fn scan_conda_env(prefix: &Path, scan_args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let prefix = std::fs::canonicalize(prefix)?;
+    let owners = conda::file_owners(&prefix)?;
+    let mut workspace = inputs::Workspace::default();
+    // the libraries of the environment come before the system ones, as their
+    // RPATHs put them
+    workspace.add_package_library_paths(&prefix);
+    let mut scan_args = scan_args.clone();
+    if scan_args.max_glibc.is_none() {
+        scan_args.max_glibc = Some(conda::CONDA_FORGE_GLIBC.to_string());
+    }
+    let mut inputs = inputs::expand(&prefix.display().to_string(), &mut workspace);
+    for input in &mut inputs {
+        input.archive = owners.get(Path::new(&input.path)).cloned();
+    }
+    scan(&scan_args, Path::new("/"), inputs, &workspace, &[])
+}
+
+/// Scans the objects the running process `pid` has loaded.
+fn scan_process(pid: u32, scan_args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let workspace = inputs::Workspace::default();
+    let inputs = process::loaded_objects(pid)?
+        .into_iter()
+        .map(|path| {
+            let path = path.display().to_string();
+            inputs::Input {
+                path: path.clone(),
+                relative: Some(path),
+                archive: None,
+            }
+        })
+        .collect::<Vec<inputs::Input>>();
+    scan(scan_args, Path::new("/"), inputs, &workspace, &[])
+}
+
+/// Scans every file of the root filesystem in `dir` against its own
+/// libraries.
+fn scan_rootfs(dir: &Path, scan_args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let root = std::fs::canonicalize(dir)?;
+    let mut workspace = inputs::Workspace::default();
+    workspace.add_package_library_paths(&root);
+    let mut scan_args = scan_args.clone();
+    scan_args.glibc_summary = true;
+    let inputs = inputs::expand(&root.display().to_string(), &mut workspace);
+    scan(&scan_args, &root, inputs, &workspace, &[])
+}
+
+/// Scans the snap `snap` against its base, or the base given.
+fn scan_snap(snap: &Path, base: Option<&Path>, scan_args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let mut workspace = inputs::Workspace::default();
+    let snap = snap::open(snap, base, &mut workspace)?;
+    // the base is the root filesystem of the snap, every import has to be
+    // satisfied by the snap or the base, with the base's glibc
+    let mut scan_args = scan_args.clone();
+    scan_args.check_resolution = true;
+    if scan_args.max_glibc.is_none() {
+        scan_args.max_glibc = snap.base_glibc().map(str::to_string);
+    }
+    let mut inputs = inputs::expand(&snap.files.display().to_string(), &mut workspace);
+    for input in &mut inputs {
+        input.archive = Some(snap.name.clone());
+    }
+    scan(&scan_args, &snap.base_files, inputs, &workspace, &[])
+}
+
+/// Prints how what `paths` require changed since the `baseline`, or what
+/// the new build requires since the old one of `builds`.
+fn scan_diff(
+    baseline: Option<&Path>,
+    builds: Option<(&String, &String)>,
+    paths: &[String],
+    root: &Path,
+    scan_args: &ScanArgs,
+) -> Result<(), Box<dyn Error>> {
+    let mut scan_args = scan_args.clone();
+    let (stage, paths) = match (baseline, builds) {
+        (Some(baseline), _) => {
+            let baseline = diff::read_baseline(baseline)?;
+            (diff::Stage::Baseline(baseline), paths.to_vec())
+        }
+        (None, Some((old, new))) => {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            scan_args.diff = Some(diff::Stage::Old(sender));
+            let mut workspace = inputs::Workspace::default();
+            let inputs = inputs::expand(old, &mut workspace);
+            scan(
+                &scan_args,
+                root,
+                inputs,
+                &workspace,
+                std::slice::from_ref(old),
+            )?;
+            (diff::Stage::New(receiver.recv()?), vec![new.clone()])
+        }
+        (None, None) => return Err("nothing to compare".into()),
+    };
+    scan_args.diff = Some(stage);
+    let mut workspace = inputs::Workspace::default();
+    let inputs = paths
+        .iter()
+        .flat_map(|path| inputs::expand(path, &mut workspace))
+        .collect();
+    scan(&scan_args, root, inputs, &workspace, &paths)
+}
+
+fn find_required_glibc_version<'a, 'b>(
+    referenced_by: &str,
+    tab: &ParsingTable<'a, AnyEndian, Symbol>,
+    str: &StringTable<'b>,
+    from_file: &Path,
+    options: &ScanOptions,
+    findings: &mut Findings,
+    errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
+) {
+    for sym in tab.iter() {
+        if options.ifuncs && sym.st_symtype() == STT_GNU_IFUNC {
+            if let Ok(name) = str.get(sym.st_name as usize) {
+                let name = split_versioned_name(name).map_or(name, |(name, _)| name);
+                insert_ifunc_symbol(findings, name, from_file);
+            }
+        }
+        if !sym.is_undefined() && !options.include_defined {
+            continue;
+        }
+        if let Ok(name) = str.get(sym.st_name as usize) {
+            // .symtab has no versym entries, so the version can only be
+            // recovered from the `name@VERSION` / `name@@VERSION` form the
+            // linker writes out
+            if let Some((function_name, version)) = split_versioned_name(name) {
+                if let Some((namespace, wants)) = split_version_namespace(version) {
+                    let weak = sym.is_undefined() && sym.st_bind() == STB_WEAK;
+                    if !(weak && options.ignore_weak) {
+                        insert_required_version(
+                            findings,
+                            namespace,
+                            wants,
+                            function_name,
+                            from_file,
+                            weak,
+                        );
+                    }
+                }
+            }
+        } else {
+            errored
+                .entry(from_file.to_path_buf())
+                .or_insert(("cannot_parse".to_string(), HashSet::new()))
+                .1
+                .insert(referenced_by.to_string());
+        }
+    }
+}
+
+fn find_required_glibc_version_by_versym(
+    referenced_by: &str,
+    file: &ElfBytes<AnyEndian>,
+    from_file: &Path,
+    options: &ScanOptions,
+    findings: &mut Findings,
+    errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
+) {
+    let (dynsym, dynstr) = match file.dynamic_symbol_table() {
+        Ok(Some(tables)) => tables,
+        _ => return,
+    };
+    // objects without a .gnu.version section only have unversioned symbols
+    let versions = file.symbol_version_table().ok().flatten();
+
+    for (index, sym) in dynsym.iter().enumerate() {
+        if options.ifuncs && sym.st_symtype() == STT_GNU_IFUNC {
+            if let Ok(name) = dynstr.get(sym.st_name as usize) {
+                insert_ifunc_symbol(findings, name, from_file);
+            }
+        }
+        if !sym.is_undefined() && !options.include_defined {
+            continue;
+        }
+        let name = match dynstr.get(sym.st_name as usize) {
+            Ok(name) => name,
+            Err(_) => {
+                errored
+                    .entry(from_file.to_path_buf())
+                    .or_insert(("cannot_parse".to_string(), HashSet::new()))
+                    .1
+                    .insert(referenced_by.to_string());
+                continue;
+            }
+        };
+        if name.is_empty() {
+            continue;
+        }
+
+        // undefined symbols reference a .gnu.version_r (verneed) entry,
+        // defined ones a .gnu.version_d (verdef) entry
+        let Some(versions) = versions.as_ref() else {
+            if sym.is_undefined() {
+                insert_unversioned_symbol(findings, name, from_file);
+            }
+            continue;
+        };
+        let version = if sym.is_undefined() {
+            match versions.get_requirement(index) {
+                Ok(Some(req)) => Some(req.name),
+                _ => {
+                    insert_unversioned_symbol(findings, name, from_file);
+                    None
+                }
+            }
+        } else {
+            match versions.get_definition(index) {
+                Ok(Some(mut def)) if def.flags & VER_FLG_BASE == 0 => {
+                    def.names.next().and_then(|name| name.ok())
+                }
+                _ => None,
+            }
+        };
+
+        if let Some((namespace, wants)) = version.and_then(split_version_namespace) {
+            let weak = sym.is_undefined() && sym.st_bind() == STB_WEAK;
+            if !(weak && options.ignore_weak) {
+                insert_required_version(findings, namespace, wants, name, from_file, weak);
+            }
+        }
+    }
+}
+
+/// Splits a `name@VERSION` (non-default version) or `name@@VERSION`
+/// (default version) symbol name into its name and version parts.
+fn split_versioned_name(name: &str) -> Option<(&str, &str)> {
+    let (function_name, version) = name.rsplit_once('@')?;
+    let function_name = function_name.strip_suffix('@').unwrap_or(function_name);
+    if function_name.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((function_name, version))
+}
+
+fn insert_unversioned_symbol(findings: &mut Findings, name: &str, from_file: &Path) {
+    findings
+        .unversioned
+        .entry(name.to_string())
+        .or_default()
+        .insert(from_file.to_path_buf());
+}
+
+fn insert_ifunc_symbol(findings: &mut Findings, name: &str, from_file: &Path) {
+    findings
+        .ifuncs
+        .entry(name.to_string())
+        .or_default()
+        .insert(from_file.to_path_buf());
+}
+
+/// Splits a version name such as `GLIBCXX_3.4.29` or `OPENSSL_3.0.0` into
+/// its namespace and version.
+///
+/// The version starts after the first underscore that is followed by a
+/// digit, so that `LIBXML2_2.9` is split as `LIBXML2` and `2.9`. Names like
+/// `GLIBC_PRIVATE` without a numeric part are split at the first underscore.
+fn split_version_namespace(version: &str) -> Option<(&str, &str)> {
+    let numeric = version
+        .match_indices('_')
+        .map(|(index, _)| index)
+        .find(|index| {
+            version[index + 1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit())
+        });
+    let index = numeric.or_else(|| version.find('_'))?;
+    let (namespace, wants) = (&version[..index], &version[index + 1..]);
+    if namespace.is_empty() || wants.is_empty() {
+        return None;
+    }
+    Some((namespace, wants))
+}
+
+fn insert_required_version(
+    findings: &mut Findings,
+    namespace: &str,
+    wants: &str,
+    function_name: &str,
+    from_file: &Path,
+    weak: bool,
+) {
+    let inserted = findings
+        .wants
+        .entry(namespace.to_string())
+        .or_default()
+        .entry(wants.to_string())
+        .or_default()
+        .entry(function_name.to_string())
+        .or_default()
+        .insert(from_file.to_path_buf());
+    if inserted {
+        if let Some(pending) = &mut findings.pending {
+            pending.push((
+                namespace.to_string(),
+                wants.to_string(),
+                function_name.to_string(),
+                from_file.to_path_buf(),
+            ));
+        }
+    }
+    let key = (
+        format!("{}_{}", namespace, wants),
+        function_name.to_string(),
+        from_file.to_path_buf(),
+    );
+    if weak && inserted {
+        findings.weak.insert(key);
+    } else if !weak {
+        // a strong reference from the same file wins over a weak one
+        findings.weak.remove(&key);
+    }
+}
+
+/// Returns the name to show for a symbol, demangling C++ and Rust names if
+/// requested. A `@VERSION` suffix is kept as is.
+fn display_symbol(name: &str, demangle: bool) -> String {
+    if !demangle {
+        return name.to_string();
+    }
+    if let Some((symbol, version)) = name.split_once('@') {
+        return format!("{}@{}", display_symbol(symbol, demangle), version);
+    }
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return format!("{:#}", demangled);
+    }
+    if name.starts_with("_Z") {
+        if let Ok(symbol) = cpp_demangle::Symbol::new(name.as_bytes()) {
+            if let Ok(demangled) = symbol.demangle() {
+                return demangled;
+            }
+        }
+    }
+    name.to_string()
+}
+
+/// Prints the highest required versions of a namespace in text mode, each
+/// line starting with `prefix`, and returns their JSON value, shaped by the
+/// detail level. Versions outside of `GLIBC` are printed with their
+/// namespace, e.g. `GLIBCXX_3.4.29`.
+fn versions_report(
+    namespace: &str,
+    findings: &Findings,
+    library_exports: &HashMap<PathBuf, symbols::Exports>,
+    args: &ScanArgs,
+    prefix: &str,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let empty = HashMap::new();
+    let wants = findings.wants.get(namespace).unwrap_or(&empty);
+    // the versions above the --max-glibc gate stand out
+    let max_glibc = args.max_glibc.as_deref().map(distro::version_key);
+    let label = |version: &str| {
+        if namespace != "GLIBC" {
+            return format!("{}{}_{}", prefix, namespace, version);
+        }
+        match &max_glibc {
+            Some(max) if distro::version_key(version) > *max => {
+                format!("{}{}", prefix, color::red(version))
+            }
+            _ => format!("{}{}", prefix, version),
+        }
+    };
+    let versions = findings.highest_versions(namespace, args.versions);
+
+    if args.detail_level.is_version() {
+        if args.stdout_format.is_text() {
+            for version in &versions {
+                println!("{}", label(version));
+            }
+        }
+        serde_json::to_value(&versions)
+    } else if args.detail_level.is_function() {
+        let mut user_wants: HashMap<&String, Vec<String>> = HashMap::new();
+        for version in versions {
+            let functions = wants[version].keys();
+            if args.stdout_format.is_text() {
+                for function in functions.clone() {
+                    let name = display_symbol(function, args.demangle);
+                    if findings.is_weak(namespace, version, function) {
+                        println!("{} => {} (weak)", label(version), name);
+                    } else {
+                        println!("{} => {}", label(version), name);
+                    }
+                }
+            }
+            // C++ constructors and destructors of several kinds demangle to
+            // the same name, so they are listed rather than deduplicated
+            let mut names = functions
+                .map(|function| display_symbol(function, args.demangle))
+                .collect::<Vec<String>>();
+            names.sort();
+            user_wants.insert(version, names);
+        }
+        serde_json::to_value(&user_wants)
+    } else {
+        let mut user_wants: HashMap<&String, HashMap<String, Requirement>> = HashMap::new();
+        for version in versions {
+            let mut functions = HashMap::new();
+            for (function, files) in &wants[version] {
+                let key = (function.to_string(), format!("{}_{}", namespace, version));
+                let name = display_symbol(function, args.demangle);
+                let requirement = Requirement {
+                    weak: findings.is_weak(namespace, version, function),
+                    files: files.clone(),
+                    provided_by: library_exports
+                        .iter()
+                        .filter(|(_, exports)| exports.versioned.contains(&key))
+                        .map(|(path, _)| path.clone())
+                        .collect(),
+                    demangled: args.demangle.then(|| name.clone()),
+                };
+                if args.stdout_format.is_text() {
+                    let provided_by = requirement
+                        .provided_by
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    for file in &requirement.files {
+                        let weak = if findings.is_weak_in(namespace, version, function, file) {
+                            " (weak)"
+                        } else {
+                            ""
+                        };
+                        if provided_by.is_empty() {
+                            println!(
+                                "{} => {}{} => {}",
+                                label(version),
+                                name,
+                                weak,
+                                file.display()
+                            );
+                        } else {
+                            println!(
+                                "{} => {}{} => {} => {}",
+                                label(version),
+                                name,
+                                weak,
+                                file.display(),
+                                provided_by
+                            );
+                        }
+                    }
+                }
+                functions.insert(function.clone(), requirement);
+            }
+            user_wants.insert(version, functions);
+        }
+        serde_json::to_value(&user_wants)
+    }
+}
+
+/// Prints `{label} => key => GLIBC_2.34, GLIBCXX_3.4.29` with the highest
+/// version of every namespace the files in each closure require.
+fn closures_section(
+    label: &str,
+    closures: &BTreeMap<String, HashSet<PathBuf>>,
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let mut section = serde_json::Map::new();
+    for (key, closure) in closures {
+        let mut versions = serde_json::Map::new();
+        for namespace in findings.wants.keys() {
+            if let Some(version) = findings.highest_version_in(namespace, closure) {
+                versions.insert(namespace.clone(), serde_json::to_value(version)?);
+            }
+        }
+        if args.stdout_format.is_text() {
+            let versions = versions
+                .iter()
+                .map(|(namespace, version)| {
+                    format!("{}_{}", namespace, version.as_str().unwrap_or_default())
+                })
+                .collect::<Vec<String>>();
+            if versions.is_empty() {
+                println!("{} => {}", label, key);
+            } else {
+                println!("{} => {} => {}", label, key, versions.join(", "));
+            }
+        }
+        section.insert(key.clone(), serde_json::Value::Object(versions));
+    }
+    Ok(serde_json::Value::Object(section))
+}
+
+/// Prints symbols a target does not provide in text mode, with the files
+/// importing them whatever the detail level, and returns them as JSON.
+fn offending_section(
+    heading: &str,
+    offending: &[Offending],
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if args.stdout_format.is_text() {
+        for entry in offending {
+            let name = display_symbol(&entry.symbol, args.demangle);
+            for file in &entry.files {
+                let line = format!(
+                    "{} => {} => {} => {}",
+                    heading,
+                    entry.version,
+                    name,
+                    file.display()
+                );
+                println!("{}", color::red(&line));
+            }
+        }
+    }
+    serde_json::to_value(offending)
+}
+
+/// Prints the entries of `wants` found while analyzing the input
+/// `referenced_by` as ndjson, one object per version, per function or per
+/// file depending on the detail level, each printed once.
+fn stream_findings(
+    pending: &mut Vec<(String, String, String, PathBuf)>,
+    referenced_by: &Path,
+    streamed: &mut HashSet<(String, Option<String>, Option<PathBuf>)>,
+    workspace: &inputs::Workspace,
+    args: &ScanArgs,
+) {
+    for (namespace, version, function, file) in pending.drain(..) {
+        if namespace == "GLIBC" && version == "PRIVATE" {
+            continue;
+        }
+        let is_baseline = args.min_version.as_deref().is_some_and(|min_version| {
+            distro::version_key(&version) <= distro::version_key(min_version)
+        });
+        if namespace == "GLIBC" && is_baseline {
+            continue;
+        }
+        let version = format!("{}_{}", namespace, version);
+        let key = match args.detail_level {
+            DetailLevel::Version => (version, None, None),
+            DetailLevel::Function => (version, Some(function), None),
+            DetailLevel::File => (version, Some(function), Some(file)),
+        };
+        if !streamed.insert(key.clone()) {
+            continue;
+        }
+        let (version, function, file) = key;
+        let mut finding = serde_json::Map::new();
+        finding.insert("type".to_string(), "symbol".into());
+        finding.insert("version".to_string(), version.into());
+        if let Some(function) = &function {
+            let function = display_symbol(function, args.demangle);
+            finding.insert("function".to_string(), function.into());
+        }
+        if let Some(file) = &file {
+            let file = workspace.display_path(file).display().to_string();
+            finding.insert("file".to_string(), file.into());
+            let referenced_by = referenced_by.display().to_string();
+            finding.insert("referenced_by".to_string(), referenced_by.into());
+        }
+        println!("{}", serde_json::Value::Object(finding));
+    }
+}
+
+/// Returns the rows of the csv and tsv formats, one per version, per
+/// function at the function detail level, and per file importing it and
+/// input it is in the dependency closure of at the file detail level. The
+/// columns beyond the detail level are left empty.
+fn csv_rows(findings: &Findings, args: &ScanArgs) -> Vec<[String; 4]> {
+    let mut rows = Vec::new();
+    let mut namespaces = findings.wants.iter().collect::<Vec<_>>();
+    namespaces.sort_by_key(|(namespace, _)| *namespace);
+    for (namespace, wants) in namespaces {
+        let mut versions = wants
+            .iter()
+            .filter(|(version, _)| !(namespace == "GLIBC" && *version == "PRIVATE"))
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|(version, _)| distro::version_key(version));
+        for (version, functions) in versions {
+            let version = format!("{}_{}", namespace, version);
+            if args.detail_level.is_version() {
+                rows.push([version, String::new(), String::new(), String::new()]);
+                continue;
+            }
+            let mut functions = functions.iter().collect::<Vec<_>>();
+            functions.sort_by_key(|(function, _)| *function);
+            for (function, files) in functions {
+                let function = display_symbol(function, args.demangle);
+                if !args.detail_level.is_file() {
+                    rows.push([version.clone(), function, String::new(), String::new()]);
+                    continue;
+                }
+                let mut files = files.iter().collect::<Vec<&PathBuf>>();
+                files.sort();
+                for file in files {
+                    for (input, closure) in &findings.closures {
+                        if closure.contains(file) {
+                            rows.push([
+                                version.clone(),
+                                function.clone(),
+                                file.display().to_string(),
+                                input.clone(),
+                            ]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Returns the `--envelope` JSON document, described by `schema::json_schema`:
+/// the inputs, every requirement whatever the detail level, the `sections`
+/// of the other checks and the errors.
+fn envelope(
+    findings: &Findings,
+    sections: serde_json::Value,
+    errored: &HashMap<PathBuf, (String, HashSet<String>)>,
+    args: &ScanArgs,
+) -> serde_json::Value {
+    // the inputs loading each other are bundled, not libraries of the system
+    let given = findings
+        .closures
+        .keys()
+        .flat_map(|input| {
+            [
+                PathBuf::from(input),
+                std::fs::canonicalize(input).unwrap_or_default(),
+            ]
+        })
+        .collect::<HashSet<PathBuf>>();
+    let inputs = findings
+        .closures
+        .iter()
+        .map(|(input, closure)| {
+            let mut files = closure
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<String>>();
+            files.sort();
+            let libraries = closure
+                .iter()
+                .filter(|file| !given.contains(*file))
+                .filter_map(|file| match findings.sonames.get(file) {
+                    Some(soname) => Some(soname.clone()),
+                    None => Some(file.file_name()?.to_string_lossy().to_string()),
+                })
+                .collect::<BTreeSet<String>>();
+            serde_json::json!({
+                "path": input,
+                "glibc": findings.highest_version_in("GLIBC", closure),
+                "files": files,
+                "libraries": libraries,
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    let mut requirements = Vec::new();
+    let mut namespaces = findings.wants.iter().collect::<Vec<_>>();
+    namespaces.sort_by_key(|(namespace, _)| *namespace);
+    for (namespace, wants) in namespaces {
+        let mut versions = wants.iter().collect::<Vec<_>>();
+        versions.sort_by_key(|(version, _)| distro::version_key(version));
+        for (version, functions) in versions {
+            let mut functions = functions.iter().collect::<Vec<_>>();
+            functions.sort_by_key(|(function, _)| *function);
+            for (function, files) in functions {
+                let mut files = files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<String>>();
+                files.sort();
+                requirements.push(serde_json::json!({
+                    "namespace": namespace,
+                    "version": version,
+                    "function": display_symbol(function, args.demangle),
+                    "weak": findings.is_weak(namespace, version, function),
+                    "files": files,
+                }));
+            }
+        }
+    }
+    serde_json::json!({
+        "schema_version": schema::SCHEMA_VERSION,
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "inputs": inputs,
+        "findings": {
+            "requirements": requirements,
+            "sections": sections,
+        },
+        "errors": errors_json(errored),
+    })
+}
+
+/// Returns the files that could not be resolved or read as a JSON array of
+/// `{"path", "kind", "referenced_by"}` objects, one per file and input or
+/// library it was looked up for, as printed to stderr.
+fn errors_json(errored: &HashMap<PathBuf, (String, HashSet<String>)>) -> serde_json::Value {
+    let mut errors = errored
+        .iter()
+        .flat_map(|(path, (kind, names))| {
+            names
+                .iter()
+                .map(move |name| (path.display().to_string(), kind, name))
+        })
+        .collect::<Vec<(String, &String, &String)>>();
+    errors.sort();
+    errors
+        .into_iter()
+        .map(|(path, kind, referenced_by)| {
+            serde_json::json!({
+                "path": path,
+                "kind": kind,
+                "referenced_by": referenced_by,
+            })
+        })
+        .collect()
+}
+
+/// Returns the files of the dependency closures of the inputs as components
+/// of a software bill of materials, the inputs first, and the libraries
+/// every input depends on.
+fn sbom_components(findings: &Findings) -> (Vec<sbom::Component>, Vec<(String, Vec<String>)>) {
+    let inputs = findings
+        .closures
+        .keys()
+        .map(PathBuf::from)
+        .collect::<BTreeSet<PathBuf>>();
+    let libraries = findings
+        .closures
+        .values()
+        .flatten()
+        .filter(|file| !inputs.contains(*file))
+        .cloned()
+        .collect::<BTreeSet<PathBuf>>();
+    let components = inputs
+        .iter()
+        .map(|file| (file, true))
+        .chain(libraries.iter().map(|file| (file, false)))
+        .map(|(file, is_input)| sbom::Component {
+            path: file.display().to_string(),
+            soname: findings.sonames.get(file).cloned(),
+            build_id: findings.build_ids.get(file).cloned(),
+            sha256: findings.checksums.get(file).cloned(),
+            glibc: findings
+                .highest_version_in("GLIBC", &HashSet::from([file.clone()]))
+                .map(|glibc| format!("GLIBC_{}", glibc)),
+            is_input,
+        })
+        .collect();
+    let dependencies = findings
+        .closures
+        .iter()
+        .map(|(input, closure)| {
+            let mut closure = closure
+                .iter()
+                .filter(|file| !inputs.contains(*file))
+                .map(|file| file.display().to_string())
+                .collect::<Vec<String>>();
+            closure.sort();
+            (input.clone(), closure)
+        })
+        .collect();
+    (components, dependencies)
+}
+
+/// Returns the dependency graph of the inputs, every file annotated with the
+/// newest glibc version it requires itself (not through its libraries).
+fn dependency_graph(findings: &Findings, args: &ScanArgs) -> graph::Graph {
+    let mut files = findings
+        .closures
+        .values()
+        .flatten()
+        .collect::<BTreeSet<&PathBuf>>();
+    files.extend(
+        findings
+            .needed
+            .iter()
+            .flat_map(|(file, library)| [file, library]),
+    );
+    let inputs = findings
+        .closures
+        .keys()
+        .map(PathBuf::from)
+        .collect::<HashSet<PathBuf>>();
+    let max_glibc = args.max_glibc.as_deref().map(distro::version_key);
+    let nodes = files
+        .into_iter()
+        .map(|file| {
+            let glibc = findings.highest_version_in("GLIBC", &HashSet::from([file.clone()]));
+            let is_offending = glibc
+                .zip(max_glibc.as_ref())
+                .is_some_and(|(glibc, max)| distro::version_key(glibc) > *max);
+            graph::Node {
+                path: file.display().to_string(),
+                glibc: glibc.map(|glibc| format!("GLIBC_{}", glibc)),
+                is_input: inputs.contains(file),
+                is_offending,
+            }
+        })
+        .collect();
+    let edges = findings
+        .needed
+        .iter()
+        .map(|(file, library)| (file.display().to_string(), library.display().to_string()))
+        .collect();
+    graph::Graph { nodes, edges }
+}
+
+/// Returns the versions of every namespace, in order, with the functions
+/// needing each and the files importing those, for the markdown and HTML
+/// reports.
+fn version_tree(findings: &Findings, args: &ScanArgs) -> Vec<(String, html::VersionFunctions)> {
+    let mut tree = Vec::new();
+    let mut namespaces = findings.wants.iter().collect::<Vec<_>>();
+    namespaces.sort_by_key(|(namespace, _)| *namespace);
+    for (namespace, wants) in namespaces {
+        let mut versions = wants
+            .iter()
+            .filter(|(version, _)| !(namespace == "GLIBC" && *version == "PRIVATE"))
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|(version, _)| distro::version_key(version));
+        for (version, functions) in versions {
+            let mut functions = functions
+                .iter()
+                .map(|(function, files)| {
+                    let mut files = files
+                        .iter()
+                        .map(|file| file.display().to_string())
+                        .collect::<Vec<String>>();
+                    files.sort();
+                    (display_symbol(function, args.demangle), files)
+                })
+                .collect::<Vec<(String, Vec<String>)>>();
+            functions.sort();
+            tree.push((format!("{}_{}", namespace, version), functions));
+        }
+    }
+    tree
+}
+
+/// Returns the header and rows of the markdown table: the versions and the
+/// number of functions needing each, the versions and their functions at the
+/// function detail level, and the files importing each function, one per
+/// line of the cell, at the file detail level.
+fn markdown_rows(findings: &Findings, args: &ScanArgs) -> (Vec<&'static str>, Vec<Vec<String>>) {
+    let header = if args.detail_level.is_version() {
+        vec!["Version", "Functions"]
+    } else if args.detail_level.is_file() {
+        vec!["Version", "Function", "Files"]
+    } else {
+        vec!["Version", "Function"]
+    };
+    let mut rows = Vec::new();
+    for (version, functions) in version_tree(findings, args) {
+        if args.detail_level.is_version() {
+            rows.push(vec![version, functions.len().to_string()]);
+            continue;
+        }
+        for (function, files) in functions {
+            if args.detail_level.is_file() {
+                rows.push(vec![version.clone(), function, files.join("\n")]);
+            } else {
+                rows.push(vec![version.clone(), function]);
+            }
+        }
+    }
+    (header, rows)
+}
+
+/// Returns the fields of the `--format` template for every requirement: a
+/// versioned function, a file importing it and an input that file is in the
+/// dependency closure of, in the order of the csv format.
+fn template_rows<'a>(
+    findings: &Findings,
+    library_exports: &HashMap<PathBuf, symbols::Exports>,
+    args: &ScanArgs,
+) -> Vec<HashMap<&'a str, String>> {
+    let mut rows = Vec::new();
+    let mut namespaces = findings.wants.iter().collect::<Vec<_>>();
+    namespaces.sort_by_key(|(namespace, _)| *namespace);
+    for (namespace, wants) in namespaces {
+        let mut versions = wants.iter().collect::<Vec<_>>();
+        versions.sort_by_key(|(version, _)| distro::version_key(version));
+        for (version, functions) in versions {
+            let mut functions = functions.iter().collect::<Vec<_>>();
+            functions.sort_by_key(|(function, _)| *function);
+            for (function, files) in functions {
+                let key = (function.to_string(), format!("{}_{}", namespace, version));
+                let mut provided_by = library_exports
+                    .iter()
+                    .filter(|(_, exports)| exports.versioned.contains(&key))
+                    .map(|(path, _)| path.display().to_string())
+                    .collect::<Vec<String>>();
+                provided_by.sort();
+                let mut files = files.iter().collect::<Vec<&PathBuf>>();
+                files.sort();
+                for file in files {
+                    for (input, closure) in &findings.closures {
+                        if !closure.contains(file) {
+                            continue;
+                        }
+                        let weak = findings.is_weak_in(namespace, version, function, file);
+                        rows.push(HashMap::from([
+                            ("namespace", namespace.clone()),
+                            ("version", key.1.clone()),
+                            ("function", display_symbol(function, args.demangle)),
+                            ("file", file.display().to_string()),
+                            ("referenced_by", input.clone()),
+                            ("provided_by", provided_by.join(",")),
+                            ("weak", weak.to_string()),
+                        ]));
+                    }
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// The width of the longest bar of the `--stats` chart.
+const STATS_BAR_WIDTH: usize = 40;
+
+/// Prints how many distinct symbols require each glibc version, oldest first,
+/// with a bar chart on a terminal, and returns the JSON value.
+fn stats_section(findings: &Findings, args: &ScanArgs) -> serde_json::Value {
+    let mut counts = findings
+        .wants
+        .get("GLIBC")
+        .into_iter()
+        .flatten()
+        .filter(|(version, _)| *version != "PRIVATE")
+        .map(|(version, functions)| (version, functions.len()))
+        .collect::<Vec<(&String, usize)>>();
+    counts.sort_by_key(|(version, _)| distro::version_key(version));
+    if args.stdout_format.is_text() {
+        let most = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+        let bars = std::io::IsTerminal::is_terminal(&std::io::stdout());
+        let lines = counts
+            .iter()
+            .map(|(version, count)| format!("stats => GLIBC_{} => {}", version, count))
+            .collect::<Vec<String>>();
+        let longest = lines.iter().map(String::len).max().unwrap_or(0);
+        for (line, (_, count)) in lines.iter().zip(&counts) {
+            if bars {
+                // a bar of at least one character for every version
+                let width = (count * STATS_BAR_WIDTH).div_ceil(most);
+                println!("{:longest$}  {}", line, "#".repeat(width));
+            } else {
+                println!("{}", line);
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(version, count)| (version.clone(), serde_json::Value::from(count)))
+        .collect::<serde_json::Map<String, serde_json::Value>>()
+        .into()
+}
+
+/// Prints the files importing each of `names` with the version they import
+/// it at, `unversioned` for the imports without one, and returns the JSON
+/// value, symbol => the files and their versions.
+fn find_symbol_section(
+    names: &[String],
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut section = serde_json::Map::new();
+    for name in names {
+        let mut importers: BTreeSet<(String, Option<String>)> = BTreeSet::new();
+        for (namespace, versions) in &findings.wants {
+            for (version, functions) in versions {
+                let matching = functions.iter().filter(|(function, _)| {
+                    *function == name || display_symbol(function, args.demangle) == *name
+                });
+                for (_, files) in matching {
+                    importers.extend(files.iter().map(|file| {
+                        (
+                            file.display().to_string(),
+                            Some(format!("{}_{}", namespace, version)),
+                        )
+                    }));
+                }
+            }
+        }
+        for file in findings.unversioned.get(name).into_iter().flatten() {
+            importers.insert((file.display().to_string(), None));
+        }
+        if args.stdout_format.is_text() {
+            for (file, version) in &importers {
+                println!(
+                    "symbol => {} => {} => {}",
+                    name,
+                    version.as_deref().unwrap_or("unversioned"),
+                    file
+                );
+            }
+        }
+        let importers = importers
+            .into_iter()
+            .map(|(file, version)| serde_json::json!({ "file": file, "version": version }))
+            .collect::<Vec<serde_json::Value>>();
+        section.insert(name.clone(), serde_json::to_value(importers)?);
+    }
+    Ok(serde_json::Value::Object(section))
+}
+
+/// Returns what the files found in `paths` (directories are searched, as
+/// for scan) do against `policy`: the symbol versions they import, the
+/// libraries they load from outside of them, resolved with `analyzer`, and
+/// their RPATHs and RUNPATHs, but what its exceptions cover.
+fn policy_file_violations(
+    policy: &policy_file::PolicyFile,
+    paths: &[String],
+    analyzer: DependencyAnalyzer,
+) -> Vec<violations::Violation> {
+    let mut workspace = inputs::Workspace::default();
+    let inputs = paths
+        .iter()
+        .flat_map(|path| inputs::expand(path, &mut workspace))
+        .collect::<Vec<inputs::Input>>();
+    // the libraries among the files are theirs, not loaded from outside
+    let bundled = inputs
+        .iter()
+        .filter_map(|given| std::fs::canonicalize(&given.path).ok())
+        .collect::<HashSet<PathBuf>>();
+    let mut violations = Vec::new();
+    for given in &inputs {
+        let path = Path::new(&given.path);
+        let file = workspace.display_path(path);
+        let name = file.display().to_string();
+        let mut violation = |rule, message, subject: policy_file::Subject| {
+            if !policy.is_excepted(&subject) {
+                violations.push(violations::Violation {
+                    rule,
+                    message,
+                    file: Some(file.clone()),
+                });
+            }
+        };
+        let mut imports = symbols::read_imports(path)
+            .into_iter()
+            .filter(|import| !import.weak)
+            .filter_map(|import| Some((import.version?, import.name)))
+            .collect::<Vec<(String, String)>>();
+        imports.sort();
+        imports.dedup();
+        for (version, symbol) in &imports {
+            if let Some(max) = policy.exceeded_max(version) {
+                let subject = policy_file::Subject {
+                    file: &name,
+                    symbol: Some(symbol),
+                    version: Some(version),
+                    ..Default::default()
+                };
+                let message = format!("{} requires {}, newer than {}", symbol, version, max);
+                violation("policy-file-version", message, subject);
+            }
+        }
+        for rpath in symbols::read_search_paths(path) {
+            if !policy.allows_rpath(&rpath) {
+                let subject = policy_file::Subject {
+                    file: &name,
+                    rpath: Some(&rpath),
+                    ..Default::default()
+                };
+                let message = format!("searches {} for libraries", rpath);
+                violation("policy-file-rpath", message, subject);
+            }
+        }
+        let Ok(deps) = analyzer.clone().analyze(path) else {
+            violation(
+                "unreadable-input",
+                "cannot be analyzed".to_string(),
+                policy_file::Subject {
+                    file: &name,
+                    ..Default::default()
+                },
+            );
+            continue;
+        };
+        let mut libraries = deps.libraries.iter().collect::<Vec<_>>();
+        libraries.sort_by_key(|(soname, _)| *soname);
+        for (soname, library) in libraries {
+            // the interpreter is named by its path
+            let soname = soname.rsplit('/').next().unwrap_or(soname);
+            let realpath = library.realpath.as_ref();
+            if realpath.is_some_and(|realpath| bundled.contains(realpath))
+                || policy.allows_library(soname)
+            {
+                continue;
+            }
+            let subject = policy_file::Subject {
+                file: &name,
+                soname: Some(soname),
+                ..Default::default()
+            };
+            let message = match realpath {
+                Some(realpath) => format!("loads {} from {}", soname, realpath.display()),
+                None => format!("loads {}, which cannot be found", soname),
+            };
+            violation("policy-file-library", message, subject);
+        }
+    }
+    violations
+}
+
+/// How a symbol gets required: the input, the libraries loaded one by the
+/// other through `DT_NEEDED`, the last one importing the symbol.
+#[derive(Debug, Serialize)]
+struct Chain {
+    files: Vec<PathBuf>,
+    symbol: String,
+    /// e.g. `GLIBC_2.33`
+    version: String,
+}
+
+/// Returns the chains from the inputs to the files importing `query`, a
+/// version (`GLIBC_2.33`) or a symbol (`memfd_create`), the shortest one for
+/// every input and importing file.
+fn why_chains(query: &str, findings: &Findings, args: &ScanArgs) -> Vec<Chain> {
+    let version_query = split_version_namespace(query)
+        .filter(|(namespace, _)| findings.wants.contains_key(*namespace));
+    let mut loads: HashMap<&PathBuf, Vec<&PathBuf>> = HashMap::new();
+    for (file, library) in &findings.needed {
+        loads.entry(file).or_default().push(library);
+    }
+    let mut chains = Vec::new();
+    for (namespace, versions) in &findings.wants {
+        for (version, functions) in versions {
+            for (function, files) in functions {
+                let symbol = display_symbol(function, args.demangle);
+                let matches = match version_query {
+                    Some(wanted) => wanted == (namespace.as_str(), version.as_str()),
+                    None => query == function || query == symbol,
+                };
+                if !matches {
+                    continue;
+                }
+                for file in files {
+                    for (input, closure) in &findings.closures {
+                        if !is_in_closure(closure, file) {
+                            continue;
+                        }
+                        let input = PathBuf::from(input);
+                        let files = needed_path(&loads, &input, file)
+                            .unwrap_or_else(|| vec![input.clone(), file.clone()]);
+                        chains.push(Chain {
+                            files,
+                            symbol: symbol.clone(),
+                            version: format!("{}_{}", namespace, version),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    chains.sort_by(|a, b| (&a.files, &a.symbol).cmp(&(&b.files, &b.symbol)));
+    chains
+}
+
+/// Returns the shortest path from `from` to `to` following `loads`, the
+/// libraries every file loads, both ends included.
+fn needed_path(
+    loads: &HashMap<&PathBuf, Vec<&PathBuf>>,
+    from: &PathBuf,
+    to: &PathBuf,
+) -> Option<Vec<PathBuf>> {
+    let mut parents: HashMap<&PathBuf, &PathBuf> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([from]);
+    while let Some(file) = queue.pop_front() {
+        if file == to {
+            let mut path = vec![file.clone()];
+            let mut file = file;
+            while let Some(parent) = parents.get(file) {
+                path.push((*parent).clone());
+                file = parent;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for library in loads.get(file).into_iter().flatten() {
+            if *library != from && !parents.contains_key(library) {
+                parents.insert(library, file);
+                queue.push_back(library);
+            }
+        }
+    }
+    None
+}
+
+/// A file and the libraries it loads through `DT_NEEDED`, as printed by
+/// `tree`.
+#[derive(Debug, Serialize)]
+struct TreeNode {
+    /// The `DT_SONAME` of the file, or the `DT_NEEDED` entry when it was not
+    /// found.
+    soname: Option<String>,
+    /// `None` when the library was not found.
+    path: Option<PathBuf>,
+    /// The newest glibc version the file imports itself.
+    glibc: Option<String>,
+    needed: Vec<TreeNode>,
+}
+
+/// Returns the tree of the libraries `input` loads. Like lddtree, a library
+/// is only followed the first time it appears.
+fn dependency_tree(findings: &Findings, input: &PathBuf) -> TreeNode {
+    let mut loads: HashMap<&PathBuf, Vec<&PathBuf>> = HashMap::new();
+    for (file, library) in &findings.needed {
+        loads.entry(file).or_default().push(library);
+    }
+    let mut not_found: HashMap<&PathBuf, Vec<&String>> = HashMap::new();
+    for (file, name) in &findings.not_found {
+        not_found.entry(file).or_default().push(name);
+    }
+    fn node(
+        findings: &Findings,
+        loads: &HashMap<&PathBuf, Vec<&PathBuf>>,
+        not_found: &HashMap<&PathBuf, Vec<&String>>,
+        file: &PathBuf,
+        shown: &mut HashSet<PathBuf>,
+    ) -> TreeNode {
+        let mut needed = Vec::new();
+        if shown.insert(file.clone()) {
+            for library in loads.get(file).into_iter().flatten() {
+                needed.push(node(findings, loads, not_found, library, shown));
+            }
+            for name in not_found.get(file).into_iter().flatten() {
+                needed.push(TreeNode {
+                    soname: Some(name.to_string()),
+                    path: None,
+                    glibc: None,
+                    needed: Vec::new(),
+                });
+            }
+        }
+        TreeNode {
+            soname: findings.sonames.get(file).cloned(),
+            path: Some(file.clone()),
+            glibc: findings
+                .highest_version_in("GLIBC", &HashSet::from([file.clone()]))
+                .cloned(),
+            needed,
+        }
+    }
+    node(findings, &loads, &not_found, input, &mut HashSet::new())
+}
+
+/// Prints `tree` indented by `depth` levels, a line per file: its soname
+/// (or file name), path and the newest glibc version it imports.
+fn print_tree(tree: &TreeNode, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let name = tree.soname.clone().or_else(|| {
+        let name = tree.path.as_ref()?.file_name()?;
+        Some(name.to_string_lossy().to_string())
+    });
+    let name = name.unwrap_or_default();
+    match (&tree.path, &tree.glibc) {
+        (Some(path), Some(glibc)) => {
+            println!(
+                "{}{} => {} => GLIBC_{}",
+                indent,
+                name,
+                path.display(),
+                glibc
+            )
+        }
+        (Some(path), None) => println!("{}{} => {}", indent, name, path.display()),
+        (None, _) => println!(
+            "{}{}",
+            indent,
+            color::red(&format!("{} => not found", name))
+        ),
+    }
+    for library in &tree.needed {
+        print_tree(library, depth + 1);
+    }
+}
+
+/// Prints, for the highest version required from every namespace, the
+/// files importing symbols at that version and those symbols: the files to
+/// rebuild or replace to lower it, and returns the JSON value.
+fn why_max_section(
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut section = serde_json::Map::new();
+    for namespace in findings.wants.keys().collect::<BTreeSet<&String>>() {
+        let versions = &findings.wants[namespace];
+        let Some((version, functions)) = versions
+            .iter()
+            .filter(|(version, _)| !(namespace == "GLIBC" && *version == "PRIVATE"))
+            .max_by_key(|(version, _)| distro::version_key(version))
+        else {
+            continue;
+        };
+        let mut by_file: BTreeMap<String, BTreeSet<&String>> = BTreeMap::new();
+        for (function, importers) in functions {
+            for file in importers {
+                by_file
+                    .entry(file.display().to_string())
+                    .or_default()
+                    .insert(function);
+            }
+        }
+        let files = by_file
+            .into_iter()
+            .map(|(file, functions)| {
+                let functions = functions
+                    .into_iter()
+                    .map(|function| display_symbol(function, args.demangle))
+                    .collect::<Vec<String>>();
+                (file, functions)
+            })
+            .collect::<BTreeMap<String, Vec<String>>>();
+        if args.stdout_format.is_text() {
+            for (file, functions) in &files {
+                println!(
+                    "why max => {}_{} => {} => {}",
+                    namespace,
+                    version,
+                    file,
+                    functions.join(", ")
+                );
+            }
+        }
+        section.insert(
+            namespace.clone(),
+            serde_json::json!({ "version": version, "files": serde_json::to_value(&files)? }),
+        );
+    }
+    Ok(serde_json::Value::Object(section))
+}
+
+/// Prints how much was analyzed (the inputs, the files of their dependency
+/// closures and the libraries among them), the highest version required from
+/// every namespace, the errors by kind and how long it took, and returns the
+/// JSON value.
+fn summary_section(
+    findings: &Findings,
+    errored: &HashMap<PathBuf, (String, HashSet<String>)>,
+    runtime: std::time::Duration,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let inputs = findings
+        .closures
+        .keys()
+        .map(PathBuf::from)
+        .collect::<HashSet<PathBuf>>();
+    let files = findings
+        .closures
+        .values()
+        .flatten()
+        .collect::<HashSet<&PathBuf>>();
+    let libraries = files.iter().filter(|file| !inputs.contains(**file)).count();
+    let mut max_versions = BTreeMap::new();
+    for (namespace, versions) in &findings.wants {
+        let newest = versions
+            .keys()
+            .filter(|version| !(namespace == "GLIBC" && *version == "PRIVATE"))
+            .max_by_key(|version| distro::version_key(version));
+        if let Some(newest) = newest {
+            max_versions.insert(namespace.clone(), newest.clone());
+        }
+    }
+    let mut errors: BTreeMap<&String, usize> = BTreeMap::new();
+    for (kind, _) in errored.values() {
+        *errors.entry(kind).or_default() += 1;
+    }
+    let runtime = runtime.as_secs_f64();
+    if args.stdout_format.is_text() {
+        println!("summary => inputs => {}", inputs.len());
+        println!("summary => files => {}", files.len());
+        println!("summary => libraries => {}", libraries);
+        for (namespace, version) in &max_versions {
+            println!("summary => max => {}_{}", namespace, version);
+        }
+        for (kind, count) in &errors {
+            println!("summary => errors => {} => {}", kind, count);
+        }
+        println!("summary => runtime => {:.3}s", runtime);
+    }
+    Ok(serde_json::json!({
+        "inputs": inputs.len(),
+        "files": files.len(),
+        "libraries": libraries,
+        "max_versions": max_versions,
+        "errors": errors,
+        "runtime_seconds": runtime,
+    }))
+}
+
+/// Prints the newest glibc version each input imports itself (not through
+/// its libraries), the newest of all and the outliers, the inputs needing a
+/// newer glibc than 90% of the inputs do, and returns the JSON value.
+fn glibc_summary_section(
+    inputs: &[inputs::Input],
+    findings: &Findings,
+    workspace: &inputs::Workspace,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut newest: HashMap<&PathBuf, &String> = HashMap::new();
+    for (version, functions) in findings.wants.get("GLIBC").into_iter().flatten() {
+        if version == "PRIVATE" {
+            continue;
+        }
+        for file in functions.values().flatten() {
+            let entry = newest.entry(file).or_insert(version);
+            if distro::version_key(version) > distro::version_key(entry) {
+                *entry = version;
+            }
+        }
+    }
+    let mut files = BTreeMap::new();
+    for given in inputs {
+        let path = workspace.display_path(Path::new(&given.path));
+        if let Some(version) = newest.get(&path) {
+            let name = given
+                .relative
+                .clone()
+                .unwrap_or_else(|| path.display().to_string());
+            files.insert(name, (*version).clone());
+        }
+    }
+    let mut versions = files.values().collect::<Vec<&String>>();
+    versions.sort_by_key(|version| distro::version_key(version));
+    let max = versions.last().map(|version| format!("GLIBC_{}", version));
+    // the version that covers 90% of the inputs
+    let typical = versions
+        .get((versions.len() * 9).div_ceil(10).saturating_sub(1))
+        .map(|version| distro::version_key(version));
+    let outliers = files
+        .iter()
+        .filter(|(_, version)| {
+            typical
+                .as_ref()
+                .is_some_and(|typical| distro::version_key(version) > *typical)
+        })
+        .map(|(file, version)| (file.clone(), format!("GLIBC_{}", version)))
+        .collect::<BTreeMap<String, String>>();
+    let files = files
+        .into_iter()
+        .map(|(file, version)| (file, format!("GLIBC_{}", version)))
+        .collect::<BTreeMap<String, String>>();
+    if args.stdout_format.is_text() {
+        for (file, version) in &files {
+            println!("glibc => {} => {}", file, version);
+        }
+        if let Some(max) = &max {
+            println!("glibc max => {}", max);
+        }
+        for (file, version) in &outliers {
+            println!("glibc outlier => {} => {}", file, version);
+        }
+    }
+    Ok(serde_json::json!({
+        "files": files,
+        "max": max,
+        "outliers": outliers,
+    }))
+}
+
+/// Prints a symbol => files report section in text mode and returns its
+/// JSON value. The files are only part of the `file` detail level.
+fn symbol_section(
+    label: &str,
+    symbols: &HashMap<String, HashSet<PathBuf>>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut names = symbols.keys().collect::<Vec<&String>>();
+    names.sort();
+    if args.stdout_format.is_text() {
+        for name in &names {
+            let display_name = display_symbol(name, args.demangle);
+            if args.detail_level.is_file() {
+                for file in &symbols[*name] {
+                    println!("{} => {} => {}", label, display_name, file.display());
+                }
+            } else {
+                println!("{} => {}", label, display_name);
+            }
+        }
+    }
+    if args.detail_level.is_file() {
+        // keyed by the mangled name, several of which can demangle alike
+        serde_json::to_value(symbols)
+    } else {
+        let names = names
+            .iter()
+            .map(|name| display_symbol(name, args.demangle))
+            .collect::<Vec<String>>();
+        serde_json::to_value(names)
+    }
+}
+
+/// Why a symbol imported from the C library would not resolve against musl,
+/// if it would not.
+fn musl_incompatibility(name: &str, versioned: bool) -> Option<&'static str> {
+    if name.starts_with("__isoc99_") {
+        Some("isoc99")
+    } else if name.starts_with("__") && name.ends_with("_chk") {
+        Some("fortify")
+    } else if name.starts_with("_nss_") || name.starts_with("__nss_") {
+        Some("nss")
+    } else if versioned {
+        Some("versioned")
+    } else {
+        None
+    }
+}
+
+/// Prints the symbols imported from glibc that `classify` gives a reason for
+/// in text mode, and returns them as a report section: symbol => reason,
+/// with the importing files at the `file` detail level. glibc's own
+/// libraries are left out.
+fn flagged_imports_section(
+    label: &str,
+    findings: &Findings,
+    args: &ScanArgs,
+    classify: fn(&str, bool) -> Option<&'static str>,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut symbols: HashMap<&str, (&str, HashSet<&PathBuf>)> = HashMap::new();
+    let versioned = findings
+        .wants
+        .get("GLIBC")
+        .into_iter()
+        .flat_map(|versions| versions.values())
+        .flat_map(|functions| functions.iter())
+        .map(|(name, files)| (name, files, true));
+    let unversioned = findings
+        .unversioned
+        .iter()
+        .map(|(name, files)| (name, files, false));
+    for (name, files, versioned) in versioned.chain(unversioned) {
+        let Some(reason) = classify(name, versioned) else {
+            continue;
+        };
+        let files = files.iter().filter(|file| {
+            !file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_glibc_library)
+        });
+        let entry = symbols
+            .entry(name)
+            .or_insert_with(|| (reason, HashSet::new()));
+        entry.1.extend(files);
+    }
+    symbols.retain(|_, (_, files)| !files.is_empty());
+
+    let mut names = symbols.keys().copied().collect::<Vec<&str>>();
+    names.sort();
+    if args.stdout_format.is_text() {
+        let label = color::yellow(label);
+        for name in &names {
+            let (reason, files) = &symbols[name];
+            let display_name = display_symbol(name, args.demangle);
+            if args.detail_level.is_file() {
+                for file in files {
+                    println!(
+                        "{} => {} => {} => {}",
+                        label,
+                        display_name,
+                        reason,
+                        file.display()
+                    );
+                }
+            } else {
+                println!("{} => {} => {}", label, display_name, reason);
+            }
+        }
+    }
+    let symbols = symbols
+        .iter()
+        .map(|(name, (reason, files))| {
+            let value = if args.detail_level.is_file() && args.demangle {
+                let demangled = display_symbol(name, true);
+                serde_json::json!({ "reason": reason, "files": files, "demangled": demangled })
+            } else if args.detail_level.is_file() {
+                serde_json::json!({ "reason": reason, "files": files })
+            } else {
+                serde_json::Value::from(*reason)
+            };
+            (name.to_string(), value)
+        })
+        .collect::<serde_json::Map<String, serde_json::Value>>();
+    Ok(serde_json::Value::Object(symbols))
+}
+
+/// Prints the Android report section in text mode and returns its JSON value:
+/// the minimum API level the files were built for, and the Bionic symbols
+/// that only exist after the `api` level that was asked for.
+fn android_section(
+    api: u32,
+    findings: &Findings,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let min_sdk = if args.detail_level.is_file() {
+        let mut files = findings.android_api_levels.iter().collect::<Vec<_>>();
+        files.sort();
+        if args.stdout_format.is_text() {
+            for (file, level) in &files {
+                println!("android => min-sdk => {} => {}", level, file.display());
+            }
+        }
+        serde_json::to_value(&findings.android_api_levels)?
+    } else {
+        let level = findings.android_api_levels.values().max();
+        if args.stdout_format.is_text() {
+            if let Some(level) = level {
+                println!("android => min-sdk => {}", level);
+            }
+        }
+        serde_json::to_value(level)?
+    };
+
+    let mut too_new = serde_json::Map::new();
+    let libc = findings.wants.get("LIBC").into_iter().flatten();
+    for (version, functions) in libc {
+        let Some(introduced) = android::libc_api_level(version).filter(|level| *level > api) else {
+            continue;
+        };
+        for (function, files) in functions {
+            let name = display_symbol(function, args.demangle);
+            if args.stdout_format.is_text() {
+                if args.detail_level.is_file() {
+                    for file in files {
+                        println!(
+                            "android => {} => api {} => {}",
+                            name,
+                            introduced,
+                            file.display()
+                        );
                     }
+                } else {
+                    println!("android => {} => api {}", name, introduced);
                 }
             }
+            let value = if args.detail_level.is_file() {
+                serde_json::json!({ "api": introduced, "files": files })
+            } else {
+                serde_json::Value::from(introduced)
+            };
+            too_new.insert(name, value);
+        }
+    }
+
+    Ok(serde_json::json!({
+        "min_sdk": min_sdk,
+        "too_new": too_new,
+    }))
+}
+
+/// Prints the minimum kernel version report section in text mode and returns
+/// its JSON value: the highest version any file declares, or every file's
+/// version at the `file` detail level.
+fn kernel_section(
+    kernel_versions: &HashMap<PathBuf, notes::KernelVersion>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    if args.detail_level.is_file() {
+        let mut files = kernel_versions.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        if args.stdout_format.is_text() {
+            for (file, version) in &files {
+                println!("kernel => {} => {}", version, file.display());
+            }
+        }
+        let files = files
+            .into_iter()
+            .map(|(file, version)| (file, version.to_string()))
+            .collect::<HashMap<&PathBuf, String>>();
+        serde_json::to_value(files)
+    } else {
+        let version = kernel_versions.values().max().map(|v| v.to_string());
+        if args.stdout_format.is_text() {
+            if let Some(version) = &version {
+                println!("kernel => {}", version);
+            }
         }
-        PrintError::None => {}
+        serde_json::to_value(version)
     }
+}
 
-    if !errored.is_empty() {
-        if parsed_args
-            .paths
+/// Prints the GNU property report section in text mode and returns its JSON
+/// value. A feature only counts as enabled when every file has it, the
+/// `file` detail level also lists the files that lack it.
+fn properties_section(
+    properties: &HashMap<PathBuf, notes::GnuProperties>,
+    args: &ScanArgs,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let features = properties
+        .values()
+        .flat_map(|file| file.features.iter().copied())
+        .collect::<BTreeSet<&str>>();
+    let mut features_json = serde_json::Map::new();
+    for feature in features {
+        let mut missing = properties
             .iter()
-            .find(|x| errored.contains_key(&PathBuf::from(x)))
-            .is_some()
-        {
-            std::process::exit(1);
+            .filter(|(_, file)| !file.features.contains(feature))
+            .map(|(path, _)| path)
+            .collect::<Vec<&PathBuf>>();
+        missing.sort();
+        let state = if missing.is_empty() {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        if args.stdout_format.is_text() {
+            if args.detail_level.is_file() && !missing.is_empty() {
+                for path in &missing {
+                    println!("property => {} => missing => {}", feature, path.display());
+                }
+            } else {
+                println!("property => {} => {}", feature, state);
+            }
         }
+        let value = if args.detail_level.is_file() {
+            serde_json::json!({ "enabled": missing.is_empty(), "missing": missing })
+        } else {
+            serde_json::Value::Bool(missing.is_empty())
+        };
+        features_json.insert(feature.to_string(), value);
     }
-    Ok(())
+
+    let x86_isa_needed = if args.detail_level.is_file() {
+        let mut files = properties
+            .iter()
+            .filter_map(|(path, file)| Some((path, file.x86_isa_needed?)))
+            .collect::<Vec<_>>();
+        files.sort();
+        if args.stdout_format.is_text() {
+            for (path, level) in &files {
+                println!(
+                    "property => x86-isa-needed => {} => {}",
+                    level,
+                    path.display()
+                );
+            }
+        }
+        serde_json::to_value(files.into_iter().collect::<HashMap<&PathBuf, &str>>())?
+    } else {
+        let level = properties
+            .values()
+            .filter_map(|file| file.x86_isa_needed)
+            .max_by_key(|level| notes::x86_isa_level_rank(level));
+        if args.stdout_format.is_text() {
+            if let Some(level) = level {
+                println!("property => x86-isa-needed => {}", level);
+            }
+        }
+        serde_json::to_value(level)?
+    };
+
+    Ok(serde_json::json!({
+        "features": features_json,
+        "x86_isa_needed": x86_isa_needed,
+    }))
 }
 
-fn find_required_glibc_version<'a, 'b>(
+/// Replaces a GNU ld script (such as `libc.so` on most distributions) by the
+/// files its `GROUP`/`INPUT` directives reference, resolved under `root`
+/// and, for `-lname`, in `search_dirs`. Any other path is returned as is.
+fn expand_linker_scripts(
     referenced_by: &str,
-    tab: &ParsingTable<'a, AnyEndian, Symbol>,
-    str: &StringTable<'b>,
-    from_file: &PathBuf,
-    map: &mut HashMap<String, HashMap<String, HashSet<PathBuf>>>,
+    path: &Path,
+    root: &Path,
+    search_dirs: &[PathBuf],
+    scripts: &mut HashSet<PathBuf>,
+    errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
+) -> Vec<PathBuf> {
+    let is_script = std::fs::read(path).is_ok_and(|data| linker_script::is_linker_script(&data));
+    if !is_script {
+        return vec![path.to_path_buf()];
+    }
+    if !scripts.insert(path.to_path_buf()) {
+        return Vec::new();
+    }
+
+    let text = std::fs::read_to_string(path).unwrap_or_default();
+    let mut inputs = Vec::new();
+    for reference in linker_script::parse_linker_script(&text) {
+        let input = linker_script::resolve_linker_script_input(&reference, path, root, search_dirs);
+        if input.exists() {
+            inputs.extend(expand_linker_scripts(
+                referenced_by,
+                &input,
+                root,
+                search_dirs,
+                scripts,
+                errored,
+            ));
+        } else {
+            errored
+                .entry(input)
+                .or_insert(("not_found".to_string(), HashSet::new()))
+                .1
+                .insert(referenced_by.to_string());
+        }
+    }
+    inputs
+}
+
+/// Whether the file at the given path is a relocatable (`ET_REL`) object.
+fn is_relocatable_file(path: &Path) -> bool {
+    std::fs::read(path).is_ok_and(|data| {
+        ElfBytes::<AnyEndian>::minimal_parse(&data).is_ok_and(|file| file.ehdr.e_type == ET_REL)
+    })
+}
+
+/// Tells statically linked executables apart: `static` for ones without a
+/// dynamic section, `static-pie` for position independent ones that relocate
+/// themselves. Returns `None` for everything the dynamic loader handles.
+fn static_linkage(path: &Path) -> Option<&'static str> {
+    let file_data = std::fs::read(path).ok()?;
+    let file = ElfBytes::<AnyEndian>::minimal_parse(&file_data).ok()?;
+    let segments = file.segments()?;
+    if segments.iter().any(|phdr| phdr.p_type == PT_INTERP) {
+        return None;
+    }
+    if !segments.iter().any(|phdr| phdr.p_type == PT_DYNAMIC) {
+        return Some("static");
+    }
+    // shared libraries have no interpreter either, only static-pie
+    // executables carry the PIE flag without one
+    let dynamic = file.dynamic().ok()??;
+    let is_pie = dynamic
+        .iter()
+        .any(|entry| entry.d_tag == DT_FLAGS_1 && entry.d_val() as i64 & DF_1_PIE != 0);
+    is_pie.then_some("static-pie")
+}
+
+/// Returns the `DT_SONAME` of the given ELF file, if it has one.
+fn soname<'a>(file: &ElfBytes<'a, AnyEndian>) -> Option<&'a str> {
+    let dynamic = file.dynamic().ok()??;
+    let (_, dynstr) = file.dynamic_symbol_table().ok()??;
+    let entry = dynamic.iter().find(|entry| entry.d_tag == DT_SONAME)?;
+    dynstr.get(entry.d_val() as usize).ok()
+}
+
+/// Renames the files a map is keyed by with `rename`.
+fn rename_keys<V>(
+    map: HashMap<PathBuf, V>,
+    rename: &impl Fn(&Path) -> PathBuf,
+) -> HashMap<PathBuf, V> {
+    map.into_iter()
+        .map(|(file, value)| (rename(&file), value))
+        .collect()
+}
+
+/// Whether `file` belongs to `closure`, archive members (`libfoo.a(bar.o)`)
+/// do when their archive does.
+fn is_in_closure(closure: &HashSet<PathBuf>, file: &Path) -> bool {
+    if closure.contains(file) {
+        return true;
+    }
+    let name = file.to_string_lossy();
+    name.strip_suffix(')')
+        .and_then(|name| name.rsplit_once('('))
+        .is_some_and(|(archive, _)| closure.contains(Path::new(archive)))
+}
+
+/// Whether two files hold the same object, by build-id when both have one
+/// and by contents otherwise.
+fn have_same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(a), Ok(b)) = (std::fs::read(a), std::fs::read(b)) else {
+        return false;
+    };
+    let build_id = |data: &[u8]| {
+        ElfBytes::<AnyEndian>::minimal_parse(data)
+            .ok()
+            .and_then(|file| debuginfo::build_id(&file))
+    };
+    match (build_id(&a), build_id(&b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether the `DT_SONAME` of the library at `realpath` differs from the
+/// `DT_NEEDED` entry that pulled it in or from the file name the loader
+/// opened it by. Libraries without a soname are not checked.
+fn is_soname_mismatch(needed: &str, path: &Path, realpath: &Path) -> bool {
+    let Ok(file_data) = std::fs::read(realpath) else {
+        return false;
+    };
+    let Ok(file) = ElfBytes::<AnyEndian>::minimal_parse(&file_data) else {
+        return false;
+    };
+    let Some(soname) = soname(&file) else {
+        return false;
+    };
+    // DT_NEEDED may also hold a path
+    let needed = Path::new(needed).file_name();
+    let file_name = path.file_name();
+    needed != Some(soname.as_ref()) || file_name != Some(soname.as_ref())
+}
+
+/// Whether the given file name belongs to one of the libraries glibc ships,
+/// which share GLIBC_PRIVATE symbols among themselves.
+fn is_glibc_library(name: &str) -> bool {
+    const GLIBC_LIBRARIES: [&str; 15] = [
+        "libm.so.",
+        "libmvec.so.",
+        "libpthread.so.",
+        "libdl.so.",
+        "librt.so.",
+        "libresolv.so.",
+        "libanl.so.",
+        "libutil.so.",
+        "libnsl.so.",
+        "libnss_",
+        "libthread_db.so.",
+        "libBrokenLocale.so.",
+        "libc_malloc_debug.so.",
+        "libmemusage.so",
+        "libpcprofile.so",
+    ];
+    is_c_library(name) || GLIBC_LIBRARIES.iter().any(|lib| name.starts_with(lib))
+}
+
+/// Whether the given soname belongs to the C library or the dynamic loader.
+fn is_c_library(soname: &str) -> bool {
+    // Bionic's libc has no version suffix
+    soname == "libc.so"
+        || soname.starts_with("libc.so.")
+        || soname.starts_with("ld-linux")
+        || soname.starts_with("ld64.so.")
+        || soname.starts_with("ld.so.")
+}
+
+fn find_required_glibc_version_in_file(
+    referenced_by: &str,
+    name: &str,
+    lib_path: &Path,
+    options: &ScanOptions,
+    findings: &mut Findings,
     errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
 ) {
-    for sym in tab.iter() {
-        if let Ok(name) = str.get(sym.st_name as usize) {
-            if !name.is_empty() {
-                if name.contains("@@GLIBC_") {
-                    let parsed = name.split("@@GLIBC_").collect::<Vec<&str>>();
-                    if parsed.len() != 2 {
-                        // todo: error?
-                        continue;
+    if let Ok(file_data) = std::fs::read(lib_path) {
+        if archive::is_ar_archive(&file_data) {
+            match archive::ar_members(&file_data) {
+                Ok(members) => {
+                    for (member, data) in members {
+                        let member_path =
+                            PathBuf::from(format!("{}({})", lib_path.display(), member));
+                        find_required_glibc_version_in_bytes(
+                            referenced_by,
+                            name,
+                            &data,
+                            &member_path,
+                            options,
+                            findings,
+                            errored,
+                        );
                     }
-                    let function_name = parsed[0];
-                    let wants = parsed[1];
-                    let v = map.entry(wants.to_string()).or_insert(HashMap::new());
-                    let v = v.entry(function_name.to_string()).or_insert(HashSet::new());
-                    v.insert(from_file.clone());
+                }
+                Err(_) => {
+                    errored
+                        .entry(lib_path.to_path_buf())
+                        .or_insert(("cannot_parse".to_string(), HashSet::new()))
+                        .1
+                        .insert(name.to_string());
                 }
             }
         } else {
+            find_required_glibc_version_in_bytes(
+                referenced_by,
+                name,
+                &file_data,
+                lib_path,
+                options,
+                findings,
+                errored,
+            );
+        }
+    } else {
+        errored
+            .entry(lib_path.to_path_buf())
+            .or_insert(("cannot_read".to_string(), HashSet::new()))
+            .1
+            .insert(name.to_string());
+    }
+}
+
+/// Scans a single ELF object (shared library, executable or relocatable
+/// object) that has already been read into memory.
+fn find_required_glibc_version_in_bytes(
+    referenced_by: &str,
+    name: &str,
+    file_data: &[u8],
+    lib_path: &Path,
+    options: &ScanOptions,
+    findings: &mut Findings,
+    errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
+) {
+    if let Ok(file) = ElfBytes::<AnyEndian>::minimal_parse(file_data) {
+        if let Some(soname) = soname(&file) {
+            findings
+                .sonames
+                .insert(lib_path.to_path_buf(), soname.to_string());
+        }
+        if options.checksums {
+            let checksum = Sha256::digest(file_data)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>();
+            findings.checksums.insert(lib_path.to_path_buf(), checksum);
+        }
+        if let Some(build_id) = debuginfo::build_id(&file) {
+            findings
+                .build_ids
+                .insert(lib_path.to_path_buf(), build_id.clone());
+            if let Some(original) = findings.scanned_build_ids.get(&build_id).cloned() {
+                findings.copy_file_findings(&original, lib_path);
+                return;
+            }
+            findings
+                .scanned_build_ids
+                .insert(build_id, lib_path.to_path_buf());
+        }
+        // whatever the C library and the loader export is what they
+        // provide, not what they require
+        let is_libc = soname(&file)
+            .or_else(|| lib_path.file_name().and_then(|name| name.to_str()))
+            .is_some_and(is_c_library);
+        let options = &ScanOptions {
+            include_defined: options.include_defined && (options.include_libc || !is_libc),
+            ifuncs: options.ifuncs && (options.include_libc || !is_libc),
+            ..options.clone()
+        };
+        findings
+            .properties
+            .insert(lib_path.to_path_buf(), notes::gnu_properties(&file));
+        if let Some(api) = notes::android_api_level(&file) {
+            findings
+                .android_api_levels
+                .insert(lib_path.to_path_buf(), api);
+        }
+        if let Some(version) = notes::kernel_version(&file) {
+            findings
+                .kernel_versions
+                .insert(lib_path.to_path_buf(), version);
+        }
+        find_required_glibc_version_by_versym(
+            referenced_by,
+            &file,
+            lib_path,
+            options,
+            findings,
+            errored,
+        );
+        find_required_glibc_version_in_symtab(
+            referenced_by,
+            name,
+            &file,
+            lib_path,
+            options,
+            findings,
+            errored,
+        );
+        if options.separate_debug_files || !options.debuginfod_urls.is_empty() {
+            let debug_data = debuginfo::find_debug_file(&file, lib_path, &options.root)
+                .and_then(|debug_file| std::fs::read(debug_file).ok())
+                .or_else(|| {
+                    // only bother a debuginfod server for objects that have
+                    // been stripped of their symbol table
+                    if options.debuginfod_urls.is_empty()
+                        || !matches!(compression::symbol_table_data(&file), Ok(None))
+                    {
+                        return None;
+                    }
+                    let build_id = debuginfo::build_id(&file)?;
+                    debuginfod::fetch_debuginfo(&options.debuginfod_urls, &build_id).ok()
+                });
+            if let Some(debug_data) = debug_data {
+                if let Ok(debug) = ElfBytes::<AnyEndian>::minimal_parse(&debug_data) {
+                    find_required_glibc_version_in_symtab(
+                        referenced_by,
+                        name,
+                        &debug,
+                        lib_path,
+                        options,
+                        findings,
+                        errored,
+                    );
+                }
+            }
+        }
+    } else {
+        errored
+            .entry(lib_path.to_path_buf())
+            .or_insert(("cannot_parse".to_string(), HashSet::new()))
+            .1
+            .insert(name.to_string());
+    }
+}
+
+/// Scans the (possibly compressed) `.symtab` of an ELF object.
+fn find_required_glibc_version_in_symtab(
+    referenced_by: &str,
+    name: &str,
+    file: &ElfBytes<AnyEndian>,
+    lib_path: &Path,
+    options: &ScanOptions,
+    findings: &mut Findings,
+    errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
+) {
+    match compression::symbol_table_data(file) {
+        Ok(Some((symtab, strtab))) => {
+            let symtab = SymbolTable::new(file.ehdr.endianness, file.ehdr.class, &symtab);
+            let strtab = StringTable::new(&strtab);
+            find_required_glibc_version(
+                referenced_by,
+                &symtab,
+                &strtab,
+                lib_path,
+                options,
+                findings,
+                errored,
+            );
+        }
+        Ok(None) => {}
+        Err(_) => {
             errored
-                .entry(from_file.clone())
-                .or_insert(("".to_string(), HashSet::new()))
+                .entry(lib_path.to_path_buf())
+                .or_insert(("cannot_parse".to_string(), HashSet::new()))
                 .1
-                .insert(referenced_by.to_string());
+                .insert(name.to_string());
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn gather_deps_required_libc_version(
     referenced_by: &str,
     name: &str,
     libraries: &HashMap<String, Library>,
-    scopes: &Vec<PathBuf>,
-    wants: &mut HashMap<String, HashMap<String, HashSet<PathBuf>>>,
+    scopes: &[PathBuf],
+    exclusions: &inputs::Exclusions,
+    resolution: &Resolution,
+    options: &ScanOptions,
+    findings: &mut Findings,
     visited: &mut HashSet<PathBuf>,
     errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
 ) {
@@ -393,69 +5543,76 @@ fn gather_deps_required_libc_version(
         name,
         libraries,
         scopes,
+        exclusions,
+        resolution,
         &mut paths,
         visited,
         errored,
     );
     for lib_path in paths {
-        if let Ok(file_data) = std::fs::read(lib_path.clone()) {
-            let slice = file_data.as_slice();
-            if let Ok(file) = ElfBytes::<AnyEndian>::minimal_parse(slice) {
-                if let Ok(common) = file.find_common_data() {
-                    if let (Some(dynsym), Some(dynstr)) = (common.dynsyms, common.dynsyms_strs) {
-                        find_required_glibc_version(
-                            &referenced_by,
-                            &dynsym,
-                            &dynstr,
-                            &lib_path,
-                            wants,
-                            errored,
-                        );
-                    }
-                    if let (Some(symtab), Some(strtab)) = (common.symtab, common.symtab_strs) {
-                        find_required_glibc_version(
-                            &referenced_by,
-                            &symtab,
-                            &strtab,
-                            &lib_path,
-                            wants,
-                            errored,
-                        );
-                    }
-                }
-            } else {
-                errored
-                    .entry(lib_path.clone())
-                    .or_insert(("cannot_parse".to_string(), HashSet::new()))
-                    .1
-                    .insert(name.to_string());
-            }
-        } else {
-            errored
-                .entry(lib_path.clone())
-                .or_insert(("cannot_read".to_string(), HashSet::new()))
-                .1
-                .insert(name.to_string());
-        }
+        find_required_glibc_version_in_file(
+            referenced_by,
+            name,
+            &lib_path,
+            options,
+            findings,
+            errored,
+        );
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn gather_deps_paths<'a>(
     referenced_by: &'a str,
     name: &'a str,
     libraries: &'a HashMap<String, Library>,
-    scopes: &Vec<PathBuf>,
+    scopes: &[PathBuf],
+    exclusions: &inputs::Exclusions,
+    resolution: &Resolution,
     paths: &mut HashSet<PathBuf>,
     visited: &mut HashSet<PathBuf>,
     errored: &mut HashMap<PathBuf, (String, HashSet<String>)>,
 ) {
     if let Some(lib) = libraries.get(name) {
         if let Some(path) = lib.realpath.as_ref() {
-            if let Some(_scope) = scopes.iter().find(|scope| path.starts_with(scope)) {
+            // in a sysroot with both lib/ and lib64/ the resolved library may
+            // have the wrong width, prefer another candidate that matches
+            let path = if resolution.is_wrong_arch(path) {
+                match resolution.find_matching_candidate(name) {
+                    Some(candidate) => candidate,
+                    None => {
+                        errored
+                            .entry(path.to_path_buf())
+                            .or_insert(("wrong_arch".to_string(), HashSet::new()))
+                            .1
+                            .insert(referenced_by.to_string());
+                        return;
+                    }
+                }
+            } else {
+                path.to_path_buf()
+            };
+            let in_scope = scopes.iter().any(|scope| path.starts_with(scope));
+            if in_scope && !exclusions.is_excluded(&path) {
+                if is_soname_mismatch(name, &lib.path, &path) {
+                    errored
+                        .entry(path.to_path_buf())
+                        .or_insert(("soname_mismatch".to_string(), HashSet::new()))
+                        .1
+                        .insert(referenced_by.to_string());
+                }
                 if !paths.insert(path.to_path_buf()) || !visited.insert(path.to_path_buf()) {
                     return;
                 };
             }
+        } else if let Some(candidate) = resolution.find_wrong_arch_candidate(name) {
+            // lddtree skips incompatible candidates, so a library that only
+            // exists for another architecture would look like it is missing
+            errored
+                .entry(candidate)
+                .or_insert(("wrong_arch".to_string(), HashSet::new()))
+                .1
+                .insert(referenced_by.to_string());
         } else {
             errored
                 .entry(lib.path.clone())
@@ -467,9 +5624,11 @@ fn gather_deps_paths<'a>(
         for needed in &lib.needed {
             gather_deps_paths(
                 referenced_by,
-                &needed,
+                needed,
                 libraries,
                 scopes,
+                exclusions,
+                resolution,
                 paths,
                 visited,
                 errored,
@@ -477,3 +5636,103 @@ fn gather_deps_paths<'a>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compiles the C `source` into `output` with the extra `args`.
+    fn cc(source: &str, output: &Path, args: &[&str]) {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("source.c");
+        std::fs::write(&file, source).unwrap();
+        let status = std::process::Command::new("cc")
+            .arg(&file)
+            .arg("-o")
+            .arg(output)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    /// The options of `lddcheck` run with `args`.
+    fn scan_args(args: &[&str]) -> ScanArgs {
+        let args = ["lddcheck", "-p", "input"].iter().chain(args);
+        Args::try_parse_from(args).unwrap().input.scan
+    }
+
+    #[test]
+    fn keeps_symbols_that_demangle_alike_apart() {
+        let mut findings = Findings::default();
+        let functions = findings
+            .wants
+            .entry("GLIBCXX".to_string())
+            .or_default()
+            .entry("3.4".to_string())
+            .or_default();
+        // the complete and base object constructors
+        for (function, file) in [("_ZN3FooC1Ev", "/a"), ("_ZN3FooC2Ev", "/b")] {
+            functions.insert(function.to_string(), HashSet::from([PathBuf::from(file)]));
+        }
+        let args = scan_args(&["--demangle", "--detail-level", "file", "--stdout", "json"]);
+
+        let report = versions_report("GLIBCXX", &findings, &HashMap::new(), &args, "").unwrap();
+        let functions = report["3.4"].as_object().unwrap();
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions["_ZN3FooC1Ev"]["demangled"], "Foo::Foo()");
+        assert_eq!(functions["_ZN3FooC2Ev"]["files"], serde_json::json!(["/b"]));
+    }
+
+    #[test]
+    fn resolves_runpaths_under_the_root() {
+        let root = tempfile::tempdir().unwrap();
+        let bin = root.path().join("opt/app/bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let app = bin.join("app");
+        cc(
+            "int main(void) { return 0; }",
+            &app,
+            &[
+                "-Wl,--enable-new-dtags",
+                "-Wl,-rpath,$ORIGIN/../lib:/opt/other",
+            ],
+        );
+
+        assert_eq!(
+            rooted_runpaths(&app, root.path()),
+            [
+                root.path().join("opt/app/bin/../lib"),
+                root.path().join("opt/other")
+            ]
+        );
+    }
+
+    #[test]
+    fn explains_versions_of_namespaces_with_underscores() {
+        let mut findings = Findings::default();
+        let library = Path::new("/lib/libbar.so.1");
+        insert_required_version(&mut findings, "LIBFOO_BAR", "1.0", "foo", library, false);
+        insert_required_version(
+            &mut findings,
+            "GLIBC",
+            "PRIVATE",
+            "_dl_argv",
+            library,
+            false,
+        );
+        findings
+            .closures
+            .insert("app".to_string(), HashSet::from([library.to_path_buf()]));
+        let args = scan_args(&[]);
+
+        // the queries are the versions as reported
+        let chains = why_chains("LIBFOO_BAR_1.0", &findings, &args);
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].symbol, "foo");
+        assert_eq!(chains[0].version, "LIBFOO_BAR_1.0");
+        let chains = why_chains("GLIBC_PRIVATE", &findings, &args);
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].symbol, "_dl_argv");
+    }
+}
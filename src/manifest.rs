@@ -0,0 +1,108 @@
+use crate::abidb::AbiDb;
+use crate::distro;
+use crate::symbols;
+use lddtree::ld_so_conf::parse_ld_so_conf;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// The versioned symbols every library of a sysroot exports, so binaries
+/// can be checked against it without the sysroot at hand.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The sysroot the manifest was generated from.
+    pub root: String,
+    /// soname (or file name) => symbol => versions
+    #[serde(flatten)]
+    pub symbols: AbiDb,
+}
+
+impl Manifest {
+    /// Scans the library directories of the sysroot at `root`: the ones
+    /// listed in `etc/ld.so.conf` and the default `lib`, `lib64`, `usr/lib`
+    /// and `usr/lib64`.
+    pub fn generate(root: &Path) -> Manifest {
+        let mut dirs: Vec<PathBuf> = parse_ld_so_conf(root.join("etc/ld.so.conf"), root)
+            .unwrap_or_default()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        for dir in ["lib", "lib64", "usr/lib", "usr/lib64"] {
+            dirs.push(root.join(dir));
+        }
+
+        let mut manifest = Manifest {
+            root: root.display().to_string(),
+            ..Manifest::default()
+        };
+        let mut seen = HashSet::new();
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut paths = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| is_shared_library_name(path) && path.is_file())
+                .collect::<Vec<PathBuf>>();
+            paths.sort();
+            for path in paths {
+                // symlinks such as libfoo.so.1 -> libfoo.so.1.2.3 are only
+                // read once
+                let realpath = std::fs::canonicalize(&path).unwrap_or(path.clone());
+                if !seen.insert(realpath) {
+                    continue;
+                }
+                let exports = symbols::read_exports(&path);
+                if exports.versioned.is_empty() {
+                    continue;
+                }
+                let name = exports.soname.clone().unwrap_or_else(|| {
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                });
+                manifest.symbols.add_exports(name, exports);
+            }
+        }
+        manifest
+    }
+
+    /// Reads a manifest written by the `manifest` subcommand.
+    pub fn read(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Returns the versioned symbols the ELF file at `path` imports that no
+    /// library in the manifest provides, as (version, symbol) pairs. Weak
+    /// references are left out since they may stay unresolved.
+    pub fn missing_imports(&self, path: &Path) -> Vec<(String, String)> {
+        let mut missing = symbols::read_imports(path)
+            .into_iter()
+            .filter(|import| !import.weak)
+            .filter_map(|import| Some((import.version?, import.name)))
+            .filter(|(version, name)| !self.symbols.provides(name, version))
+            .collect::<Vec<_>>();
+        missing.sort_by(|(a_version, a_name), (b_version, b_name)| {
+            let key = |version: &str| {
+                let (namespace, version) = version.rsplit_once('_').unwrap_or_default();
+                (namespace.to_string(), distro::version_key(version))
+            };
+            key(a_version)
+                .cmp(&key(b_version))
+                .then_with(|| a_name.cmp(b_name))
+        });
+        missing.dedup();
+        missing
+    }
+}
+
+/// Whether the file name looks like a shared library (`libfoo.so` or
+/// `libfoo.so.1.2`).
+fn is_shared_library_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".so") || name.contains(".so."))
+}
@@ -0,0 +1,66 @@
+use crate::violations::Violation;
+
+/// Returns a Markdown report for pull request descriptions and bots: a
+/// summary (the number of inputs, the newest glibc version they require and
+/// the violations), then a table of the required versions, with the columns
+/// of the detail level. Cells of several lines list several values.
+pub fn report(
+    inputs: usize,
+    newest_glibc: Option<&str>,
+    violations: &[Violation],
+    header: &[&str],
+    rows: &[Vec<String>],
+) -> String {
+    let mut markdown = String::from("## lddcheck report\n\n");
+    markdown.push_str(&format!("- Inputs: {}\n", inputs));
+    markdown.push_str(&format!(
+        "- Newest glibc version required: {}\n",
+        newest_glibc.map_or("none".to_string(), |version| format!("`{}`", version))
+    ));
+    markdown.push_str(&format!("- Violations: {}\n", violations.len()));
+    if !violations.is_empty() {
+        markdown.push_str("\n### Violations\n\n");
+        for violation in violations {
+            match &violation.file {
+                Some(file) => markdown.push_str(&format!(
+                    "- **{}** `{}`: {}\n",
+                    violation.rule,
+                    file.display(),
+                    escape(&violation.message)
+                )),
+                None => markdown.push_str(&format!(
+                    "- **{}**: {}\n",
+                    violation.rule,
+                    escape(&violation.message)
+                )),
+            }
+        }
+    }
+    markdown.push_str("\n### Required versions\n\n");
+    markdown.push_str(&format!("| {} |\n", header.join(" | ")));
+    markdown.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in rows {
+        let cells = row
+            .iter()
+            .map(|cell| code_cell(cell))
+            .collect::<Vec<String>>();
+        markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    markdown
+}
+
+/// Formats a table cell as code, one line of `text` per line of the cell.
+fn code_cell(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("`{}`", line.replace('`', "'").replace('|', "\\|")))
+        .collect::<Vec<String>>()
+        .join("<br>")
+}
+
+/// Escapes the characters that would start formatting in a message.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('*', "\\*")
+        .replace('_', "\\_")
+        .replace('<', "&lt;")
+}
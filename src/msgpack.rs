@@ -0,0 +1,101 @@
+use serde_json::Value;
+
+/// Returns `value` encoded as MessagePack, integers in the smallest format
+/// that holds them.
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write(&mut bytes, value);
+    bytes
+}
+
+fn write(bytes: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => bytes.push(0xc0),
+        Value::Bool(false) => bytes.push(0xc2),
+        Value::Bool(true) => bytes.push(0xc3),
+        Value::Number(number) => {
+            if let Some(number) = number.as_u64() {
+                write_unsigned(bytes, number);
+            } else if let Some(number) = number.as_i64() {
+                write_negative(bytes, number);
+            } else {
+                bytes.push(0xcb);
+                bytes.extend(number.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(string) => {
+            write_length(bytes, string.len(), 0xa0, 32, [0xd9, 0xda, 0xdb]);
+            bytes.extend(string.as_bytes());
+        }
+        Value::Array(values) => {
+            write_length(bytes, values.len(), 0x90, 16, [0, 0xdc, 0xdd]);
+            for value in values {
+                write(bytes, value);
+            }
+        }
+        Value::Object(map) => {
+            write_length(bytes, map.len(), 0x80, 16, [0, 0xde, 0xdf]);
+            for (key, value) in map {
+                write(bytes, &Value::String(key.clone()));
+                write(bytes, value);
+            }
+        }
+    }
+}
+
+fn write_unsigned(bytes: &mut Vec<u8>, number: u64) {
+    if number < 0x80 {
+        bytes.push(number as u8);
+    } else if number <= u64::from(u8::MAX) {
+        bytes.extend([0xcc, number as u8]);
+    } else if number <= u64::from(u16::MAX) {
+        bytes.push(0xcd);
+        bytes.extend((number as u16).to_be_bytes());
+    } else if number <= u64::from(u32::MAX) {
+        bytes.push(0xce);
+        bytes.extend((number as u32).to_be_bytes());
+    } else {
+        bytes.push(0xcf);
+        bytes.extend(number.to_be_bytes());
+    }
+}
+
+fn write_negative(bytes: &mut Vec<u8>, number: i64) {
+    if number >= -32 {
+        bytes.push(number as u8);
+    } else if number >= i64::from(i8::MIN) {
+        bytes.extend([0xd0, number as u8]);
+    } else if number >= i64::from(i16::MIN) {
+        bytes.push(0xd1);
+        bytes.extend((number as i16).to_be_bytes());
+    } else if number >= i64::from(i32::MIN) {
+        bytes.push(0xd2);
+        bytes.extend((number as i32).to_be_bytes());
+    } else {
+        bytes.push(0xd3);
+        bytes.extend(number.to_be_bytes());
+    }
+}
+
+/// Writes the header of a string, array or map of `length`: the `fixed`
+/// format below `fixed_limit`, then those of `markers` with an 8, 16 and 32
+/// bit length (0 where the type has none).
+fn write_length(
+    bytes: &mut Vec<u8>,
+    length: usize,
+    fixed: u8,
+    fixed_limit: usize,
+    markers: [u8; 3],
+) {
+    if length < fixed_limit {
+        bytes.push(fixed | length as u8);
+    } else if markers[0] != 0 && length <= usize::from(u8::MAX) {
+        bytes.extend([markers[0], length as u8]);
+    } else if length <= usize::from(u16::MAX) {
+        bytes.push(markers[1]);
+        bytes.extend((length as u16).to_be_bytes());
+    } else {
+        bytes.push(markers[2]);
+        bytes.extend((length as u32).to_be_bytes());
+    }
+}
@@ -0,0 +1,195 @@
+use elf::abi::{
+    GNU_PROPERTY_AARCH64_FEATURE_1_AND, GNU_PROPERTY_AARCH64_FEATURE_1_BTI,
+    GNU_PROPERTY_AARCH64_FEATURE_1_PAC, NT_GNU_PROPERTY_TYPE_0, PT_GNU_PROPERTY, PT_NOTE, SHT_NOTE,
+};
+use elf::endian::AnyEndian;
+use elf::file::Class;
+use elf::note::Note;
+use elf::ElfBytes;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The `NT_GNU_ABI_TAG` operating system value for Linux. The others name
+/// the Hurd, Solaris and FreeBSD.
+const ABI_TAG_OS_LINUX: u32 = 0;
+
+/// The type of the note in `.note.android.ident`, holding the API level the
+/// object was built for.
+const NT_ANDROID_TYPE_IDENT: u64 = 1;
+
+/// x86 properties, which the elf crate has no constants for.
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc0000002;
+const GNU_PROPERTY_X86_FEATURE_1_IBT: u32 = 0x1;
+const GNU_PROPERTY_X86_FEATURE_1_SHSTK: u32 = 0x2;
+const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc0008002;
+
+/// The x86-64 micro-architecture levels, in order of their bit in
+/// `GNU_PROPERTY_X86_ISA_1_NEEDED`.
+const X86_ISA_LEVELS: [&str; 4] = ["x86-64-baseline", "x86-64-v2", "x86-64-v3", "x86-64-v4"];
+
+/// A Linux kernel version, as found in a `NT_GNU_ABI_TAG` note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub subminor: u32,
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.subminor)
+    }
+}
+
+/// Returns the minimum Linux kernel version declared by the `.note.ABI-tag`
+/// note of the given ELF file, falling back to the `PT_NOTE` segments when
+/// the section headers have been stripped.
+pub fn kernel_version(file: &ElfBytes<AnyEndian>) -> Option<KernelVersion> {
+    let from_sections = file.section_headers().and_then(|shdrs| {
+        shdrs
+            .iter()
+            .filter(|shdr| shdr.sh_type == SHT_NOTE)
+            .find_map(|shdr| find_abi_tag(file.section_data_as_notes(&shdr).ok()?))
+    });
+    from_sections.or_else(|| {
+        file.segments()?
+            .iter()
+            .filter(|phdr| phdr.p_type == PT_NOTE)
+            .find_map(|phdr| find_abi_tag(file.segment_data_as_notes(&phdr).ok()?))
+    })
+}
+
+fn find_abi_tag<'data>(mut notes: impl Iterator<Item = Note<'data>>) -> Option<KernelVersion> {
+    notes.find_map(|note| match note {
+        Note::GnuAbiTag(tag) if tag.os == ABI_TAG_OS_LINUX => Some(KernelVersion {
+            major: tag.major,
+            minor: tag.minor,
+            subminor: tag.subminor,
+        }),
+        _ => None,
+    })
+}
+
+/// What the `.note.gnu.property` note of an object declares.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GnuProperties {
+    /// The control-flow protection features the object was built with:
+    /// `ibt` and `shstk` on x86, `bti` and `pac` on AArch64. The loader only
+    /// enables one when every object in the process has it.
+    pub features: BTreeSet<&'static str>,
+    /// The highest x86-64 micro-architecture level the object needs.
+    pub x86_isa_needed: Option<&'static str>,
+}
+
+/// Returns the GNU properties of the given ELF file. Objects without a
+/// property note have none of the features.
+pub fn gnu_properties(file: &ElfBytes<AnyEndian>) -> GnuProperties {
+    let mut properties = GnuProperties::default();
+    let from_sections = file.section_headers().and_then(|shdrs| {
+        shdrs
+            .iter()
+            .filter(|shdr| shdr.sh_type == SHT_NOTE)
+            .find_map(|shdr| find_property_note(file.section_data_as_notes(&shdr).ok()?))
+    });
+    let desc = from_sections.or_else(|| {
+        file.segments()?
+            .iter()
+            .filter(|phdr| phdr.p_type == PT_GNU_PROPERTY)
+            .find_map(|phdr| find_property_note(file.segment_data_as_notes(&phdr).ok()?))
+    });
+    let Some(desc) = desc else {
+        return properties;
+    };
+
+    // each property is a (type, size) pair followed by its data, padded to
+    // the word size of the object
+    let align = match file.ehdr.class {
+        Class::ELF32 => 4,
+        Class::ELF64 => 8,
+    };
+    let read_u32 = |data: &[u8]| -> u32 {
+        let bytes: [u8; 4] = data.try_into().unwrap();
+        match file.ehdr.endianness {
+            AnyEndian::Little => u32::from_le_bytes(bytes),
+            AnyEndian::Big => u32::from_be_bytes(bytes),
+        }
+    };
+    let mut rest = desc;
+    while rest.len() >= 8 {
+        let pr_type = read_u32(&rest[0..4]);
+        let pr_datasz = read_u32(&rest[4..8]) as usize;
+        let Some(data) = rest.get(8..8 + pr_datasz) else {
+            break;
+        };
+        let value = if data.len() >= 4 {
+            read_u32(&data[..4])
+        } else {
+            0
+        };
+        match pr_type {
+            GNU_PROPERTY_X86_FEATURE_1_AND => {
+                if value & GNU_PROPERTY_X86_FEATURE_1_IBT != 0 {
+                    properties.features.insert("ibt");
+                }
+                if value & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0 {
+                    properties.features.insert("shstk");
+                }
+            }
+            GNU_PROPERTY_AARCH64_FEATURE_1_AND => {
+                if value & GNU_PROPERTY_AARCH64_FEATURE_1_BTI != 0 {
+                    properties.features.insert("bti");
+                }
+                if value & GNU_PROPERTY_AARCH64_FEATURE_1_PAC != 0 {
+                    properties.features.insert("pac");
+                }
+            }
+            GNU_PROPERTY_X86_ISA_1_NEEDED => {
+                properties.x86_isa_needed = X86_ISA_LEVELS
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find(|(bit, _)| value & (1 << bit) != 0)
+                    .map(|(_, level)| *level);
+            }
+            _ => {}
+        }
+        let next = (8 + pr_datasz).div_ceil(align) * align;
+        rest = rest.get(next..).unwrap_or_default();
+    }
+    properties
+}
+
+fn find_property_note<'data>(mut notes: impl Iterator<Item = Note<'data>>) -> Option<&'data [u8]> {
+    notes.find_map(|note| match note {
+        Note::Unknown(note) if note.name == "GNU" && note.n_type == NT_GNU_PROPERTY_TYPE_0 => {
+            Some(note.desc)
+        }
+        _ => None,
+    })
+}
+
+/// The position of an x86-64 micro-architecture level, for finding the
+/// highest one needed.
+pub fn x86_isa_level_rank(level: &str) -> usize {
+    X86_ISA_LEVELS
+        .iter()
+        .position(|known| *known == level)
+        .unwrap_or(0)
+}
+
+/// Returns the minimum Android API level recorded in the
+/// `.note.android.ident` note the NDK adds to every object it links.
+pub fn android_api_level(file: &ElfBytes<AnyEndian>) -> Option<u32> {
+    let shdr = file.section_header_by_name(".note.android.ident").ok()??;
+    let mut notes = file.section_data_as_notes(&shdr).ok()?;
+    notes.find_map(|note| match note {
+        Note::Unknown(note) if note.name == "Android" && note.n_type == NT_ANDROID_TYPE_IDENT => {
+            let bytes: [u8; 4] = note.desc.get(..4)?.try_into().ok()?;
+            Some(match file.ehdr.endianness {
+                AnyEndian::Little => u32::from_le_bytes(bytes),
+                AnyEndian::Big => u32::from_be_bytes(bytes),
+            })
+        }
+        _ => None,
+    })
+}
@@ -0,0 +1,152 @@
+use lddtree::DependencyTree;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// A PyPA platform tag policy: the newest symbol versions and the system
+/// libraries a wheel tagged with it may depend on, as in auditwheel's
+/// policy files (x86_64 values).
+#[derive(Debug)]
+pub struct Policy {
+    pub name: &'static str,
+    /// The legacy names of the tag, e.g. `manylinux2014`.
+    pub aliases: &'static [&'static str],
+    /// The newest `GLIBC_` version allowed, `None` for musllinux tags which
+    /// allow none at all.
+    pub glibc: Option<&'static str>,
+    pub glibcxx: &'static str,
+    pub cxxabi: &'static str,
+    pub gcc: &'static str,
+    /// The sonames that may be loaded from the system.
+    pub libraries: &'static [&'static str],
+}
+
+const MANYLINUX_LIBRARIES: &[&str] = &[
+    "libgcc_s.so.1",
+    "libstdc++.so.6",
+    "libm.so.6",
+    "libdl.so.2",
+    "librt.so.1",
+    "libc.so.6",
+    "libnsl.so.1",
+    "libutil.so.1",
+    "libpthread.so.0",
+    "libresolv.so.2",
+    "libX11.so.6",
+    "libXext.so.6",
+    "libXrender.so.1",
+    "libICE.so.6",
+    "libSM.so.6",
+    "libGL.so.1",
+    "libgobject-2.0.so.0",
+    "libgthread-2.0.so.0",
+    "libglib-2.0.so.0",
+];
+
+const MANYLINUX1_LIBRARIES: &[&str] = &[
+    "libpanelw.so.5",
+    "libncursesw.so.5",
+    "libgcc_s.so.1",
+    "libstdc++.so.6",
+    "libm.so.6",
+    "libdl.so.2",
+    "librt.so.1",
+    "libc.so.6",
+    "libnsl.so.1",
+    "libutil.so.1",
+    "libpthread.so.0",
+    "libresolv.so.2",
+    "libX11.so.6",
+    "libXext.so.6",
+    "libXrender.so.1",
+    "libICE.so.6",
+    "libSM.so.6",
+    "libGL.so.1",
+    "libgobject-2.0.so.0",
+    "libgthread-2.0.so.0",
+    "libglib-2.0.so.0",
+];
+
+const MUSLLINUX_LIBRARIES: &[&str] = &["libc.so", "libgcc_s.so.1", "libstdc++.so.6"];
+
+#[rustfmt::skip]
+const POLICIES: &[Policy] = &[
+    Policy { name: "manylinux_2_5", aliases: &["manylinux1"], glibc: Some("2.5"), glibcxx: "3.4.8", cxxabi: "1.3.1", gcc: "4.0.0", libraries: MANYLINUX1_LIBRARIES },
+    Policy { name: "manylinux_2_12", aliases: &["manylinux2010"], glibc: Some("2.12"), glibcxx: "3.4.13", cxxabi: "1.3.3", gcc: "4.4.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "manylinux_2_17", aliases: &["manylinux2014"], glibc: Some("2.17"), glibcxx: "3.4.19", cxxabi: "1.3.7", gcc: "4.8.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "manylinux_2_24", aliases: &[], glibc: Some("2.24"), glibcxx: "3.4.22", cxxabi: "1.3.10", gcc: "4.8.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "manylinux_2_28", aliases: &[], glibc: Some("2.28"), glibcxx: "3.4.25", cxxabi: "1.3.11", gcc: "7.0.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "manylinux_2_31", aliases: &[], glibc: Some("2.31"), glibcxx: "3.4.28", cxxabi: "1.3.12", gcc: "7.0.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "manylinux_2_34", aliases: &[], glibc: Some("2.34"), glibcxx: "3.4.29", cxxabi: "1.3.13", gcc: "7.0.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "manylinux_2_35", aliases: &[], glibc: Some("2.35"), glibcxx: "3.4.30", cxxabi: "1.3.13", gcc: "12.0.0", libraries: MANYLINUX_LIBRARIES },
+    Policy { name: "musllinux_1_1", aliases: &[], glibc: None, glibcxx: "3.4.28", cxxabi: "1.3.12", gcc: "7.0.0", libraries: MUSLLINUX_LIBRARIES },
+    Policy { name: "musllinux_1_2", aliases: &[], glibc: None, glibcxx: "3.4.28", cxxabi: "1.3.12", gcc: "7.0.0", libraries: MUSLLINUX_LIBRARIES },
+];
+
+/// Looks up a policy by its name or one of its aliases.
+pub fn find_policy(name: &str) -> Option<&'static Policy> {
+    POLICIES
+        .iter()
+        .find(|policy| policy.name == name || policy.aliases.contains(&name))
+}
+
+impl Policy {
+    /// The newest version of `namespace` the policy allows, `None` if it
+    /// does not limit that namespace.
+    pub fn max_version(&self, namespace: &str) -> Option<&'static str> {
+        match namespace {
+            "GLIBCXX" => Some(self.glibcxx),
+            "CXXABI" => Some(self.cxxabi),
+            "GCC" => Some(self.gcc),
+            _ => None,
+        }
+    }
+
+    /// Whether the library may be loaded from the system. The dynamic
+    /// loader always may.
+    pub fn allows_library(&self, soname: &str) -> bool {
+        self.libraries.contains(&soname) || soname.starts_with("ld-")
+    }
+}
+
+/// The libraries of a dependency tree as a policy sees them.
+#[derive(Debug, Default)]
+pub struct LibraryCheck {
+    /// The files of the libraries that may be loaded from the system.
+    pub allowed: Vec<PathBuf>,
+    /// The libraries that may not, with the file they resolved to if any.
+    pub disallowed: Vec<(String, Option<PathBuf>)>,
+}
+
+impl Policy {
+    /// Walks the dependency tree like auditwheel does: libraries the policy
+    /// allows are loaded from the system and not looked into, bundled ones
+    /// are followed, and anything else is not allowed (and followed too, as
+    /// it would have to be bundled along).
+    pub fn check_libraries(
+        &self,
+        deps: &DependencyTree,
+        is_bundled: impl Fn(&Path) -> bool,
+    ) -> LibraryCheck {
+        let mut check = LibraryCheck::default();
+        let mut queue = deps.needed.clone();
+        let mut seen = HashSet::new();
+        while let Some(name) = queue.pop() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let lib = deps.libraries.get(&name);
+            let realpath = lib.and_then(|lib| lib.realpath.clone());
+            if self.allows_library(&name) {
+                check.allowed.extend(realpath);
+                continue;
+            }
+            if !realpath.as_deref().is_some_and(&is_bundled) {
+                check.disallowed.push((name.clone(), realpath));
+            }
+            if let Some(lib) = lib {
+                queue.extend(lib.needed.iter().cloned());
+            }
+        }
+        check
+    }
+}
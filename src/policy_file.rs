@@ -0,0 +1,266 @@
+use crate::distro;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item};
+
+/// The rules of a project, read from a TOML policy file for `lddcheck check
+/// --policy`:
+///
+/// ```toml
+/// [max]
+/// GLIBC = "2.17"
+/// GLIBCXX = "3.4.19"
+///
+/// [libraries]
+/// allowed = ["libc.so.6", "libm.so.6", "ld-linux-*.so.*"]
+///
+/// [rpaths]
+/// allowed = ["$ORIGIN", "$ORIGIN/../lib"]
+///
+/// [[exceptions]]
+/// file = "*/libfoo.so.1"
+/// symbol = "memfd_create"
+/// reason = "only called after checking the kernel supports it"
+/// ```
+#[derive(Debug, Default)]
+pub struct PolicyFile {
+    /// namespace => the newest version allowed
+    pub max: BTreeMap<String, String>,
+    /// The sonames that may be loaded from outside the checked files, any
+    /// when there is no `[libraries]` table.
+    pub libraries: Option<Vec<glob::Pattern>>,
+    /// The `DT_RPATH` and `DT_RUNPATH` entries allowed, any when there is no
+    /// `[rpaths]` table.
+    pub rpaths: Option<Vec<glob::Pattern>>,
+    pub exceptions: Vec<Exception>,
+}
+
+/// What a violation is about, for the exceptions to match it.
+#[derive(Debug, Default)]
+pub struct Subject<'a> {
+    pub file: &'a str,
+    pub symbol: Option<&'a str>,
+    /// e.g. `GLIBC_2.34`
+    pub version: Option<&'a str>,
+    pub soname: Option<&'a str>,
+    pub rpath: Option<&'a str>,
+}
+
+/// A violation not to report: every pattern given has to match it.
+#[derive(Debug, Default)]
+pub struct Exception {
+    file: Option<glob::Pattern>,
+    symbol: Option<glob::Pattern>,
+    version: Option<glob::Pattern>,
+    soname: Option<glob::Pattern>,
+    rpath: Option<glob::Pattern>,
+}
+
+impl Exception {
+    fn matches(&self, subject: &Subject) -> bool {
+        let matches = |pattern: &Option<glob::Pattern>, value: Option<&str>| match pattern {
+            Some(pattern) => value.is_some_and(|value| pattern.matches(value)),
+            None => true,
+        };
+        matches(&self.file, Some(subject.file))
+            && matches(&self.symbol, subject.symbol)
+            && matches(&self.version, subject.version)
+            && matches(&self.soname, subject.soname)
+            && matches(&self.rpath, subject.rpath)
+    }
+}
+
+impl PolicyFile {
+    pub fn read(path: &Path) -> Result<PolicyFile, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let document = text
+            .parse::<DocumentMut>()
+            .map_err(|err| format!("cannot parse {}: {}", path.display(), err))?;
+        let mut policy = PolicyFile::default();
+        if let Some(max) = document.get("max") {
+            let max = max.as_table_like().ok_or("[max] must be a table")?;
+            for (namespace, version) in max.iter() {
+                let version = version
+                    .as_str()
+                    .ok_or_else(|| format!("max.{} must be a string", namespace))?;
+                policy
+                    .max
+                    .insert(namespace.to_string(), version.to_string());
+            }
+        }
+        policy.libraries = allowed(&document, "libraries")?;
+        policy.rpaths = allowed(&document, "rpaths")?;
+        if let Some(exceptions) = document.get("exceptions") {
+            let exceptions = exceptions
+                .as_array_of_tables()
+                .ok_or("exceptions must be an array of tables, [[exceptions]]")?;
+            for exception in exceptions.iter() {
+                let pattern = |key: &str| -> Result<Option<glob::Pattern>, String> {
+                    let Some(value) = exception.get(key) else {
+                        return Ok(None);
+                    };
+                    let value = value
+                        .as_str()
+                        .ok_or_else(|| format!("exceptions.{} must be a string", key))?;
+                    glob::Pattern::new(value)
+                        .map(Some)
+                        .map_err(|err| format!("invalid pattern {}: {}", value, err))
+                };
+                policy.exceptions.push(Exception {
+                    file: pattern("file")?,
+                    symbol: pattern("symbol")?,
+                    version: pattern("version")?,
+                    soname: pattern("soname")?,
+                    rpath: pattern("rpath")?,
+                });
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Returns the newest version allowed in the namespace of `version`
+    /// (e.g. `GLIBC_2.34`) when `version` is newer than that.
+    pub fn exceeded_max(&self, version: &str) -> Option<&str> {
+        let (namespace, version) = version.rsplit_once('_')?;
+        let max = self.max.get(namespace)?;
+        (distro::version_key(version) > distro::version_key(max)).then_some(max.as_str())
+    }
+
+    pub fn allows_library(&self, soname: &str) -> bool {
+        is_allowed(&self.libraries, soname)
+    }
+
+    pub fn allows_rpath(&self, rpath: &str) -> bool {
+        is_allowed(&self.rpaths, rpath)
+    }
+
+    /// Whether an exception covers the violation about `subject`.
+    pub fn is_excepted(&self, subject: &Subject) -> bool {
+        self.exceptions
+            .iter()
+            .any(|exception| exception.matches(subject))
+    }
+}
+
+fn is_allowed(patterns: &Option<Vec<glob::Pattern>>, value: &str) -> bool {
+    patterns
+        .as_ref()
+        .is_none_or(|patterns| patterns.iter().any(|pattern| pattern.matches(value)))
+}
+
+/// Reads the `allowed` patterns of the table `name`, if the file has it.
+fn allowed(document: &DocumentMut, name: &str) -> Result<Option<Vec<glob::Pattern>>, String> {
+    let Some(table) = document.get(name) else {
+        return Ok(None);
+    };
+    let allowed = table
+        .as_table_like()
+        .and_then(|table| table.get("allowed"))
+        .and_then(Item::as_array)
+        .ok_or_else(|| format!("{}.allowed must be an array of strings", name))?;
+    allowed
+        .iter()
+        .map(|value| {
+            let value = value
+                .as_str()
+                .ok_or_else(|| format!("{}.allowed must be an array of strings", name))?;
+            glob::Pattern::new(value).map_err(|err| format!("invalid pattern {}: {}", value, err))
+        })
+        .collect::<Result<Vec<glob::Pattern>, String>>()
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> Result<PolicyFile, Box<dyn Error>> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.toml");
+        std::fs::write(&path, text).unwrap();
+        PolicyFile::read(&path)
+    }
+
+    #[test]
+    fn reads_the_rules_of_a_policy_file() {
+        let policy = read(
+            r#"
+            [max]
+            GLIBC = "2.17"
+            GLIBCXX = "3.4.19"
+
+            [libraries]
+            allowed = ["libc.so.6", "ld-linux-*.so.*"]
+
+            [rpaths]
+            allowed = ["$ORIGIN", "$ORIGIN/../lib"]
+
+            [[exceptions]]
+            file = "*/libfoo.so.1"
+            symbol = "memfd_create"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(policy.exceeded_max("GLIBC_2.34"), Some("2.17"));
+        assert_eq!(policy.exceeded_max("GLIBC_2.17"), None);
+        assert_eq!(policy.exceeded_max("GLIBCXX_3.4.30"), Some("3.4.19"));
+        assert_eq!(policy.exceeded_max("CXXABI_1.3.13"), None);
+        assert!(policy.allows_library("libc.so.6"));
+        assert!(policy.allows_library("ld-linux-x86-64.so.2"));
+        assert!(!policy.allows_library("libssl.so.3"));
+        assert!(policy.allows_rpath("$ORIGIN/../lib"));
+        assert!(!policy.allows_rpath("/opt/lib"));
+
+        let subject = Subject {
+            file: "build/lib/libfoo.so.1",
+            symbol: Some("memfd_create"),
+            version: Some("GLIBC_2.27"),
+            ..Subject::default()
+        };
+        assert!(policy.is_excepted(&subject));
+        // every pattern of the exception has to match
+        let other_symbol = Subject {
+            symbol: Some("getrandom"),
+            ..subject
+        };
+        assert!(!policy.is_excepted(&other_symbol));
+        let no_symbol = Subject {
+            file: "build/lib/libfoo.so.1",
+            soname: Some("libssl.so.3"),
+            ..Subject::default()
+        };
+        assert!(!policy.is_excepted(&no_symbol));
+    }
+
+    #[test]
+    fn allows_anything_without_the_tables() {
+        let policy = read("").unwrap();
+        assert_eq!(policy.exceeded_max("GLIBC_2.41"), None);
+        assert!(policy.allows_library("libssl.so.3"));
+        assert!(policy.allows_rpath("/opt/lib"));
+        assert!(!policy.is_excepted(&Subject::default()));
+    }
+
+    #[test]
+    fn rejects_malformed_policy_files() {
+        let error = |text: &str| read(text).unwrap_err().to_string();
+        assert_eq!(error("max = 1"), "[max] must be a table");
+        assert_eq!(error("[max]\nGLIBC = 2.17"), "max.GLIBC must be a string");
+        assert_eq!(
+            error("[libraries]\nallowed = \"libc.so.6\""),
+            "libraries.allowed must be an array of strings"
+        );
+        assert_eq!(
+            error("[rpaths]\nallowed = [1]"),
+            "rpaths.allowed must be an array of strings"
+        );
+        assert!(error("[libraries]\nallowed = [\"lib[\"]").starts_with("invalid pattern lib["));
+        assert_eq!(
+            error("[exceptions]\nfile = \"*\""),
+            "exceptions must be an array of tables, [[exceptions]]"
+        );
+        assert!(error("[max").starts_with("cannot parse"));
+    }
+}
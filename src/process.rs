@@ -0,0 +1,46 @@
+use crate::inputs;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Returns the ELF objects the process `pid` has loaded: its executable,
+/// then every other ELF file mapped into its memory in address order, which
+/// includes the libraries it loaded with dlopen. The paths are those seen in
+/// the mount namespace of the process.
+pub fn loaded_objects(pid: u32) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let proc = PathBuf::from(format!("/proc/{}", pid));
+    let exe = std::fs::read_link(proc.join("exe"))
+        .map_err(|err| format!("cannot read the executable of process {}: {}", pid, err))?;
+    let maps = std::fs::read_to_string(proc.join("maps"))
+        .map_err(|err| format!("cannot read the mappings of process {}: {}", pid, err))?;
+    let mut objects = vec![exe];
+    let mut seen = objects.iter().cloned().collect::<HashSet<PathBuf>>();
+    for line in maps.lines() {
+        let Some(path) = mapped_path(line) else {
+            continue;
+        };
+        if let Some(path) = path.strip_suffix(" (deleted)") {
+            eprintln!("deleted since loaded => {}", path);
+            continue;
+        }
+        let path = PathBuf::from(path);
+        if seen.contains(&path) || !inputs::is_elf_file(&path) {
+            continue;
+        }
+        seen.insert(path.clone());
+        objects.push(path);
+    }
+    Ok(objects)
+}
+
+/// Returns the path of the file mapped by a line of `/proc/<pid>/maps`,
+/// `address perms offset dev inode path`, if it maps one (not the heap, the
+/// stack, the vDSO or anonymous memory).
+fn mapped_path(line: &str) -> Option<&str> {
+    let mut rest = line;
+    for _ in 0..5 {
+        rest = rest.trim_start().split_once(char::is_whitespace)?.1;
+    }
+    let path = rest.trim_start();
+    path.starts_with('/').then_some(path)
+}
@@ -0,0 +1,114 @@
+use serde_json::Value;
+
+/// The protobuf definition of `--stdout proto`, which `lddcheck schema
+/// --proto` prints.
+pub const DEFINITION: &str = include_str!("../proto/lddcheck.proto");
+
+/// Returns the `Report` message of the `--envelope` JSON document
+/// `envelope`, prefixed by its length.
+pub fn report(envelope: &Value) -> Vec<u8> {
+    let mut report = Message::default();
+    report.uint64(1, envelope["schema_version"].as_u64().unwrap_or_default());
+    report.string(2, str_of(&envelope["tool_version"]));
+    for input in array_of(&envelope["inputs"]) {
+        let mut message = Message::default();
+        message.string(1, str_of(&input["path"]));
+        if let Some(glibc) = input["glibc"].as_str() {
+            message.optional_string(2, glibc);
+        }
+        for file in array_of(&input["files"]) {
+            message.optional_string(3, str_of(file));
+        }
+        for library in array_of(&input["libraries"]) {
+            message.optional_string(4, str_of(library));
+        }
+        report.message(3, &message);
+    }
+    for requirement in array_of(&envelope["findings"]["requirements"]) {
+        let mut message = Message::default();
+        message.string(1, str_of(&requirement["namespace"]));
+        message.string(2, str_of(&requirement["version"]));
+        message.string(3, str_of(&requirement["function"]));
+        message.uint64(
+            4,
+            u64::from(requirement["weak"].as_bool().unwrap_or_default()),
+        );
+        for file in array_of(&requirement["files"]) {
+            message.optional_string(5, str_of(file));
+        }
+        report.message(4, &message);
+    }
+    for error in array_of(&envelope["errors"]) {
+        let mut message = Message::default();
+        message.string(1, str_of(&error["path"]));
+        message.string(2, str_of(&error["kind"]));
+        message.string(3, str_of(&error["referenced_by"]));
+        report.message(5, &message);
+    }
+    let sections = &envelope["findings"]["sections"];
+    if sections
+        .as_object()
+        .is_some_and(|sections| !sections.is_empty())
+    {
+        report.string(6, &sections.to_string());
+    }
+    let mut delimited = Vec::new();
+    varint(&mut delimited, report.bytes.len() as u64);
+    delimited.extend(report.bytes);
+    delimited
+}
+
+fn str_of(value: &Value) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+fn array_of(value: &Value) -> &[Value] {
+    value.as_array().map_or(&[], Vec::as_slice)
+}
+
+/// The encoding of a message, its fields in the order they are added.
+#[derive(Default)]
+struct Message {
+    bytes: Vec<u8>,
+}
+
+impl Message {
+    /// A varint field, left out when 0 as proto3 does.
+    fn uint64(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            varint(&mut self.bytes, u64::from(field << 3));
+            varint(&mut self.bytes, value);
+        }
+    }
+
+    /// A string field, left out when empty as proto3 does.
+    fn string(&mut self, field: u32, value: &str) {
+        if !value.is_empty() {
+            self.optional_string(field, value);
+        }
+    }
+
+    /// A string field written even when empty, for `optional` and `repeated`
+    /// fields.
+    fn optional_string(&mut self, field: u32, value: &str) {
+        self.length_delimited(field, value.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, message: &Message) {
+        self.length_delimited(field, &message.bytes);
+    }
+
+    fn length_delimited(&mut self, field: u32, bytes: &[u8]) {
+        varint(&mut self.bytes, u64::from(field << 3 | 2));
+        varint(&mut self.bytes, bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+    }
+}
+
+fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
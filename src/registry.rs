@@ -0,0 +1,370 @@
+use crate::image::{self, OciIndex, OciManifest};
+use crate::inputs;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The registry images without a registry host in their name are pulled
+/// from.
+const DOCKER_HUB: &str = "registry-1.docker.io";
+
+/// The key Docker Hub credentials are stored under in the docker config.
+const DOCKER_HUB_AUTH_KEY: &str = "https://index.docker.io/v1/";
+
+/// The manifest formats asked for, image indexes included.
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+/// An image reference such as `ghcr.io/org/app:tag`, `ubuntu:22.04` or
+/// `localhost:5000/app@sha256:...`.
+#[derive(Debug)]
+struct Reference {
+    registry: String,
+    repository: String,
+    /// The tag or digest.
+    reference: String,
+}
+
+impl Reference {
+    fn parse(name: &str) -> Result<Reference, Box<dyn Error>> {
+        let (registry, rest) = match name.split_once('/') {
+            Some((host, rest)) if host.contains(['.', ':']) || host == "localhost" => {
+                (host.to_string(), rest)
+            }
+            _ => (DOCKER_HUB.to_string(), name),
+        };
+        let registry = match registry.as_str() {
+            "docker.io" | "index.docker.io" => DOCKER_HUB.to_string(),
+            _ => registry,
+        };
+        let (repository, reference) = match rest.split_once('@') {
+            Some((repository, digest)) => (repository, digest),
+            None => match rest.rsplit_once(':') {
+                Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+                _ => (rest, "latest"),
+            },
+        };
+        if repository.is_empty() || reference.is_empty() {
+            return Err(format!("invalid image reference {}", name).into());
+        }
+        // official images live in the library namespace of Docker Hub
+        let repository = if registry == DOCKER_HUB && !repository.contains('/') {
+            format!("library/{}", repository)
+        } else {
+            repository.to_string()
+        };
+        Ok(Reference {
+            registry,
+            repository,
+            reference: reference.to_string(),
+        })
+    }
+}
+
+/// A client of the registry API for one repository, handling the token
+/// authentication registries ask for, anonymous or with credentials.
+struct Client {
+    base: String,
+    credentials: Option<(String, String)>,
+    authorization: Option<String>,
+}
+
+impl Client {
+    fn new(reference: &Reference) -> Client {
+        // like docker, only a registry on the local machine is spoken to
+        // over plain HTTP
+        let scheme = if reference.registry.starts_with("localhost")
+            || reference.registry.starts_with("127.0.0.1")
+        {
+            "http"
+        } else {
+            "https"
+        };
+        Client {
+            base: format!(
+                "{}://{}/v2/{}",
+                scheme, reference.registry, reference.repository
+            ),
+            credentials: credentials(&reference.registry),
+            authorization: None,
+        }
+    }
+
+    /// Sends a GET request for `path` in the repository, authenticating
+    /// once if the registry asks for it.
+    fn get(&mut self, path: &str, accept: &str) -> Result<ureq::Response, Box<dyn Error>> {
+        let url = format!("{}/{}", self.base, path);
+        let request = |authorization: &Option<String>| {
+            let request = ureq::get(&url).set("Accept", accept);
+            match authorization {
+                Some(authorization) => request.set("Authorization", authorization),
+                None => request,
+            }
+        };
+        match request(&self.authorization).call() {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(401, response)) if self.authorization.is_none() => {
+                let challenge = response.header("WWW-Authenticate").unwrap_or_default();
+                self.authorization = Some(self.authenticate(challenge)?);
+                Ok(request(&self.authorization).call()?)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Answers the `WWW-Authenticate` challenge of the registry, returning
+    /// the `Authorization` header to send from now on.
+    fn authenticate(&self, challenge: &str) -> Result<String, Box<dyn Error>> {
+        let basic = self.credentials.as_ref().map(|(username, password)| {
+            let encoded = base64::engine::general_purpose::STANDARD
+                .encode(format!("{}:{}", username, password));
+            format!("Basic {}", encoded)
+        });
+        let Some(parameters) = challenge.strip_prefix("Bearer ") else {
+            return basic.ok_or_else(|| "the registry requires credentials".into());
+        };
+        let parameters = parse_challenge(parameters);
+        let parameter = |key: &str| {
+            parameters
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value.as_str())
+        };
+        let Some(realm) = parameter("realm") else {
+            return Err("no realm in the registry's authentication challenge".into());
+        };
+        let mut request = ureq::get(realm);
+        for key in ["service", "scope"] {
+            if let Some(value) = parameter(key) {
+                request = request.query(key, value);
+            }
+        }
+        if let Some(basic) = &basic {
+            request = request.set("Authorization", basic);
+        }
+        let response: serde_json::Value = serde_json::from_reader(request.call()?.into_reader())?;
+        let token = response
+            .get("token")
+            .or_else(|| response.get("access_token"))
+            .and_then(|token| token.as_str())
+            .ok_or("no token in the registry's response")?;
+        Ok(format!("Bearer {}", token))
+    }
+
+    /// Downloads the blob with the given digest into `dir`, checking its
+    /// contents against the digest.
+    fn download_blob(&mut self, digest: &str, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let Some(hash) = digest.strip_prefix("sha256:") else {
+            return Err(format!("unsupported digest {}", digest).into());
+        };
+        if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid digest {}", digest).into());
+        }
+        let path = dir.join(hash);
+        let mut reader = self
+            .get(&format!("blobs/{}", digest), "application/octet-stream")?
+            .into_reader();
+        let mut file = std::fs::File::create(&path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read])?;
+        }
+        if hex(&hasher.finalize()) != hash {
+            return Err(format!("the contents of blob {} do not match its digest", digest).into());
+        }
+        Ok(path)
+    }
+}
+
+/// Pulls the image `name` from its registry, applying its layers to a new
+/// directory in `workspace`.
+pub fn pull(name: &str, workspace: &mut inputs::Workspace) -> Result<image::Image, Box<dyn Error>> {
+    let reference = Reference::parse(name)?;
+    let mut client = Client::new(&reference);
+    let mut manifest_reference = reference.reference.clone();
+    // an index is followed to the manifest of the platform lddcheck runs on,
+    // a nested index once more at most
+    let mut manifest = None;
+    for _ in 0..3 {
+        let mut data = Vec::new();
+        client
+            .get(&format!("manifests/{}", manifest_reference), MANIFEST_TYPES)?
+            .into_reader()
+            .read_to_end(&mut data)?;
+        let value: serde_json::Value = serde_json::from_slice(&data)?;
+        if value.get("manifests").is_none() {
+            manifest = Some(serde_json::from_value::<OciManifest>(value)?);
+            break;
+        }
+        let index: OciIndex = serde_json::from_value(value)?;
+        let Some(descriptor) = index.select() else {
+            return Err(format!("no manifest in the index of {}", name).into());
+        };
+        manifest_reference = descriptor.digest.clone();
+    }
+    let Some(manifest) = manifest else {
+        return Err(format!("no image manifest found for {}", name).into());
+    };
+    let blobs = workspace.new_dir(Path::new(name))?;
+    let config = client.download_blob(&manifest.config.digest, &blobs)?;
+    let layers = manifest
+        .layers
+        .iter()
+        .map(|layer| client.download_blob(&layer.digest, &blobs))
+        .collect::<Result<Vec<PathBuf>, Box<dyn Error>>>()?;
+    image::build(Path::new(name), &config, &layers, workspace)
+}
+
+/// Returns the credentials for `registry`, from `LDDCHECK_REGISTRY_USERNAME`
+/// and `LDDCHECK_REGISTRY_PASSWORD` or else from the docker config written
+/// by `docker login` (credential helpers are not supported).
+fn credentials(registry: &str) -> Option<(String, String)> {
+    if let (Ok(username), Ok(password)) = (
+        std::env::var("LDDCHECK_REGISTRY_USERNAME"),
+        std::env::var("LDDCHECK_REGISTRY_PASSWORD"),
+    ) {
+        return Some((username, password));
+    }
+    let config = match std::env::var("DOCKER_CONFIG") {
+        Ok(dir) => PathBuf::from(dir).join("config.json"),
+        Err(_) => PathBuf::from(std::env::var("HOME").ok()?).join(".docker/config.json"),
+    };
+    let config: serde_json::Value = serde_json::from_slice(&std::fs::read(config).ok()?).ok()?;
+    let key = if registry == DOCKER_HUB {
+        DOCKER_HUB_AUTH_KEY
+    } else {
+        registry
+    };
+    let auth = config.get("auths")?.get(key)?.get("auth")?.as_str()?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(auth)
+        .ok()?;
+    let (username, password) = String::from_utf8(decoded)
+        .ok()?
+        .split_once(':')
+        .map(|(username, password)| (username.to_string(), password.to_string()))?;
+    Some((username, password))
+}
+
+/// Splits the parameters of a `WWW-Authenticate` challenge,
+/// `key="value",key="value"`, where values may contain commas.
+fn parse_challenge(parameters: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = parameters.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, after)) => (value, after),
+                None => (quoted, ""),
+            },
+            None => after.split_once(',').unwrap_or((after, "")),
+        };
+        parsed.push((key, value.to_string()));
+        rest = after.trim_start_matches(',').trim();
+    }
+    parsed
+}
+
+/// Formats bytes as lowercase hex.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+
+    /// A layer tarball with the symlinks (path, target) and empty files.
+    fn layer(symlinks: &[(&str, &str)], files: &[&str]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, target) in symlinks {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, path, target).unwrap();
+        }
+        for path in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(0);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, &[][..]).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    /// Serves the `blobs` of the repository `app` and a manifest tagged
+    /// `latest` made of them, the first being the configuration, on a local
+    /// port it returns.
+    fn serve(blobs: Vec<Vec<u8>>) -> u16 {
+        let digest = |blob: &[u8]| format!("sha256:{}", hex(&Sha256::digest(blob)));
+        let descriptor = |blob: &Vec<u8>| serde_json::json!({ "digest": digest(blob) });
+        let manifest = serde_json::json!({
+            "config": descriptor(&blobs[0]),
+            "layers": blobs[1..].iter().map(descriptor).collect::<Vec<_>>(),
+        });
+        let mut paths = blobs
+            .iter()
+            .map(|blob| (format!("/v2/app/blobs/{}", digest(blob)), blob.clone()))
+            .collect::<HashMap<String, Vec<u8>>>();
+        paths.insert(
+            "/v2/app/manifests/latest".to_string(),
+            manifest.to_string().into_bytes(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = String::new();
+                std::io::BufReader::new(&stream)
+                    .read_line(&mut request)
+                    .unwrap();
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let body = paths.get(path).cloned().unwrap_or_default();
+                let status = if paths.contains_key(path) {
+                    "200 OK"
+                } else {
+                    "404 Not Found"
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn keeps_pulled_layers_inside_the_image() {
+        let victim = tempfile::tempdir().unwrap();
+        std::fs::write(victim.path().join("secret"), "").unwrap();
+        let escape = format!("{}{}", "../".repeat(16), victim.path().display());
+        let port = serve(vec![
+            br#"{"config": {"Cmd": ["/bin/sh"]}}"#.to_vec(),
+            layer(&[("evil", &escape)], &["bin/sh"]),
+            layer(&[], &["evil/.wh.secret"]),
+        ]);
+
+        let mut workspace = inputs::Workspace::default();
+        let image = pull(&format!("localhost:{}/app", port), &mut workspace).unwrap();
+        assert!(victim.path().join("secret").exists());
+        assert_eq!(image.entrypoint, [image.root.join("bin/sh")]);
+    }
+}
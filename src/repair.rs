@@ -0,0 +1,228 @@
+use crate::policy::Policy;
+use arwen::elf::ElfContainer;
+use elf::abi::{DT_NEEDED, EI_DATA, ELFDATA2LSB, SHT_GNU_VERNEED};
+use elf::endian::{AnyEndian, EndianParse};
+use elf::ElfBytes;
+use lddtree::DependencyAnalyzer;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Copies the libraries `policy` does not allow next to the file at `path`,
+/// renamed with a hash of their contents so they cannot clash with the
+/// system's, and rewrites the `DT_NEEDED` entries and runpaths of the file
+/// and the copies to load them from there. Returns the sonames vendored and
+/// their copies.
+///
+/// Libraries already next to the file count as bundled, so repairing a file
+/// twice does not vendor anything the second time.
+pub fn repair(
+    path: &Path,
+    policy: &Policy,
+    analyzer: DependencyAnalyzer,
+) -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+    let dir = std::fs::canonicalize(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let deps = analyzer.analyze(path)?;
+    let check = policy.check_libraries(&deps, |lib| {
+        std::fs::canonicalize(lib).is_ok_and(|lib| lib.parent() == Some(dir.as_path()))
+    });
+
+    let mut renamed = HashMap::new();
+    let mut vendored = Vec::new();
+    for (soname, realpath) in check.disallowed {
+        let Some(realpath) = realpath else {
+            return Err(format!("cannot vendor {}, it was not found", soname).into());
+        };
+        let data = std::fs::read(&realpath)?;
+        let name = hashed_name(&soname, &data);
+        let copy = dir.join(&name);
+        std::fs::write(&copy, &data)?;
+        renamed.insert(soname.clone(), name);
+        vendored.push((soname, copy));
+    }
+    if vendored.is_empty() {
+        return Ok(vendored);
+    }
+
+    patch(path, &renamed, None)?;
+    for (soname, copy) in &vendored {
+        patch(copy, &renamed, Some(&renamed[soname]))?;
+    }
+    Ok(vendored)
+}
+
+/// Points the `DT_NEEDED` entries of the ELF file at `path` at the renamed
+/// libraries and makes it search `$ORIGIN`, optionally setting its soname.
+fn patch(
+    path: &Path,
+    renamed: &HashMap<String, String>,
+    soname: Option<&String>,
+) -> Result<(), Box<dyn Error>> {
+    let data = std::fs::read(path)?;
+    let mut elf = ElfContainer::parse(&data)?;
+    let needed = elf
+        .inner
+        .elf_needed()
+        .map(|name| String::from_utf8_lossy(name).to_string())
+        .filter_map(|name| Some((name.clone(), renamed.get(&name)?.clone())))
+        .collect::<HashMap<String, String>>();
+    if !needed.is_empty() {
+        elf.replace_needed(&needed)?;
+    }
+    if let Some(soname) = soname {
+        elf.set_soname(soname)?;
+        elf.set_runpath("$ORIGIN")?;
+    } else if !elf.get_rpath().iter().any(|entry| entry == "$ORIGIN") {
+        elf.add_runpath("$ORIGIN")?;
+    }
+    elf.write_to_path(path)?;
+    if !needed.is_empty() {
+        rename_version_needs(path, &needed)?;
+    }
+    Ok(())
+}
+
+/// Points the `vn_file` of the version needs of the ELF file at `path` that
+/// name a renamed library at its new name, the string of the `DT_NEEDED`
+/// entry. ld.so looks the versions up by that name and aborts on a library
+/// it has not loaded.
+fn rename_version_needs(
+    path: &Path,
+    renamed: &HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut data = std::fs::read(path)?;
+    let mut patches = Vec::new();
+    {
+        let file = ElfBytes::<AnyEndian>::minimal_parse(&data)?;
+        let Some(shdrs) = file.section_headers() else {
+            return Ok(());
+        };
+        let Some(shdr) = shdrs.iter().find(|shdr| shdr.sh_type == SHT_GNU_VERNEED) else {
+            return Ok(());
+        };
+        let strtab = shdrs.get(shdr.sh_link as usize)?;
+        let strtab = file.section_data_as_strtab(&strtab)?;
+        let new_names = file
+            .dynamic()?
+            .map(|dynamic| {
+                dynamic
+                    .iter()
+                    .filter(|entry| entry.d_tag == DT_NEEDED)
+                    .map(|entry| entry.d_val())
+                    .filter_map(|index| Some((strtab.get(index as usize).ok()?, index)))
+                    .collect::<HashMap<&str, u64>>()
+            })
+            .unwrap_or_default();
+        let read_u32 = |offset: usize| -> Result<u32, Box<dyn Error>> {
+            let bytes = data
+                .get(offset..offset + 4)
+                .ok_or("truncated version needs")?;
+            Ok(file.ehdr.endianness.parse_u32_at(&mut 0, bytes)?)
+        };
+        // Elf32_Verneed and Elf64_Verneed are laid out alike: vn_version,
+        // vn_cnt, vn_file, vn_aux, vn_next
+        let mut offset = shdr.sh_offset as usize;
+        for _ in 0..shdr.sh_info {
+            let vn_file = read_u32(offset + 4)?;
+            let name = strtab.get(vn_file as usize)?;
+            if let Some(new_name) = renamed.get(name) {
+                let index = new_names
+                    .get(new_name.as_str())
+                    .ok_or_else(|| format!("{} is not needed by {}", new_name, path.display()))?;
+                patches.push((offset + 4, *index as u32));
+            }
+            match read_u32(offset + 12)? {
+                0 => break,
+                next => offset += next as usize,
+            }
+        }
+    }
+    if patches.is_empty() {
+        return Ok(());
+    }
+    let little_endian = data[EI_DATA] == ELFDATA2LSB;
+    for (offset, index) in patches {
+        let bytes = if little_endian {
+            index.to_le_bytes()
+        } else {
+            index.to_be_bytes()
+        };
+        data[offset..offset + 4].copy_from_slice(&bytes);
+    }
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+/// Inserts a hash of the library's contents into its name, e.g.
+/// `libfoo.so.1` becomes `libfoo-1a2b3c4d.so.1`.
+fn hashed_name(soname: &str, data: &[u8]) -> String {
+    let hash = format!("{:08x}", crc32fast::hash(data));
+    match soname.find(".so") {
+        Some(index) => format!("{}-{}{}", &soname[..index], hash, &soname[index..]),
+        None => format!("{}-{}", soname, hash),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Compiles the C `source` into `output` with the extra `args`.
+    fn cc(dir: &Path, source: &str, output: &Path, args: &[&str]) {
+        let file = dir.join("source.c");
+        std::fs::write(&file, source).unwrap();
+        let status = Command::new("cc")
+            .arg(&file)
+            .arg("-o")
+            .arg(output)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn repaired_binaries_load_the_versions_of_the_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path().join("lib");
+        let app = dir.path().join("app");
+        std::fs::create_dir_all(&lib).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        let script = dir.path().join("foo.map");
+        std::fs::write(&script, "FOO_1.0 { global: foo; local: *; };").unwrap();
+        let libfoo = lib.join("libfoo.so.1");
+        let version_script = format!("-Wl,--version-script={}", script.display());
+        cc(
+            dir.path(),
+            "int foo(void) { return 42; }",
+            &libfoo,
+            &[
+                "-shared",
+                "-fPIC",
+                "-Wl,-soname,libfoo.so.1",
+                &version_script,
+            ],
+        );
+        let program = app.join("program");
+        cc(
+            dir.path(),
+            "int foo(void); int main(void) { return foo() == 42 ? 0 : 1; }",
+            &program,
+            &[libfoo.to_str().unwrap()],
+        );
+
+        let policy = crate::policy::find_policy("manylinux_2_17").unwrap();
+        let analyzer = DependencyAnalyzer::new(PathBuf::from("/")).library_paths(vec![lib]);
+        let vendored = repair(&program, policy, analyzer).unwrap();
+        assert_eq!(vendored.len(), 1);
+        assert_eq!(vendored[0].0, "libfoo.so.1");
+
+        // ld.so checks FOO_1.0 against the library of the version need
+        let status = Command::new(&program).status().unwrap();
+        assert_eq!(status.code(), Some(0));
+    }
+}
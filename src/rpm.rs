@@ -0,0 +1,103 @@
+use crate::{archive, compression};
+use std::error::Error;
+use std::path::Path;
+
+/// Magic bytes at the start of an RPM package (its lead).
+const RPM_MAGIC: &[u8] = b"\xed\xab\xee\xdb";
+
+/// Magic bytes at the start of an RPM header structure.
+const HEADER_MAGIC: &[u8] = b"\x8e\xad\xe8\x01";
+
+/// The size of the lead, which precedes the signature header.
+const LEAD_SIZE: usize = 96;
+
+/// The size of a `newc` cpio entry header.
+const CPIO_HEADER_SIZE: usize = 110;
+
+/// Whether the given bytes look like an RPM package.
+pub fn is_rpm(data: &[u8]) -> bool {
+    data.starts_with(RPM_MAGIC)
+}
+
+/// Unpacks the payload of an RPM package into `dir`: the cpio archive that
+/// follows the signature and main headers, compressed with gzip, xz or zstd.
+/// Regular files and symlinks are created, anything else is skipped.
+pub fn unpack(data: &[u8], dir: &Path) -> Result<(), Box<dyn Error>> {
+    if !is_rpm(data) {
+        return Err("not an RPM package".into());
+    }
+    let signature_end = header_end(data, LEAD_SIZE)?;
+    // the signature header is padded to a multiple of 8 bytes
+    let payload_start = header_end(data, signature_end.next_multiple_of(8))?;
+    let payload = compression::decompress(&data[payload_start..])?;
+    unpack_cpio(&payload, dir)
+}
+
+/// Returns the offset right after the header structure at `offset`.
+fn header_end(data: &[u8], offset: usize) -> Result<usize, Box<dyn Error>> {
+    let header = data
+        .get(offset..offset + 16)
+        .ok_or("truncated RPM header")?;
+    if !header.starts_with(HEADER_MAGIC) {
+        return Err("bad RPM header magic".into());
+    }
+    let entries = u32::from_be_bytes(header[8..12].try_into()?) as usize;
+    let size = u32::from_be_bytes(header[12..16].try_into()?) as usize;
+    Ok(offset + 16 + entries * 16 + size)
+}
+
+/// Unpacks a `newc` (SVR4) cpio archive, the format of RPM payloads.
+fn unpack_cpio(data: &[u8], dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut offset = 0;
+    loop {
+        let header = data
+            .get(offset..offset + CPIO_HEADER_SIZE)
+            .ok_or("truncated cpio archive")?;
+        if !header.starts_with(b"070701") && !header.starts_with(b"070702") {
+            return Err("unsupported RPM payload, expected a newc cpio archive".into());
+        }
+        let field = |index: usize| -> Result<usize, Box<dyn Error>> {
+            let start = 6 + index * 8;
+            let hex = std::str::from_utf8(&header[start..start + 8])?;
+            Ok(usize::from_str_radix(hex, 16)?)
+        };
+        let mode = field(1)?;
+        let file_size = field(6)?;
+        let name_size = field(11)?;
+
+        let name_start = offset + CPIO_HEADER_SIZE;
+        let name = data
+            .get(name_start..name_start + name_size.saturating_sub(1))
+            .ok_or("truncated cpio archive")?;
+        let name = String::from_utf8_lossy(name).to_string();
+        let data_start = (name_start + name_size).next_multiple_of(4);
+        let contents = data
+            .get(data_start..data_start + file_size)
+            .ok_or("truncated cpio archive")?;
+        offset = (data_start + file_size).next_multiple_of(4);
+
+        if name == "TRAILER!!!" {
+            return Ok(());
+        }
+        let Some(target) = archive::safe_join(dir, &name) else {
+            continue;
+        };
+        match mode & 0o170000 {
+            0o100000 => {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&target, contents)?;
+            }
+            #[cfg(unix)]
+            0o120000 => {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let link = String::from_utf8_lossy(contents).to_string();
+                std::os::unix::fs::symlink(link, &target)?;
+            }
+            _ => {}
+        }
+    }
+}
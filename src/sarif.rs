@@ -0,0 +1,61 @@
+use crate::violations::{Violation, RULES};
+use std::path::Path;
+
+/// The SARIF schema the report follows.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Returns a SARIF 2.1.0 log with a result for every violation, located at
+/// the file at fault, for code scanning dashboards such as GitHub's.
+pub fn report(violations: &[Violation]) -> serde_json::Value {
+    let rules = RULES
+        .iter()
+        .map(|(id, description)| {
+            serde_json::json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    let results = violations
+        .iter()
+        .map(|violation| {
+            let mut result = serde_json::json!({
+                "ruleId": violation.rule,
+                "level": "error",
+                "message": { "text": violation.message },
+            });
+            if let Some(file) = &violation.file {
+                result["locations"] = serde_json::json!([{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri(file) },
+                    },
+                }]);
+            }
+            result
+        })
+        .collect::<Vec<serde_json::Value>>();
+    serde_json::json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// The URI of a file, relative paths (inputs found in a directory, archive
+/// members) stay relative to the checkout.
+fn uri(file: &Path) -> String {
+    if file.is_absolute() {
+        format!("file://{}", file.display())
+    } else {
+        file.display().to_string()
+    }
+}
@@ -0,0 +1,197 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// A file of a software bill of materials: an input or a shared library
+/// loaded by one.
+#[derive(Debug)]
+pub struct Component {
+    pub path: String,
+    /// `DT_SONAME`, for the libraries that have one.
+    pub soname: Option<String>,
+    pub build_id: Option<String>,
+    /// The SHA-256 of the contents, in hex.
+    pub sha256: Option<String>,
+    /// The newest glibc version the file itself requires, e.g. `GLIBC_2.34`.
+    pub glibc: Option<String>,
+    /// Whether the file was given as an input rather than loaded by one.
+    pub is_input: bool,
+}
+
+impl Component {
+    /// The soname, or the file name for the files without one.
+    fn name(&self) -> &str {
+        self.soname
+            .as_deref()
+            .unwrap_or_else(|| self.path.rsplit('/').next().unwrap_or(&self.path))
+    }
+}
+
+/// Returns an SPDX 2.3 document with a package for every component, the
+/// inputs described by the document and depending on the libraries of their
+/// dependency closure (`dependencies`, input path => library paths).
+pub fn spdx(components: &[Component], dependencies: &[(String, Vec<String>)]) -> Value {
+    let id = |path: &str| {
+        let index = components
+            .iter()
+            .position(|component| component.path == path)?;
+        Some(format!("SPDXRef-Package-{}", index + 1))
+    };
+    let packages = components
+        .iter()
+        .enumerate()
+        .map(|(index, component)| {
+            let mut package = serde_json::json!({
+                "SPDXID": format!("SPDXRef-Package-{}", index + 1),
+                "name": component.name(),
+                "packageFileName": component.path,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "primaryPackagePurpose": if component.is_input { "APPLICATION" } else { "LIBRARY" },
+            });
+            if let Some(sha256) = &component.sha256 {
+                package["checksums"] = serde_json::json!([{
+                    "algorithm": "SHA256",
+                    "checksumValue": sha256,
+                }]);
+            }
+            if let Some(build_id) = &component.build_id {
+                package["comment"] = format!("GNU build-id {}", build_id).into();
+            }
+            package
+        })
+        .collect::<Vec<Value>>();
+    let mut relationships = Vec::new();
+    for component in components.iter().filter(|component| component.is_input) {
+        relationships.push(relationship(
+            "SPDXRef-DOCUMENT",
+            "DESCRIBES",
+            id(&component.path),
+        ));
+    }
+    for (input, libraries) in dependencies {
+        for library in libraries {
+            if let (Some(input), Some(library)) = (id(input), id(library)) {
+                relationships.push(relationship(&input, "DEPENDS_ON", Some(library)));
+            }
+        }
+    }
+    // the namespace has to be unique to the document, its contents make it so
+    let digest = Sha256::digest(Value::Array(packages.clone()).to_string());
+    let namespace = digest
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    serde_json::json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": "lddcheck",
+        "documentNamespace": format!("https://spdx.org/spdxdocs/lddcheck-{}", namespace),
+        "creationInfo": {
+            "created": timestamp(),
+            "creators": [format!("Tool: lddcheck-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// Returns a CycloneDX 1.5 BOM with a component for every file, referenced
+/// by its path, the glibc version it requires as the `lddcheck:glibc`
+/// property, and the inputs depending on the libraries of their dependency
+/// closure (`dependencies`, input path => library paths).
+pub fn cyclonedx(components: &[Component], dependencies: &[(String, Vec<String>)]) -> Value {
+    let components = components
+        .iter()
+        .map(|component| {
+            let mut properties = vec![property("lddcheck:path", &component.path)];
+            if let Some(build_id) = &component.build_id {
+                properties.push(property("lddcheck:build-id", build_id));
+            }
+            if let Some(glibc) = &component.glibc {
+                properties.push(property("lddcheck:glibc", glibc));
+            }
+            let mut value = serde_json::json!({
+                "type": if component.is_input { "application" } else { "library" },
+                "bom-ref": component.path,
+                "name": component.name(),
+                "properties": properties,
+            });
+            if let Some(sha256) = &component.sha256 {
+                value["hashes"] = serde_json::json!([{ "alg": "SHA-256", "content": sha256 }]);
+            }
+            value
+        })
+        .collect::<Vec<Value>>();
+    let dependencies = dependencies
+        .iter()
+        .map(|(input, libraries)| serde_json::json!({ "ref": input, "dependsOn": libraries }))
+        .collect::<Vec<Value>>();
+    serde_json::json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp(),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "lddcheck",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+fn property(name: &str, value: &str) -> Value {
+    serde_json::json!({ "name": name, "value": value })
+}
+
+fn relationship(from: &str, kind: &str, to: Option<String>) -> Value {
+    serde_json::json!({
+        "spdxElementId": from,
+        "relationshipType": kind,
+        "relatedSpdxElement": to,
+    })
+}
+
+/// Returns the current time as `YYYY-MM-DDThh:mm:ssZ`, or the time
+/// `SOURCE_DATE_EPOCH` gives for reproducible documents.
+fn timestamp() -> String {
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        });
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // the civil date of a day count, as in Howard Hinnant's `civil_from_days`
+    let days = days as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
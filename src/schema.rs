@@ -0,0 +1,113 @@
+use serde_json::Value;
+
+/// The version of the `--envelope` JSON document, raised whenever a field
+/// changes in a way older consumers would not read.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Returns the JSON Schema (draft 2020-12) of the `--envelope` JSON
+/// document, which `lddcheck schema` prints.
+pub fn json_schema() -> Value {
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("https://github.com/cocoa-xu/lddcheck/schema/v{}.json", SCHEMA_VERSION),
+        "title": "lddcheck report",
+        "description": "The report of lddcheck --envelope: the versioned symbols the inputs and the libraries they load require.",
+        "type": "object",
+        "required": ["schema_version", "tool_version", "inputs", "findings", "errors"],
+        "properties": {
+            "schema_version": {
+                "description": "The version of this schema.",
+                "const": SCHEMA_VERSION,
+            },
+            "tool_version": {
+                "description": "The version of lddcheck that wrote the report.",
+                "type": "string",
+            },
+            "inputs": {
+                "description": "The inputs, sorted by path.",
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "glibc", "files"],
+                    "properties": {
+                        "path": {
+                            "description": "The input as given, or `archive!member` for a file unpacked from an archive.",
+                            "type": "string",
+                        },
+                        "glibc": {
+                            "description": "The newest glibc version the input and its libraries require, e.g. 2.34.",
+                            "type": ["string", "null"],
+                        },
+                        "files": {
+                            "description": "The files of the dependency closure of the input, itself included.",
+                            "type": "array",
+                            "items": { "type": "string" },
+                        },
+                        "libraries": {
+                            "description": "The sonames of the libraries in the dependency closure, the other inputs left out.",
+                            "type": "array",
+                            "items": { "type": "string" },
+                        },
+                    },
+                },
+            },
+            "findings": {
+                "type": "object",
+                "required": ["requirements", "sections"],
+                "properties": {
+                    "requirements": {
+                        "description": "Every versioned symbol required, whatever the --detail-level.",
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["namespace", "version", "function", "weak", "files"],
+                            "properties": {
+                                "namespace": {
+                                    "description": "GLIBC, GLIBCXX, CXXABI, GCC, ...",
+                                    "type": "string",
+                                },
+                                "version": {
+                                    "description": "The version within the namespace, e.g. 2.34.",
+                                    "type": "string",
+                                },
+                                "function": { "type": "string" },
+                                "weak": {
+                                    "description": "Whether every reference is an undefined weak symbol.",
+                                    "type": "boolean",
+                                },
+                                "files": {
+                                    "description": "The files importing the symbol at that version.",
+                                    "type": "array",
+                                    "items": { "type": "string" },
+                                },
+                            },
+                        },
+                    },
+                    "sections": {
+                        "description": "The results of the other checks asked for, keyed by the name of the section (max_glibc, target_distro, policy, ...), as in the JSON without --envelope.",
+                        "type": "object",
+                    },
+                },
+            },
+            "errors": {
+                "description": "The files that could not be resolved or read, once per input or library they were looked up for.",
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "kind", "referenced_by"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "kind": {
+                            "description": "cannot_read, cannot_parse, cannot_unpack, not_found, wrong_arch, soname_mismatch or bad_interpreter.",
+                            "type": "string",
+                        },
+                        "referenced_by": {
+                            "description": "The input or library the file was looked up for.",
+                            "type": "string",
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
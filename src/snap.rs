@@ -0,0 +1,166 @@
+use crate::inputs;
+use crate::squashfs;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// The glibc version of each base snap, the Ubuntu release it is built from.
+const BASE_GLIBC: [(&str, &str); 5] = [
+    ("core", "2.23"),
+    ("core18", "2.27"),
+    ("core20", "2.31"),
+    ("core22", "2.35"),
+    ("core24", "2.39"),
+];
+
+/// The directories snaps are mounted in, `/snap` or `/var/lib/snapd/snap`
+/// depending on the distribution.
+const SNAP_MOUNT_DIRS: [&str; 2] = ["/snap", "/var/lib/snapd/snap"];
+
+/// A snap unpacked next to the files of its base.
+#[derive(Debug)]
+pub struct Snap {
+    /// The files of the snap, what it sees at `$SNAP`.
+    pub files: PathBuf,
+    /// The files of the base, the root filesystem of the snap.
+    pub base_files: PathBuf,
+    /// The name of the snap from its `meta/snap.yaml`.
+    pub name: String,
+    /// The base from its `meta/snap.yaml`, `core` if none is declared.
+    pub base: String,
+}
+
+impl Snap {
+    /// Returns the glibc version the base of the snap provides, if known.
+    pub fn base_glibc(&self) -> Option<&'static str> {
+        BASE_GLIBC
+            .iter()
+            .find(|(base, _)| *base == self.base)
+            .map(|(_, glibc)| *glibc)
+    }
+}
+
+/// The top-level keys of `meta/snap.yaml` lddcheck needs.
+#[derive(Debug, Default)]
+struct SnapYaml {
+    name: Option<String>,
+    base: Option<String>,
+}
+
+impl SnapYaml {
+    fn read(path: &Path) -> std::io::Result<SnapYaml> {
+        let mut snap_yaml = SnapYaml::default();
+        for line in std::fs::read_to_string(path)?.lines() {
+            // nested keys are indented
+            if line.starts_with([' ', '\t']) {
+                continue;
+            }
+            let value = |value: &str| {
+                value
+                    .trim()
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .to_string()
+            };
+            match line.split_once(':') {
+                Some(("name", name)) => snap_yaml.name = Some(value(name)),
+                Some(("base", base)) => snap_yaml.base = Some(value(base)),
+                _ => {}
+            }
+        }
+        Ok(snap_yaml)
+    }
+}
+
+/// Unpacks the snap at `path` (a `.snap` file, or a directory such as
+/// `prime/` or `/snap/<name>/current`) and finds the files of its base, the
+/// installed one or `base_path`, a directory or a `.snap` file.
+pub fn open(
+    path: &Path,
+    base_path: Option<&Path>,
+    workspace: &mut inputs::Workspace,
+) -> Result<Snap, Box<dyn Error>> {
+    let files = unpack(path, workspace)?;
+    let snap_yaml = SnapYaml::read(&files.join("meta/snap.yaml"))
+        .map_err(|err| format!("cannot read meta/snap.yaml: {}", err))?;
+    let name = snap_yaml.name.unwrap_or_else(|| path.display().to_string());
+    let base = snap_yaml.base.unwrap_or_else(|| "core".to_string());
+    if base == "bare" {
+        return Err("the snap has no base (base: bare), there is nothing to check against".into());
+    }
+    let base_files = match base_path {
+        Some(base_path) => unpack(base_path, workspace)?,
+        None => SNAP_MOUNT_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(&base).join("current"))
+            .find(|dir| dir.is_dir())
+            .map(std::fs::canonicalize)
+            .transpose()?
+            .ok_or_else(|| {
+                format!(
+                    "base {} is not installed, pass --base with its files or its snap (snap download {})",
+                    base, base
+                )
+            })?,
+    };
+    // snapcraft points LD_LIBRARY_PATH at the library directories of the
+    // snap, the base's are the system ones
+    workspace.add_package_library_paths(&files);
+    workspace.add_package_library_paths(&base_files);
+    Ok(Snap {
+        files,
+        base_files,
+        name,
+        base,
+    })
+}
+
+/// Returns the files of the snap at `path`, unpacking it into a new
+/// temporary directory unless it is a directory already.
+fn unpack(path: &Path, workspace: &mut inputs::Workspace) -> Result<PathBuf, Box<dyn Error>> {
+    if path.is_dir() {
+        return Ok(std::fs::canonicalize(path)?);
+    }
+    let mut magic = [0u8; 4];
+    std::io::Read::read_exact(&mut std::fs::File::open(path)?, &mut magic)?;
+    if magic != squashfs::SQUASHFS_MAGIC {
+        return Err(format!("{} is not a snap (squashfs) file", path.display()).into());
+    }
+    let unpacked = workspace.new_dir(path)?;
+    squashfs::unpack(path, 0, &unpacked)?;
+    Ok(unpacked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backhand::{FilesystemWriter, NodeHeader};
+
+    #[test]
+    fn keeps_the_symlinks_of_the_snap_inside_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = FilesystemWriter::default();
+        let header = NodeHeader::new(0o755, 0, 0, 0);
+        writer.push_dir("meta", header).unwrap();
+        let snap_yaml = "name: app\nbase: core22\n";
+        writer
+            .push_file(snap_yaml.as_bytes(), "meta/snap.yaml", header)
+            .unwrap();
+        writer
+            .push_symlink("/etc/shadow", "shadow", header)
+            .unwrap();
+        writer
+            .push_symlink("../../../../etc/passwd", "meta/passwd", header)
+            .unwrap();
+        let path = dir.path().join("app.snap");
+        writer.write(std::fs::File::create(&path).unwrap()).unwrap();
+        let base = dir.path().join("core22");
+        std::fs::create_dir(&base).unwrap();
+
+        let mut workspace = inputs::Workspace::default();
+        let snap = open(&path, Some(&base), &mut workspace).unwrap();
+        assert_eq!(snap.name, "app");
+        assert_eq!(snap.base_glibc(), Some("2.35"));
+        let shadow = std::fs::read_link(snap.files.join("shadow")).unwrap();
+        assert_eq!(shadow, Path::new("etc/shadow"));
+        assert!(std::fs::symlink_metadata(snap.files.join("meta/passwd")).is_err());
+    }
+}
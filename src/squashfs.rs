@@ -0,0 +1,86 @@
+use crate::{archive, image};
+use backhand::{FilesystemReader, InnerNode};
+use std::error::Error;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Magic bytes at the start of a squashfs image.
+pub const SQUASHFS_MAGIC: &[u8] = b"hsqs";
+
+/// Unpacks the squashfs image found at `offset` in the file at `path` into
+/// `dir`. Directories, regular files and symlinks are created, anything
+/// else is skipped. Symlinks are kept inside `dir`, like those of container
+/// images.
+pub fn unpack(path: &Path, offset: u64, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let filesystem = FilesystemReader::from_reader_with_offset(reader, offset)?;
+    for node in filesystem.files() {
+        let Some(target) = archive::safe_join(dir, &node.fullpath.to_string_lossy()) else {
+            continue;
+        };
+        match &node.inner {
+            InnerNode::Dir(_) => std::fs::create_dir_all(&target)?,
+            InnerNode::File(file) => {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let mut output = std::fs::File::create(&target)?;
+                std::io::copy(&mut filesystem.file(file).reader(), &mut output)?;
+            }
+            #[cfg(unix)]
+            InnerNode::Symlink(symlink) => {
+                // absolute targets are made relative to point into `dir`,
+                // relative ones climbing out of it are left out
+                let link = image::confined_link(&node.fullpath, &symlink.link);
+                if symlink.link.is_relative() && link != symlink.link {
+                    continue;
+                }
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::os::unix::fs::symlink(link, &target)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backhand::{FilesystemWriter, NodeHeader};
+
+    #[test]
+    fn keeps_symlinks_inside_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = FilesystemWriter::default();
+        let header = NodeHeader::new(0o755, 0, 0, 0);
+        writer.push_dir("usr", header).unwrap();
+        writer.push_dir("usr/lib", header).unwrap();
+        writer
+            .push_file(&b"foo"[..], "usr/lib/libfoo.so.1", header)
+            .unwrap();
+        writer
+            .push_symlink("usr/lib/libfoo.so.1", "lib", header)
+            .unwrap();
+        writer
+            .push_symlink("/usr/lib/libfoo.so.1", "libfoo.so", header)
+            .unwrap();
+        writer
+            .push_symlink("../../etc/passwd", "usr/passwd", header)
+            .unwrap();
+        let image = dir.path().join("image.squashfs");
+        writer
+            .write(std::fs::File::create(&image).unwrap())
+            .unwrap();
+
+        let unpacked = dir.path().join("unpacked");
+        unpack(&image, 0, &unpacked).unwrap();
+        let link = |name: &str| std::fs::read_link(unpacked.join(name)).ok();
+        assert_eq!(link("lib"), Some("usr/lib/libfoo.so.1".into()));
+        assert_eq!(link("libfoo.so"), Some("usr/lib/libfoo.so.1".into()));
+        assert_eq!(link("usr/passwd"), None);
+        assert_eq!(std::fs::read(unpacked.join("libfoo.so")).unwrap(), b"foo");
+    }
+}
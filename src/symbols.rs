@@ -0,0 +1,155 @@
+use elf::abi::{DT_RPATH, DT_RUNPATH, DT_SONAME, STB_WEAK, VER_FLG_BASE};
+use elf::endian::AnyEndian;
+use elf::ElfBytes;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A dynamic symbol an object expects another object to define.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Import {
+    pub name: String,
+    /// The version from the object's verneed records, if any.
+    pub version: Option<String>,
+    /// Undefined weak symbols are allowed to stay unresolved at runtime.
+    pub weak: bool,
+}
+
+/// The dynamic symbols defined by a shared object.
+#[derive(Debug, Default)]
+pub struct Exports {
+    /// (symbol, version) pairs for symbols bound to a version definition.
+    pub versioned: HashSet<(String, String)>,
+    /// Every defined symbol, whatever its version.
+    pub names: HashSet<String>,
+    /// Whether the object has version definitions at all.
+    pub has_versions: bool,
+    /// The object's `DT_SONAME`, if it has one.
+    pub soname: Option<String>,
+}
+
+impl Exports {
+    /// Whether a reference to `name` (at `version`, if given) binds to one
+    /// of these symbols.
+    pub fn provides(&self, name: &str, version: Option<&str>) -> bool {
+        match version {
+            // ld.so lets versioned references bind to objects that do not
+            // use symbol versioning at all
+            Some(version) if self.has_versions => self
+                .versioned
+                .contains(&(name.to_string(), version.to_string())),
+            _ => self.names.contains(name),
+        }
+    }
+}
+
+/// Reads the dynamic symbols defined by the ELF file at `path`. Unreadable
+/// or unparsable files define nothing.
+pub fn read_exports(path: &Path) -> Exports {
+    let mut exports = Exports::default();
+    let Ok(file_data) = std::fs::read(path) else {
+        return exports;
+    };
+    let Ok(file) = ElfBytes::<AnyEndian>::minimal_parse(file_data.as_slice()) else {
+        return exports;
+    };
+    let Ok(Some((dynsym, dynstr))) = file.dynamic_symbol_table() else {
+        return exports;
+    };
+    let versions = file.symbol_version_table().ok().flatten();
+    exports.soname = file
+        .dynamic()
+        .ok()
+        .flatten()
+        .and_then(|dynamic| dynamic.iter().find(|entry| entry.d_tag == DT_SONAME))
+        .and_then(|entry| dynstr.get(entry.d_val() as usize).ok())
+        .map(|soname| soname.to_string());
+
+    for (index, sym) in dynsym.iter().enumerate() {
+        if sym.is_undefined() {
+            continue;
+        }
+        let Ok(name) = dynstr.get(sym.st_name as usize) else {
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+        exports.names.insert(name.to_string());
+
+        let Some(versions) = versions.as_ref() else {
+            continue;
+        };
+        if let Ok(Some(mut def)) = versions.get_definition(index) {
+            exports.has_versions = true;
+            if def.flags & VER_FLG_BASE != 0 {
+                continue;
+            }
+            if let Some(Ok(version)) = def.names.next() {
+                exports
+                    .versioned
+                    .insert((name.to_string(), version.to_string()));
+            }
+        }
+    }
+    exports
+}
+
+/// Reads the undefined dynamic symbols of the ELF file at `path`.
+pub fn read_imports(path: &Path) -> Vec<Import> {
+    let Ok(file_data) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let Ok(file) = ElfBytes::<AnyEndian>::minimal_parse(file_data.as_slice()) else {
+        return Vec::new();
+    };
+    let Ok(Some((dynsym, dynstr))) = file.dynamic_symbol_table() else {
+        return Vec::new();
+    };
+    let versions = file.symbol_version_table().ok().flatten();
+
+    let mut imports = Vec::new();
+    for (index, sym) in dynsym.iter().enumerate() {
+        if !sym.is_undefined() {
+            continue;
+        }
+        let Ok(name) = dynstr.get(sym.st_name as usize) else {
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+        let version = versions
+            .as_ref()
+            .and_then(|versions| versions.get_requirement(index).ok().flatten())
+            .map(|req| req.name.to_string());
+        imports.push(Import {
+            name: name.to_string(),
+            version,
+            weak: sym.st_bind() == STB_WEAK,
+        });
+    }
+    imports
+}
+
+/// Reads the directories of the `DT_RPATH` and `DT_RUNPATH` entries of the
+/// ELF file at `path` as written, `$ORIGIN` not expanded.
+pub fn read_search_paths(path: &Path) -> Vec<String> {
+    let Ok(file_data) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let Ok(file) = ElfBytes::<AnyEndian>::minimal_parse(file_data.as_slice()) else {
+        return Vec::new();
+    };
+    let (Ok(Some(dynamic)), Ok(Some((_, dynstr)))) = (file.dynamic(), file.dynamic_symbol_table())
+    else {
+        return Vec::new();
+    };
+    dynamic
+        .iter()
+        .filter(|entry| entry.d_tag == DT_RPATH || entry.d_tag == DT_RUNPATH)
+        .filter_map(|entry| dynstr.get(entry.d_val() as usize).ok())
+        .flat_map(|paths| paths.split(':'))
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,155 @@
+use crate::image;
+use crate::inputs;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The search path used when `PATH` is not set, which is also where systemd
+/// looks for a program `ExecStart=` names without a path.
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// The directories systemd loads system units from, in order of precedence.
+const UNIT_DIRS: [&str; 5] = [
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// The ELF executables in the directories of `PATH` under `root`, reported
+/// by the path they are run by, e.g. `/usr/bin/ls`. Directories reached
+/// twice (such as `/bin` linking to `/usr/bin`) are only listed once.
+pub fn path_programs(root: &Path) -> Vec<inputs::Input> {
+    let search_path = std::env::var("PATH")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| DEFAULT_PATH.to_string());
+    let mut programs = Programs::default();
+    let mut seen_dirs = HashSet::new();
+    for dir in search_path.split(':').filter(|dir| dir.starts_with('/')) {
+        let Some(resolved) = image::resolve(root, Path::new(dir)) else {
+            continue;
+        };
+        if !resolved.is_dir() || !seen_dirs.insert(resolved.clone()) {
+            continue;
+        }
+        let mut names = std::fs::read_dir(&resolved)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            programs.add(root, &Path::new(dir).join(name));
+        }
+    }
+    programs.inputs
+}
+
+/// The ELF executables the `ExecStart=` (and `ExecStartPre=` and
+/// `ExecStartPost=`) lines of the systemd service units under `root` run,
+/// drop-ins included, reported by the path they are run by.
+pub fn systemd_programs(root: &Path) -> Vec<inputs::Input> {
+    let mut programs = Programs::default();
+    let mut seen_dirs = HashSet::new();
+    for dir in UNIT_DIRS {
+        let Some(resolved) = image::resolve(root, Path::new(dir)) else {
+            continue;
+        };
+        if !resolved.is_dir() || !seen_dirs.insert(resolved.clone()) {
+            continue;
+        }
+        for unit in unit_files(&resolved) {
+            let Ok(contents) = std::fs::read_to_string(&unit) else {
+                continue;
+            };
+            for program in exec_start_programs(&contents) {
+                let path = if program.contains('/') {
+                    PathBuf::from(program)
+                } else {
+                    match DEFAULT_PATH
+                        .split(':')
+                        .map(|dir| Path::new(dir).join(program))
+                        .find(|path| image::resolve(root, path).is_some_and(|file| file.is_file()))
+                    {
+                        Some(path) => path,
+                        None => continue,
+                    }
+                };
+                programs.add(root, &path);
+            }
+        }
+    }
+    programs.inputs
+}
+
+/// The programs found so far, each file once.
+#[derive(Default)]
+struct Programs {
+    inputs: Vec<inputs::Input>,
+    files: HashSet<PathBuf>,
+}
+
+impl Programs {
+    /// Adds the program run as `path` if it is an ELF file under `root`.
+    fn add(&mut self, root: &Path, path: &Path) {
+        let Some(file) = image::resolve(root, path) else {
+            return;
+        };
+        if !file.is_file() || !inputs::is_elf_file(&file) || !self.files.insert(file.clone()) {
+            return;
+        }
+        self.inputs.push(inputs::Input {
+            path: file.display().to_string(),
+            relative: Some(path.display().to_string()),
+            archive: None,
+        });
+    }
+}
+
+/// The service units and their drop-ins (`<unit>.service.d/*.conf`) in
+/// `dir`, sorted by path.
+fn unit_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // symlinked units are aliases of units listed elsewhere, or masked
+        let is_file = entry.file_type().is_ok_and(|file_type| file_type.is_file());
+        if name.ends_with(".service") && is_file {
+            files.push(path);
+        } else if name.ends_with(".service.d") && path.is_dir() {
+            files.extend(
+                std::fs::read_dir(&path)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "conf")),
+            );
+        }
+    }
+    files.sort();
+    files
+}
+
+/// The programs of the `ExecStart=`, `ExecStartPre=` and `ExecStartPost=`
+/// lines of a unit file, without their prefixes (`-`, `@`, `+`, ...).
+fn exec_start_programs(contents: &str) -> Vec<&str> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            if !matches!(key.trim(), "ExecStart" | "ExecStartPre" | "ExecStartPost") {
+                return None;
+            }
+            let value = value
+                .trim_start()
+                .trim_start_matches(['@', '-', ':', '+', '!']);
+            let program = value.split_whitespace().next()?;
+            let program = program.trim_matches(|c| c == '"' || c == '\'');
+            (!program.is_empty()).then_some(program)
+        })
+        .collect()
+}
@@ -0,0 +1,76 @@
+/// The narrowest a column is truncated to.
+const MIN_WIDTH: usize = 8;
+
+/// Formats rows as a table with aligned columns, separated by two spaces,
+/// under a header and a line of dashes. When the table is wider than
+/// `width`, the widest columns are truncated, their cells ending with `…`.
+pub fn render<S: AsRef<str>>(header: &[&str], rows: &[Vec<S>], width: Option<usize>) -> String {
+    let length = |text: &str| text.chars().count();
+    let mut widths = header
+        .iter()
+        .map(|name| length(name))
+        .collect::<Vec<usize>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(length(cell.as_ref()));
+        }
+    }
+    if let Some(width) = width {
+        let separators = 2 * widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + separators > width {
+            let Some(widest) = widths
+                .iter_mut()
+                .filter(|width| **width > MIN_WIDTH)
+                .max_by_key(|width| **width)
+            else {
+                break;
+            };
+            *widest -= 1;
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        let cells = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| {
+                if length(cell) > *width {
+                    let kept = cell.chars().take(width - 1).collect::<String>();
+                    format!("{}…", kept)
+                } else {
+                    format!("{:width$}", cell, width = width)
+                }
+            })
+            .collect::<Vec<String>>();
+        format!("{}\n", cells.join("  ").trim_end())
+    };
+    let mut table = format_row(header.to_vec());
+    let dashes = widths
+        .iter()
+        .map(|width| "-".repeat(*width))
+        .collect::<Vec<String>>();
+    table.push_str(&format_row(dashes.iter().map(String::as_str).collect()));
+    for row in rows {
+        table.push_str(&format_row(row.iter().map(AsRef::as_ref).collect()));
+    }
+    table
+}
+
+/// The width of the terminal stdout is, `COLUMNS` if set, or `None` when
+/// stdout is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+    {
+        return Some(columns);
+    }
+    #[cfg(unix)]
+    {
+        let size = rustix::termios::tcgetwinsize(std::io::stdout()).ok()?;
+        (size.ws_col > 0).then_some(usize::from(size.ws_col))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
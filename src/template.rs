@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+/// The fields a `--format` template can use.
+pub const FIELDS: [&str; 7] = [
+    "namespace",
+    "version",
+    "function",
+    "file",
+    "referenced_by",
+    "provided_by",
+    "weak",
+];
+
+/// A `--format` template, e.g. `{version}\t{function}\t{file}`: text with
+/// `{field}` placeholders, `{{` and `}}` for literal braces, and `\t`, `\n`
+/// and `\\` escapes.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+impl Template {
+    /// Parses a template, failing on an unknown field or an unclosed `{`.
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(format!("unclosed {{{} in the template", field)),
+                        }
+                    }
+                    if !FIELDS.contains(&field.as_str()) {
+                        return Err(format!(
+                            "unknown field {{{}}}, the fields are {}",
+                            field,
+                            FIELDS.map(|field| format!("{{{}}}", field)).join(", ")
+                        ));
+                    }
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                    parts.push(Part::Field(field));
+                }
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => text.push('\\'),
+                },
+                c => text.push(c),
+            }
+        }
+        parts.push(Part::Text(text));
+        Ok(Template { parts })
+    }
+
+    /// Returns the template with its placeholders replaced by `fields`,
+    /// those missing from it by nothing.
+    pub fn render(&self, fields: &HashMap<&str, String>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.as_str(),
+                Part::Field(field) => fields.get(field.as_str()).map_or("", String::as_str),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+/// The rules lddcheck fails on, with what they check.
+pub const RULES: [(&str, &str); 10] = [
+    ("max-glibc", "Requires a newer glibc than --max-glibc"),
+    (
+        "target-distro",
+        "Requires a newer glibc or libstdc++ than the --target-distro release ships",
+    ),
+    (
+        "policy",
+        "Requires a newer symbol version than the --policy allows",
+    ),
+    (
+        "policy-library",
+        "Loads a library the --policy does not allow",
+    ),
+    (
+        "unresolved-symbol",
+        "Imports a symbol that no library in its dependency closure defines",
+    ),
+    (
+        "glibc-private",
+        "Imports GLIBC_PRIVATE symbols from outside of glibc",
+    ),
+    ("unreadable-input", "The input cannot be read or parsed"),
+    (
+        "policy-file-version",
+        "Requires a newer symbol version than the policy file allows",
+    ),
+    (
+        "policy-file-library",
+        "Loads a library the policy file does not allow",
+    ),
+    (
+        "policy-file-rpath",
+        "Has an RPATH or RUNPATH the policy file does not allow",
+    ),
+];
+
+/// A reason for lddcheck to fail, as reported by the formats meant for CI
+/// systems.
+#[derive(Debug)]
+pub struct Violation {
+    /// The rule from `RULES`.
+    pub rule: &'static str,
+    pub message: String,
+    /// The file at fault, if there is one.
+    pub file: Option<PathBuf>,
+}
@@ -0,0 +1,106 @@
+use serde_json::Value;
+
+/// Formats a JSON value as a YAML document with the same structure: objects
+/// become block mappings, arrays block sequences, and strings are quoted
+/// whenever a YAML parser would read them as anything but that string.
+pub fn to_string(value: &Value) -> String {
+    let mut output = String::new();
+    match value {
+        Value::Object(map) if !map.is_empty() => write_mapping(&mut output, value, 0, false),
+        Value::Array(items) if !items.is_empty() => write_sequence(&mut output, value, 0),
+        _ => {
+            output.push_str(&scalar(value));
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Writes a mapping, its first key on the current line when it is an item of
+/// a sequence (`- key: value`).
+fn write_mapping(output: &mut String, value: &Value, indent: usize, in_sequence: bool) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    for (index, (key, value)) in map.iter().enumerate() {
+        if index > 0 || !in_sequence {
+            output.push_str(&" ".repeat(indent));
+        }
+        output.push_str(&string(key));
+        output.push(':');
+        write_nested(output, value, indent);
+    }
+}
+
+fn write_sequence(output: &mut String, value: &Value, indent: usize) {
+    let Value::Array(items) = value else {
+        return;
+    };
+    for item in items {
+        output.push_str(&" ".repeat(indent));
+        match item {
+            Value::Object(map) if !map.is_empty() => {
+                output.push_str("- ");
+                write_mapping(output, item, indent + 2, true);
+            }
+            _ => {
+                output.push('-');
+                write_nested(output, item, indent);
+            }
+        }
+    }
+}
+
+/// Writes the value of a mapping entry or a sequence item, on the same line
+/// for scalars and empty collections, indented on the next lines otherwise.
+fn write_nested(output: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            output.push('\n');
+            write_mapping(output, value, indent + 2, false);
+        }
+        Value::Array(items) if !items.is_empty() => {
+            output.push('\n');
+            write_sequence(output, value, indent + 2);
+        }
+        _ => {
+            output.push(' ');
+            output.push_str(&scalar(value));
+            output.push('\n');
+        }
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(value) => string(value),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+/// Returns `value` as a plain scalar when that is unambiguous, e.g.
+/// `GLIBC_2.17` or `/usr/lib/libc.so.6`, double-quoted otherwise, e.g.
+/// `"2.17"`, which would be read as a number, or `"true"`.
+fn string(value: &str) -> String {
+    let is_plain = value
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '/')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '/' | '-' | '+' | '@'))
+        && !matches!(
+            value.to_ascii_lowercase().as_str(),
+            "true" | "false" | "null" | "yes" | "no" | "on" | "off" | "y" | "n"
+        );
+    if is_plain {
+        value.to_string()
+    } else {
+        // a JSON string is a valid YAML double-quoted scalar
+        Value::String(value.to_string()).to_string()
+    }
+}
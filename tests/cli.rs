@@ -0,0 +1,153 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs lddcheck with `args`.
+fn lddcheck(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lddcheck"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Compiles a program that only calls into the C library to `output`.
+fn compile_program(output: &Path) {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("main.c");
+    std::fs::write(
+        &source,
+        "#include <stdio.h>\nint main(void) { return puts(\"\"); }\n",
+    )
+    .unwrap();
+    let status = Command::new("cc")
+        .arg(&source)
+        .arg("-o")
+        .arg(output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn reports_truncated_files_and_goes_on() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("program");
+    compile_program(&program);
+    let truncated = std::fs::read(&program).unwrap()[..100].to_vec();
+    std::fs::write(dir.path().join("truncated"), truncated).unwrap();
+
+    let output = lddcheck(&["-p", dir.path().to_str().unwrap(), "--stdout", "json"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let version = &report["files"]["program"]["GLIBC"];
+    assert!(version
+        .as_str()
+        .is_some_and(|version| version.starts_with("2.")));
+    assert_eq!(report["errors"][0]["kind"], "cannot_parse");
+    assert!(report["errors"][0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("truncated"));
+}
+
+#[test]
+fn reports_archives_that_cannot_be_unpacked() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("broken.zip");
+    std::fs::write(&archive, "not a zip archive").unwrap();
+
+    let output = lddcheck(&["-p", archive.to_str().unwrap(), "--stdout", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["errors"][0]["kind"], "cannot_unpack");
+}
+
+#[test]
+fn summarizes_a_root_filesystem_with_a_corrupt_file() {
+    let root = tempfile::tempdir().unwrap();
+    let bin = root.path().join("usr/bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    compile_program(&bin.join("program"));
+    let truncated = std::fs::read(bin.join("program")).unwrap()[..200].to_vec();
+    std::fs::write(bin.join("corrupt"), truncated).unwrap();
+
+    let output = lddcheck(&["rootfs", root.path().to_str().unwrap(), "--stdout", "json"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let summary = &report["glibc_summary"]["files"];
+    assert!(summary["usr/bin/program"]
+        .as_str()
+        .is_some_and(|version| version.starts_with("GLIBC_2.")));
+    // the loader is not part of the fixture, which is reported too
+    let corrupt = report["errors"].as_array().unwrap().iter().find(|error| {
+        error["path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("usr/bin/corrupt"))
+    });
+    assert_eq!(corrupt.unwrap()["kind"], "cannot_parse");
+}
+
+#[test]
+fn reports_the_imports_of_every_copy_of_a_program() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("program");
+    compile_program(&program);
+    std::fs::copy(&program, dir.path().join("copy")).unwrap();
+
+    let output = lddcheck(&[
+        "-p",
+        program.to_str().unwrap(),
+        "-p",
+        dir.path().join("copy").to_str().unwrap(),
+        "--detail-level",
+        "file",
+        "--versions",
+        "100",
+        "--stdout",
+        "json",
+    ]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let reports = report.as_object().unwrap();
+    assert_eq!(reports.len(), 2);
+    for (input, versions) in reports {
+        // the copy has the build-id of the program, but imports puts all the
+        // same
+        let puts = versions
+            .as_object()
+            .unwrap()
+            .values()
+            .find_map(|functions| functions.get("puts"))
+            .unwrap_or_else(|| panic!("{} does not import puts", input));
+        assert!(puts["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|file| file == input));
+    }
+}
+
+#[test]
+fn reports_unreadable_symbol_names_as_parse_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("program");
+    compile_program(&program);
+    // point the name of the first dynamic symbol past the string table
+    let mut data = std::fs::read(&program).unwrap();
+    let file = elf::ElfBytes::<elf::endian::AnyEndian>::minimal_parse(&data).unwrap();
+    let dynsym = file.section_header_by_name(".dynsym").unwrap().unwrap();
+    let st_name = (dynsym.sh_offset + dynsym.sh_entsize) as usize;
+    data[st_name..st_name + 4].copy_from_slice(&0x7fff_ffffu32.to_le_bytes());
+    std::fs::write(&program, data).unwrap();
+
+    let output = lddcheck(&[
+        "-p",
+        program.to_str().unwrap(),
+        "--stdout",
+        "json",
+        "--print-error",
+        "cannot-parse",
+    ]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["errors"][0]["kind"], "cannot_parse");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot_parse"));
+}